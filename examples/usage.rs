@@ -26,18 +26,21 @@ fn main() {
 			is_dir: false,
 			size: Some(100),
 			modified: None,
+			..Default::default()
 		},
 		listing::Entry {
 			name: "src".to_owned(),
 			is_dir: true,
 			size: None,
 			modified: None,
+			..Default::default()
 		},
 		listing::Entry {
 			name: "Cargo.toml".to_owned(),
 			is_dir: false,
 			size: Some(500),
 			modified: None,
+			..Default::default()
 		},
 	];
 	listing::sort(&mut entries);
@@ -0,0 +1,614 @@
+/* src/archive.rs */
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::listing::{self, Entry};
+use crate::range::ByteRange;
+use crate::time::DateTime;
+use crate::{mime, path};
+
+/// The container format of an [`ArchiveRoot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// A ZIP archive.
+	Zip,
+	/// An uncompressed tar archive.
+	Tar,
+}
+
+/// Default cap on the number of bytes [`ArchiveRoot::read`] loads into
+/// memory for one entry.
+pub const DEFAULT_MAX_READ: u64 = 64 * 1024 * 1024;
+
+/// A file or directory indexed from an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+	/// Normalized path inside the archive.
+	pub path: String,
+	/// Whether this entry is a directory.
+	pub is_dir: bool,
+	/// Uncompressed size in bytes.
+	pub size: u64,
+	/// Last modification time recorded in the archive.
+	pub modified: Option<SystemTime>,
+	/// Absolute offset of the stored bytes, or `None` when the entry is
+	/// compressed and cannot be read at arbitrary offsets.
+	data_offset: Option<u64>,
+	/// Position in the ZIP central directory, for decompressing reads.
+	zip_index: usize,
+	/// The CRC-32 of a ZIP entry, or a tar header checksum mixed with the
+	/// header offset, so entries without a timestamp still get distinct
+	/// ETags.
+	checksum: u64,
+}
+
+impl ArchiveEntry {
+	/// Whether the entry is stored uncompressed and so supports byte ranges.
+	#[must_use]
+	pub fn is_stored(&self) -> bool {
+		self.data_offset.is_some()
+	}
+
+	/// Detects the MIME type with [`mime::detect`] from the entry name and
+	/// `content`, which may be empty to go by the name alone.
+	#[must_use]
+	pub fn mime(&self, content: &[u8]) -> mime::MediaType {
+		mime::detect(&self.path, content)
+	}
+
+	/// Generates a weak ETag from the archive metadata: the modification
+	/// time and size, as in [`mime::etag`], and the entry's checksum, since
+	/// ZIP entries often carry no timestamp.
+	#[must_use]
+	pub fn etag(&self) -> String {
+		let nanos = self
+			.modified
+			.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+			.unwrap_or_default()
+			.as_nanos();
+		format!("W/\"{nanos:x}-{:x}-{:x}\"", self.size, self.checksum)
+	}
+
+	/// Builds the listing entry for this archive entry.
+	///
+	/// Files get a MIME type guessed from the name alone, since reading
+	/// the contents would mean decompressing them, and an ETag. Archives
+	/// are served read-only, so every entry is marked as such.
+	#[must_use]
+	pub fn entry(&self) -> Entry {
+		let name = self.path.rsplit('/').next().unwrap_or_default();
+		let entry = if self.is_dir {
+			Entry::dir(name)
+		} else {
+			Entry::file(name, self.size)
+				.with_mime(self.mime(&[]))
+				.with_etag(self.etag())
+		};
+		entry.with_modified(self.modified).with_readonly(true)
+	}
+}
+
+/// A ZIP or tar file served as a read-only root.
+///
+/// The archive is indexed once on [`ArchiveRoot::open`], and for ZIP files
+/// the parsed central directory is kept for later reads. URIs are resolved
+/// with [`path::normalize`], so traversal is clamped exactly as for
+/// directories on disk, and entry names containing `..` are dropped while
+/// indexing.
+///
+/// ```no_run
+/// use serve_static::archive::ArchiveRoot;
+///
+/// let docs = ArchiveRoot::open("docs.zip").unwrap();
+/// if let Some(entry) = docs.get("/guide/index.html").unwrap() {
+///     let body = docs.read(entry).unwrap();
+///     println!("{} bytes, ETag {}", body.len(), entry.etag());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ArchiveRoot {
+	path: PathBuf,
+	format: Format,
+	entries: BTreeMap<String, ArchiveEntry>,
+	/// The parsed ZIP archive, reused to decompress entries.
+	zip: Option<Mutex<zip::ZipArchive<File>>>,
+	max_read: u64,
+}
+
+impl ArchiveRoot {
+	/// Opens and indexes an archive.
+	///
+	/// The format is taken from the `.zip` or `.tar` extension, falling back
+	/// to the ZIP signature for other names.
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let path = path.as_ref();
+		let invalid = |source| Error::InvalidArchive {
+			path: path.to_path_buf(),
+			source,
+		};
+
+		let mut file = File::open(path).map_err(invalid)?;
+		let format = detect_format(path, &mut file).map_err(invalid)?;
+		let (entries, zip) = match format {
+			Format::Zip => {
+				let mut archive = zip::ZipArchive::new(file)
+					.map_err(io::Error::other)
+					.map_err(invalid)?;
+				let entries = index_zip(&mut archive).map_err(invalid)?;
+				(entries, Some(Mutex::new(archive)))
+			}
+			Format::Tar => (index_tar(file).map_err(invalid)?, None),
+		};
+
+		Ok(Self {
+			path: path.to_path_buf(),
+			format,
+			entries,
+			zip,
+			max_read: DEFAULT_MAX_READ,
+		})
+	}
+
+	/// Caps the size of an entry [`ArchiveRoot::read`] loads into memory.
+	/// Defaults to [`DEFAULT_MAX_READ`].
+	#[must_use]
+	pub fn max_read(mut self, max: u64) -> Self {
+		self.max_read = max;
+		self
+	}
+
+	/// The archive file on disk.
+	#[must_use]
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// The container format.
+	#[must_use]
+	pub fn format(&self) -> Format {
+		self.format
+	}
+
+	/// All indexed entries, ordered by path.
+	pub fn entries(&self) -> impl Iterator<Item = &ArchiveEntry> {
+		self.entries.values()
+	}
+
+	/// Normalizes `uri` and looks up the file it names.
+	///
+	/// Directories are not returned; use [`ArchiveRoot::list`] for them.
+	pub fn get(&self, uri: &str) -> Result<Option<&ArchiveEntry>, Error> {
+		let key = path::normalize(uri)?;
+		Ok(self.entries.get(&key).filter(|entry| !entry.is_dir))
+	}
+
+	/// Lists the directory named by `uri`, ordered with [`listing::sort`].
+	///
+	/// Directories without an explicit archive entry are inferred from the
+	/// paths below them. Returns `Ok(None)` when the directory does not exist.
+	pub fn list(&self, uri: &str) -> Result<Option<Vec<Entry>>, Error> {
+		let dir = path::normalize(uri)?;
+		let explicit = self.entries.get(&dir).is_some_and(|entry| entry.is_dir);
+		let entries = listing::children(&self.entries, &dir, |_, entry| entry.entry());
+		if dir.is_empty() || explicit || !entries.is_empty() {
+			Ok(Some(entries))
+		} else {
+			Ok(None)
+		}
+	}
+
+	/// Reads the full, decompressed contents of an entry.
+	///
+	/// Entries larger than [`ArchiveRoot::max_read`] yield
+	/// [`io::ErrorKind::FileTooLarge`], and decompression stops at the size
+	/// recorded in the archive, so a ZIP bomb cannot expand beyond it.
+	pub fn read(&self, entry: &ArchiveEntry) -> io::Result<Vec<u8>> {
+		if entry.size > self.max_read {
+			return Err(io::Error::new(
+				io::ErrorKind::FileTooLarge,
+				"archive entry exceeds the read limit",
+			));
+		}
+		if entry.is_stored() {
+			return self.read_range(
+				entry,
+				ByteRange {
+					start: 0,
+					length: entry.size,
+				},
+			);
+		}
+		let zip = self.zip.as_ref().ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::Unsupported,
+				"compressed entry outside a ZIP archive",
+			)
+		})?;
+		let mut archive = zip
+			.lock()
+			.map_err(|_| io::Error::other("archive lock poisoned"))?;
+		let file = archive
+			.by_index(entry.zip_index)
+			.map_err(io::Error::other)?;
+		let mut data = Vec::with_capacity(usize::try_from(entry.size).unwrap_or_default());
+		file.take(entry.size).read_to_end(&mut data)?;
+		Ok(data)
+	}
+
+	/// Reads a byte range of a stored entry, as produced by
+	/// [`crate::range::parse`] with [`ArchiveEntry::size`].
+	///
+	/// Compressed entries yield [`io::ErrorKind::Unsupported`]; serve them
+	/// in full with [`ArchiveRoot::read`] instead.
+	pub fn read_range(&self, entry: &ArchiveEntry, range: ByteRange) -> io::Result<Vec<u8>> {
+		let offset = entry.data_offset.ok_or_else(|| {
+			io::Error::new(
+				io::ErrorKind::Unsupported,
+				"byte ranges need an uncompressed entry",
+			)
+		})?;
+		if range.start.saturating_add(range.length) > entry.size {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"range exceeds entry size",
+			));
+		}
+		let mut file = File::open(&self.path)?;
+		file.seek(SeekFrom::Start(offset + range.start))?;
+		let mut data = Vec::with_capacity(usize::try_from(range.length).unwrap_or_default());
+		file.take(range.length).read_to_end(&mut data)?;
+		Ok(data)
+	}
+}
+
+fn detect_format(path: &Path, file: &mut File) -> io::Result<Format> {
+	match path.extension().and_then(|ext| ext.to_str()) {
+		Some(ext) if ext.eq_ignore_ascii_case("zip") => return Ok(Format::Zip),
+		Some(ext) if ext.eq_ignore_ascii_case("tar") => return Ok(Format::Tar),
+		_ => {}
+	}
+	let mut magic = [0u8; 4];
+	let read = file.read(&mut magic)?;
+	file.rewind()?;
+	if read == 4 && magic == *b"PK\x03\x04" {
+		Ok(Format::Zip)
+	} else {
+		Ok(Format::Tar)
+	}
+}
+
+/// Normalizes an entry name, rejecting any name that contains `..`.
+fn entry_key(name: &str) -> Option<String> {
+	let mut segments = Vec::new();
+	for segment in name.split('/') {
+		match segment {
+			"" | "." => {}
+			".." => return None,
+			_ => segments.push(segment),
+		}
+	}
+	(!segments.is_empty()).then(|| segments.join("/"))
+}
+
+fn index_zip(archive: &mut zip::ZipArchive<File>) -> io::Result<BTreeMap<String, ArchiveEntry>> {
+	let mut entries = BTreeMap::new();
+	for zip_index in 0..archive.len() {
+		let file = archive.by_index_raw(zip_index).map_err(io::Error::other)?;
+		if !(file.is_dir() || file.is_file()) {
+			continue;
+		}
+		let Some(key) = entry_key(file.name()) else {
+			continue;
+		};
+		let stored = file.compression() == zip::CompressionMethod::Stored;
+		let entry = ArchiveEntry {
+			path: key.clone(),
+			is_dir: file.is_dir(),
+			size: file.size(),
+			modified: file.last_modified().and_then(|t| {
+				DateTime {
+					year: i64::from(t.year()),
+					month: u32::from(t.month()),
+					day: u32::from(t.day()),
+					hour: u32::from(t.hour()),
+					minute: u32::from(t.minute()),
+					second: u32::from(t.second()),
+				}
+				.to_system_time()
+			}),
+			data_offset: file.data_start().filter(|_| stored),
+			zip_index,
+			checksum: u64::from(file.crc32()),
+		};
+		entries.insert(key, entry);
+	}
+	Ok(entries)
+}
+
+fn index_tar(file: File) -> io::Result<BTreeMap<String, ArchiveEntry>> {
+	let mut archive = tar::Archive::new(file);
+	let mut entries = BTreeMap::new();
+	for entry in archive.entries_with_seek()? {
+		let entry = entry?;
+		let kind = entry.header().entry_type();
+		let is_dir = kind.is_dir();
+		if !(is_dir || kind.is_file()) {
+			continue;
+		}
+		let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+		let Some(key) = entry_key(&name) else {
+			continue;
+		};
+		let indexed = ArchiveEntry {
+			path: key.clone(),
+			is_dir,
+			size: if is_dir { 0 } else { entry.size() },
+			modified: entry
+				.header()
+				.mtime()
+				.ok()
+				.and_then(|secs| UNIX_EPOCH.checked_add(Duration::from_secs(secs))),
+			data_offset: Some(entry.raw_file_position()),
+			zip_index: 0,
+			checksum: u64::from(entry.header().cksum()?) ^ entry.raw_header_position().rotate_left(32),
+		};
+		entries.insert(key, indexed);
+	}
+	Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	const BODY: &[u8] = b"0123456789abcdefghij";
+
+	fn make_zip(dir: &Path) -> PathBuf {
+		let path = dir.join("docs.zip");
+		let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+		let stored = zip::write::SimpleFileOptions::default()
+			.compression_method(zip::CompressionMethod::Stored)
+			.last_modified_time(zip::DateTime::from_date_and_time(2024, 3, 15, 12, 30, 10).unwrap());
+		let deflated =
+			zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+		zip.add_directory("guide/", stored).unwrap();
+		zip.start_file("guide/index.html", stored).unwrap();
+		zip.write_all(b"<html></html>").unwrap();
+		zip.start_file("data.bin", stored).unwrap();
+		zip.write_all(BODY).unwrap();
+		zip.start_file("api/big.txt", deflated).unwrap();
+		zip.write_all(&BODY.repeat(10)).unwrap();
+		zip.start_file("../evil.txt", stored).unwrap();
+		zip.write_all(b"evil").unwrap();
+		zip.finish().unwrap();
+		path
+	}
+
+	fn make_tar(dir: &Path) -> PathBuf {
+		let path = dir.join("docs.tar");
+		let mut tar = tar::Builder::new(File::create(&path).unwrap());
+		for (name, data) in [
+			("guide/index.html", &b"<html></html>"[..]),
+			("data.bin", BODY),
+		] {
+			let mut header = tar::Header::new_gnu();
+			header.set_size(data.len() as u64);
+			header.set_mtime(1_700_000_000);
+			header.set_mode(0o644);
+			header.set_cksum();
+			tar.append_data(&mut header, name, data).unwrap();
+		}
+		tar.finish().unwrap();
+		path
+	}
+
+	#[test]
+	fn zip_get_and_read() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		assert_eq!(root.format(), Format::Zip);
+
+		let entry = root.get("/guide/../guide/index.html").unwrap().unwrap();
+		assert_eq!(root.read(entry).unwrap(), b"<html></html>");
+		assert!(entry.is_stored());
+	}
+
+	#[test]
+	fn zip_deflated_read_but_no_ranges() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		let entry = root.get("/api/big.txt").unwrap().unwrap();
+		assert!(!entry.is_stored());
+		assert_eq!(entry.size, 200);
+		assert_eq!(root.read(entry).unwrap(), BODY.repeat(10));
+
+		let range = ByteRange {
+			start: 0,
+			length: 1,
+		};
+		let err = root.read_range(entry, range).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+	}
+
+	#[test]
+	fn zip_reads_reuse_the_index() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = make_zip(dir.path());
+		let root = ArchiveRoot::open(&path).unwrap();
+		let entry = root.get("/api/big.txt").unwrap().unwrap().clone();
+		// The central directory is not parsed again, so reads keep working
+		// from the open handle even after the file is unlinked.
+		#[cfg(unix)]
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(root.read(&entry).unwrap(), BODY.repeat(10));
+		assert_eq!(root.read(&entry).unwrap(), BODY.repeat(10));
+	}
+
+	#[test]
+	fn read_limit() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path()))
+			.unwrap()
+			.max_read(100);
+		let big = root.get("/api/big.txt").unwrap().unwrap();
+		let err = root.read(big).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+		let small = root.get("/data.bin").unwrap().unwrap();
+		assert_eq!(root.read(small).unwrap(), BODY);
+	}
+
+	#[test]
+	fn zip_range_on_stored_entry() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		let entry = root.get("/data.bin").unwrap().unwrap();
+		let range = crate::range::parse("bytes=10-14", entry.size).unwrap();
+		assert_eq!(root.read_range(entry, range).unwrap(), b"abcde");
+		let suffix = crate::range::parse("bytes=-3", entry.size).unwrap();
+		assert_eq!(root.read_range(entry, suffix).unwrap(), b"hij");
+	}
+
+	#[test]
+	fn zip_slip_entries_dropped() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		assert!(root.entries().all(|e| !e.path.contains("evil")));
+		assert!(root.get("/../evil.txt").unwrap().is_none());
+	}
+
+	#[test]
+	fn zip_metadata_and_etag() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		let entry = root.get("/data.bin").unwrap().unwrap();
+		// 2024-03-15T12:30:10Z
+		let expected = UNIX_EPOCH + Duration::from_secs(1_710_505_810);
+		assert_eq!(entry.modified, Some(expected));
+		let tag = mime::etag(expected, 20);
+		assert!(entry.etag().starts_with(tag.trim_end_matches('"')));
+		assert_ne!(entry.etag(), tag);
+	}
+
+	#[test]
+	fn etags_differ_without_timestamps() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("bare.zip");
+		let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+		let options = zip::write::SimpleFileOptions::default()
+			.compression_method(zip::CompressionMethod::Stored)
+			.last_modified_time(zip::DateTime::default());
+		for (name, body) in [("a.txt", b"aaaa"), ("b.txt", b"bbbb")] {
+			zip.start_file(name, options).unwrap();
+			zip.write_all(body).unwrap();
+		}
+		zip.finish().unwrap();
+
+		let path = dir.path().join("bare.tar");
+		let mut tar = tar::Builder::new(File::create(&path).unwrap());
+		for (name, body) in [("a.txt", b"aaaa"), ("b.txt", b"bbbb")] {
+			let mut header = tar::Header::new_gnu();
+			header.set_size(4);
+			header.set_cksum();
+			tar.append_data(&mut header, name, &body[..]).unwrap();
+		}
+		tar.finish().unwrap();
+
+		for archive in ["bare.zip", "bare.tar"] {
+			let root = ArchiveRoot::open(dir.path().join(archive)).unwrap();
+			let a = root.get("/a.txt").unwrap().unwrap();
+			let b = root.get("/b.txt").unwrap().unwrap();
+			assert_eq!((a.modified, a.size), (b.modified, b.size), "{archive}");
+			assert_ne!(a.etag(), b.etag(), "{archive}");
+		}
+	}
+
+	#[test]
+	fn zip_listing() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_zip(dir.path())).unwrap();
+		let names: Vec<_> = root
+			.list("/")
+			.unwrap()
+			.unwrap()
+			.into_iter()
+			.map(|e| (e.name, e.is_dir))
+			.collect();
+		assert_eq!(
+			names,
+			[
+				("api".to_owned(), true),
+				("guide".to_owned(), true),
+				("data.bin".to_owned(), false),
+			]
+		);
+		assert_eq!(root.list("/guide/").unwrap().unwrap().len(), 1);
+		assert!(root.list("/missing").unwrap().is_none());
+		assert!(root.get("/guide").unwrap().is_none());
+	}
+
+	#[test]
+	fn tar_get_range_and_listing() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_tar(dir.path())).unwrap();
+		assert_eq!(root.format(), Format::Tar);
+
+		let entry = root.get("/data.bin").unwrap().unwrap();
+		assert!(entry.is_stored());
+		assert_eq!(root.read(entry).unwrap(), BODY);
+		let range = crate::range::parse("bytes=5-", entry.size).unwrap();
+		assert_eq!(root.read_range(entry, range).unwrap(), &BODY[5..]);
+		assert_eq!(
+			entry.modified,
+			Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+		);
+
+		let listing = root.list("").unwrap().unwrap();
+		assert_eq!(listing[0].name, "guide");
+		assert!(listing[0].is_dir);
+	}
+
+	#[test]
+	fn range_beyond_entry_rejected() {
+		let dir = tempfile::tempdir().unwrap();
+		let root = ArchiveRoot::open(make_tar(dir.path())).unwrap();
+		let entry = root.get("/data.bin").unwrap().unwrap();
+		let range = ByteRange {
+			start: 15,
+			length: 10,
+		};
+		let err = root.read_range(entry, range).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+	}
+
+	#[test]
+	fn format_sniffed_without_extension() {
+		let dir = tempfile::tempdir().unwrap();
+		let zip_path = make_zip(dir.path());
+		let renamed = dir.path().join("bundle.bin");
+		std::fs::rename(zip_path, &renamed).unwrap();
+		assert_eq!(ArchiveRoot::open(renamed).unwrap().format(), Format::Zip);
+	}
+
+	#[test]
+	fn invalid_archive() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("broken.zip");
+		std::fs::write(&path, b"not a zip").unwrap();
+		assert!(matches!(
+			ArchiveRoot::open(&path),
+			Err(Error::InvalidArchive { .. })
+		));
+		assert!(matches!(
+			ArchiveRoot::open(dir.path().join("missing.tar")),
+			Err(Error::InvalidArchive { .. })
+		));
+	}
+}
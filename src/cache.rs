@@ -0,0 +1,504 @@
+/* src/cache.rs */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::glob;
+use crate::mime::MediaType;
+
+/// One year, the conventional lifetime for fingerprinted assets.
+const YEAR: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// A set of `Cache-Control` response directives.
+///
+/// ```
+/// use std::time::Duration;
+/// use serve_static::cache::CacheControl;
+///
+/// let cc = CacheControl::new()
+///     .public()
+///     .max_age(Duration::from_secs(600))
+///     .stale_while_revalidate(Duration::from_secs(60));
+/// assert_eq!(cc.to_string(), "public, max-age=600, stale-while-revalidate=60");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CacheControl {
+	public: bool,
+	no_store: bool,
+	no_cache: bool,
+	max_age: Option<Duration>,
+	s_maxage: Option<Duration>,
+	stale_while_revalidate: Option<Duration>,
+	immutable: bool,
+}
+
+impl CacheControl {
+	/// Creates an empty set of directives.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// `no-store`: never cache, for responses that must always be fetched.
+	#[must_use]
+	pub fn no_store() -> Self {
+		Self {
+			no_store: true,
+			..Self::default()
+		}
+	}
+
+	/// `no-cache`: cache, but revalidate before every use.
+	#[must_use]
+	pub fn no_cache() -> Self {
+		Self {
+			no_cache: true,
+			..Self::default()
+		}
+	}
+
+	/// Adds `public`, allowing shared caches to store the response.
+	#[must_use]
+	pub fn public(mut self) -> Self {
+		self.public = true;
+		self
+	}
+
+	/// Sets `max-age`, in whole seconds.
+	#[must_use]
+	pub fn max_age(mut self, age: Duration) -> Self {
+		self.max_age = Some(age);
+		self
+	}
+
+	/// Sets `s-maxage`, the lifetime in shared caches, in whole seconds.
+	#[must_use]
+	pub fn s_maxage(mut self, age: Duration) -> Self {
+		self.s_maxage = Some(age);
+		self
+	}
+
+	/// Sets `stale-while-revalidate`, in whole seconds.
+	#[must_use]
+	pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+		self.stale_while_revalidate = Some(window);
+		self
+	}
+
+	/// Adds `immutable`: the response will never change while fresh.
+	#[must_use]
+	pub fn immutable(mut self) -> Self {
+		self.immutable = true;
+		self
+	}
+
+	/// Whether no directive is set, in which case no header should be sent.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+impl fmt::Display for CacheControl {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut directives = Vec::new();
+		if self.public {
+			directives.push("public".to_owned());
+		}
+		if self.no_store {
+			directives.push("no-store".to_owned());
+		}
+		if self.no_cache {
+			directives.push("no-cache".to_owned());
+		}
+		let seconds = [
+			("max-age", self.max_age),
+			("s-maxage", self.s_maxage),
+			("stale-while-revalidate", self.stale_while_revalidate),
+		];
+		for (name, value) in seconds {
+			if let Some(value) = value {
+				directives.push(format!("{name}={}", value.as_secs()));
+			}
+		}
+		if self.immutable {
+			directives.push("immutable".to_owned());
+		}
+		f.write_str(&directives.join(", "))
+	}
+}
+
+/// A broad class of media type, for per-kind cache rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+	/// HTML documents.
+	Html,
+	/// JavaScript.
+	Script,
+	/// CSS.
+	Style,
+	/// `image/*`.
+	Image,
+	/// `font/*` and legacy font types.
+	Font,
+	/// `audio/*` and `video/*`.
+	Media,
+	/// JSON, XML and other structured data.
+	Data,
+	/// Anything else.
+	Other,
+}
+
+impl Category {
+	/// Classifies a media type such as one from [`crate::mime::detect`].
+	#[must_use]
+	pub fn of(media: &MediaType) -> Self {
+		match (media.type_(), media.subtype()) {
+			("text", "html") | ("application", "xhtml+xml") => Self::Html,
+			("text" | "application", "javascript" | "ecmascript" | "x-javascript") => Self::Script,
+			("text", "css") => Self::Style,
+			("image", _) => Self::Image,
+			("font", _) | ("application", "vnd.ms-fontobject" | "font-woff" | "x-font-ttf") => Self::Font,
+			("audio" | "video", _) => Self::Media,
+			("application", "json" | "xml") | ("text", "xml" | "csv") => Self::Data,
+			_ if matches!(media.suffix(), Some("json" | "xml")) => Self::Data,
+			_ => Self::Other,
+		}
+	}
+}
+
+/// Maps paths and media types to `Cache-Control` directives.
+///
+/// Rules are checked in order: path globs in the order they were added,
+/// then [content-hashed](is_content_hashed) file names, then the media type
+/// [`Category`], then the fallback. Globs containing a `/` match the whole
+/// path with any leading `/` removed; others match the file name.
+///
+/// ```
+/// use serve_static::cache::{CacheControl, CachePolicy};
+/// use serve_static::mime::MediaType;
+///
+/// let policy = CachePolicy::recommended().path("/api/**", CacheControl::no_store());
+/// let js = MediaType::parse("text/javascript").unwrap();
+/// let html = MediaType::parse("text/html").unwrap();
+///
+/// assert_eq!(
+///     policy.resolve("assets/app.3f9a2c.js", &js).to_string(),
+///     "public, max-age=31536000, immutable"
+/// );
+/// assert_eq!(policy.resolve("index.html", &html).to_string(), "no-cache");
+/// assert_eq!(policy.resolve("/api/v1/status.json", &js).to_string(), "no-store");
+/// assert_eq!(policy.for_listing().to_string(), "public, max-age=60");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+	paths: Vec<(String, CacheControl)>,
+	hashed: Option<CacheControl>,
+	categories: HashMap<Category, CacheControl>,
+	listing: CacheControl,
+	fallback: CacheControl,
+}
+
+impl CachePolicy {
+	/// Creates a policy with no rules, which yields empty directives.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// A policy suited to typical static sites:
+	///
+	/// - content-hashed files: `public, max-age=31536000, immutable`
+	/// - HTML: `no-cache`
+	/// - listings: `public, max-age=60`
+	/// - everything else: `public, max-age=3600`
+	#[must_use]
+	pub fn recommended() -> Self {
+		Self::new()
+			.hashed(CacheControl::new().public().max_age(YEAR).immutable())
+			.category(Category::Html, CacheControl::no_cache())
+			.listing(
+				CacheControl::new()
+					.public()
+					.max_age(Duration::from_secs(60)),
+			)
+			.fallback(
+				CacheControl::new()
+					.public()
+					.max_age(Duration::from_secs(3600)),
+			)
+	}
+
+	/// Applies `directives` to paths matching a glob pattern.
+	#[must_use]
+	pub fn path(mut self, pattern: &str, directives: CacheControl) -> Self {
+		self
+			.paths
+			.push((pattern.trim_start_matches('/').to_owned(), directives));
+		self
+	}
+
+	/// Applies `directives` to content-hashed file names.
+	#[must_use]
+	pub fn hashed(mut self, directives: CacheControl) -> Self {
+		self.hashed = Some(directives);
+		self
+	}
+
+	/// Applies `directives` to a media type category.
+	#[must_use]
+	pub fn category(mut self, category: Category, directives: CacheControl) -> Self {
+		self.categories.insert(category, directives);
+		self
+	}
+
+	/// Sets the directives for directory listings.
+	#[must_use]
+	pub fn listing(mut self, directives: CacheControl) -> Self {
+		self.listing = directives;
+		self
+	}
+
+	/// Sets the directives used when no other rule applies.
+	#[must_use]
+	pub fn fallback(mut self, directives: CacheControl) -> Self {
+		self.fallback = directives;
+		self
+	}
+
+	/// The directives for a file at `path` with the given media type.
+	#[must_use]
+	pub fn resolve(&self, path: impl AsRef<Path>, media: &MediaType) -> &CacheControl {
+		let path = path.as_ref().to_string_lossy();
+		let path = path.trim_start_matches('/');
+		let name = path.rsplit('/').next().unwrap_or_default();
+		let by_path = self.paths.iter().find(|(pattern, _)| {
+			let text = if pattern.contains('/') { path } else { name };
+			glob::matches(pattern, text)
+		});
+		if let Some((_, directives)) = by_path {
+			return directives;
+		}
+		if let Some(directives) = &self.hashed
+			&& is_content_hashed(name)
+		{
+			return directives;
+		}
+		self
+			.categories
+			.get(&Category::of(media))
+			.unwrap_or(&self.fallback)
+	}
+
+	/// The directives for directory listings.
+	#[must_use]
+	pub fn for_listing(&self) -> &CacheControl {
+		&self.listing
+	}
+}
+
+/// Whether a file name carries a build fingerprint, such as
+/// `app.3f9a2c.js`, `main.3f9a2c1b.chunk.js` or `index-BdkS2k3c.js`.
+///
+/// Only the last token before the extension is checked, with `.min` and
+/// `.chunk` counted as part of the extension. A fingerprint is either hex
+/// in one case mixing letters and digits, of 6 to 64 characters after a
+/// `.` or 8 to 64 after a `-` or `_`, or exactly 8 base64url characters
+/// after a `.` or `-` that mix lower and upper case with a digit inside, as
+/// bundlers emit. Plain words, versions, dates and model names do not
+/// count.
+///
+/// ```
+/// use serve_static::cache::is_content_hashed;
+///
+/// assert!(is_content_hashed("app.3f9a2c.js"));
+/// assert!(!is_content_hashed("jquery-3.7.1.min.js"));
+/// assert!(!is_content_hashed("build-abc123.txt"));
+/// ```
+#[must_use]
+pub fn is_content_hashed(name: &str) -> bool {
+	let Some((mut stem, _extension)) = name.rsplit_once('.') else {
+		return false;
+	};
+	while let Some(rest) = stem
+		.strip_suffix(".min")
+		.or_else(|| stem.strip_suffix(".chunk"))
+	{
+		stem = rest;
+	}
+	let bytes = stem.as_bytes();
+	if bytes.len() > 9
+		&& matches!(bytes[bytes.len() - 9], b'.' | b'-')
+		&& is_base64_hash(&bytes[bytes.len() - 8..])
+	{
+		return true;
+	}
+	// Short hex is only trusted as a whole `[name].[hash]` part, so that
+	// `build-abc123` stays a name.
+	match stem.rfind(['.', '-', '_']) {
+		Some(0) | None => false,
+		Some(i) => is_hex_hash(
+			&stem[i + 1..],
+			if stem[i..].starts_with('.') { 6 } else { 8 },
+		),
+	}
+}
+
+fn is_hex_hash(token: &str, min_len: usize) -> bool {
+	let count = |f: fn(&u8) -> bool| token.bytes().filter(f).count();
+	let len = token.len();
+	let digits = count(u8::is_ascii_digit);
+	let mixed_case = count(u8::is_ascii_lowercase) > 0 && count(u8::is_ascii_uppercase) > 0;
+	(min_len..=64).contains(&len)
+		&& token.bytes().all(|b| b.is_ascii_hexdigit())
+		&& digits > 0
+		&& digits < len
+		&& !mixed_case
+}
+
+fn is_base64_hash(token: &[u8]) -> bool {
+	let base64url = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_');
+	// Upper case beyond the first letter, so `Section1` is not a hash, and a
+	// digit followed by a letter, so `iPhone12` is not either.
+	token.iter().all(base64url)
+		&& token.iter().any(u8::is_ascii_lowercase)
+		&& token[1..].iter().any(u8::is_ascii_uppercase)
+		&& token
+			.windows(2)
+			.any(|w| w[0].is_ascii_digit() && w[1].is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn media(s: &str) -> MediaType {
+		MediaType::parse(s).unwrap()
+	}
+
+	#[test]
+	fn directives_render_in_order() {
+		assert_eq!(CacheControl::new().to_string(), "");
+		assert!(CacheControl::new().is_empty());
+		assert_eq!(CacheControl::no_store().to_string(), "no-store");
+		let all = CacheControl::no_cache()
+			.public()
+			.immutable()
+			.stale_while_revalidate(Duration::from_secs(30))
+			.s_maxage(Duration::from_secs(86_400))
+			.max_age(Duration::from_millis(1_500));
+		assert_eq!(
+			all.to_string(),
+			"public, no-cache, max-age=1, s-maxage=86400, stale-while-revalidate=30, immutable"
+		);
+		assert!(!all.is_empty());
+	}
+
+	#[test]
+	fn categories() {
+		assert_eq!(Category::of(&media("text/html")), Category::Html);
+		assert_eq!(
+			Category::of(&media("application/xhtml+xml")),
+			Category::Html
+		);
+		assert_eq!(Category::of(&media("text/javascript")), Category::Script);
+		assert_eq!(Category::of(&media("text/css")), Category::Style);
+		assert_eq!(Category::of(&media("image/svg+xml")), Category::Image);
+		assert_eq!(Category::of(&media("font/woff2")), Category::Font);
+		assert_eq!(Category::of(&media("video/mp4")), Category::Media);
+		assert_eq!(
+			Category::of(&media("application/manifest+json")),
+			Category::Data
+		);
+		assert_eq!(Category::of(&media("application/zip")), Category::Other);
+	}
+
+	#[test]
+	fn hashed_names() {
+		for hashed in [
+			"app.3f9a2c.js",
+			"app.3f9a2c1b.js",
+			"main.3f9a2c1b.chunk.js",
+			"styles.A1B2C3D4.css",
+			"index-BdkS2k3c.js",
+			"vendor_9f86d081884c7d659a2feaa0c55ad015.js",
+			"logo-3f9a2c1b.min.svg",
+			"chunk.Ab3dE-_x.js",
+		] {
+			assert!(is_content_hashed(hashed), "{hashed}");
+		}
+		for plain in [
+			"app.js",
+			"index.html",
+			"jquery-3.7.1.min.js",
+			"report-20240315.pdf",
+			"photo-deadbeef.jpg",
+			"Settings.js",
+			"page-section1.js",
+			"my-Section1.txt",
+			"3f9a2c.js",
+			"3f9a2c",
+			"archive.tar.gz",
+			"app.3f9a2.js",
+			".3f9a2c.js",
+			"app.3F9a2c.js",
+			"3f9a2c1b.v2.js",
+			"report-2024Q1Final.pdf",
+			"notes-cafe01.md",
+			"build-abc123.txt",
+			"font-Roboto400Bold.woff2",
+			"photos.iPhone12Pro.png",
+			"Chapter.Part2Final.txt",
+			"avatar-iPhone12.png",
+		] {
+			assert!(!is_content_hashed(plain), "{plain}");
+		}
+	}
+
+	#[test]
+	fn policy_precedence() {
+		let policy = CachePolicy::recommended()
+			.path("*.webmanifest", CacheControl::no_cache())
+			.path("/private/**", CacheControl::no_store())
+			.category(
+				Category::Image,
+				CacheControl::new().max_age(Duration::from_secs(86_400)),
+			);
+		let js = media("text/javascript");
+		let png = media("image/png");
+
+		assert_eq!(
+			policy.resolve("/static/app.3f9a2c.js", &js).to_string(),
+			"public, max-age=31536000, immutable"
+		);
+		assert_eq!(
+			policy.resolve("/private/app.3f9a2c.js", &js).to_string(),
+			"no-store"
+		);
+		assert_eq!(
+			policy
+				.resolve("site.webmanifest", &media("application/manifest+json"))
+				.to_string(),
+			"no-cache"
+		);
+		assert_eq!(
+			policy.resolve("img/logo-3f9a2c1b.png", &png).to_string(),
+			"public, max-age=31536000, immutable"
+		);
+		assert_eq!(
+			policy.resolve("img/logo.png", &png).to_string(),
+			"max-age=86400"
+		);
+		assert_eq!(
+			policy.resolve("app.js", &js).to_string(),
+			"public, max-age=3600"
+		);
+		assert_eq!(policy.for_listing().to_string(), "public, max-age=60");
+
+		let empty = CachePolicy::new();
+		assert!(empty.resolve("app.3f9a2c.js", &js).is_empty());
+		assert!(empty.for_listing().is_empty());
+	}
+}
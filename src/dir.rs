@@ -0,0 +1,915 @@
+/* src/dir.rs */
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::fs::{FileSystem, FileType, Metadata, StdFs};
+use crate::listing::{self, Entry};
+use crate::{glob, mime, path};
+
+/// Default cap on the number of entries read from one directory.
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// Default cap on the number of children examined in one directory,
+/// including hidden and denied ones.
+pub const DEFAULT_MAX_SCAN: usize = 100_000;
+
+/// The entries read from a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+	/// Visible entries, ordered with [`listing::sort`].
+	pub entries: Vec<Entry>,
+	/// Whether reading stopped at the entry or scan cap. When set, `entries`
+	/// is an arbitrary subset of the directory, not its first page.
+	pub truncated: bool,
+}
+
+/// Reads a jailed directory into [`Entry`] values.
+///
+/// The directory is located with [`path::resolve_in`], so the same
+/// traversal rules apply as for file requests. Then each child is filtered
+/// and its metadata collected:
+/// - hidden (dot) files are skipped unless [`ListingBuilder::show_hidden`]
+/// - names matching a [`ListingBuilder::deny`] glob are skipped
+/// - names that are not valid UTF-8 are skipped, since no URI can reach them
+/// - broken symlinks are listed as plain entries without a size
+///
+/// Files get a MIME type guessed from the name alone, so listing a
+/// directory never opens its files, and an ETag when the modification time
+/// is known. Symlink targets are only reported for links that resolve
+/// inside the root, unless [`ListingBuilder::allow_symlinks`] is set.
+///
+/// ```
+/// use serve_static::dir::ListingBuilder;
+///
+/// let dir = std::env::temp_dir();
+/// let listing = ListingBuilder::new()
+///     .deny("*.tmp")
+///     .max_entries(100)
+///     .read(&dir, "/")
+///     .unwrap();
+/// assert!(listing.entries.len() <= 100);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingBuilder {
+	show_hidden: bool,
+	deny: Vec<String>,
+	follow_symlinks: bool,
+	allow_symlinks: bool,
+	max_entries: usize,
+	max_scan: usize,
+}
+
+impl Default for ListingBuilder {
+	fn default() -> Self {
+		Self {
+			show_hidden: false,
+			deny: Vec::new(),
+			follow_symlinks: false,
+			allow_symlinks: false,
+			max_entries: DEFAULT_MAX_ENTRIES,
+			max_scan: DEFAULT_MAX_SCAN,
+		}
+	}
+}
+
+impl ListingBuilder {
+	/// Creates a builder that hides dot files, does not follow symlinks and
+	/// reads at most [`DEFAULT_MAX_ENTRIES`] entries.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets whether names starting with `.` are listed.
+	#[must_use]
+	pub fn show_hidden(mut self, show: bool) -> Self {
+		self.show_hidden = show;
+		self
+	}
+
+	/// Adds a glob pattern (`*`, `?`) for names that must never be listed.
+	#[must_use]
+	pub fn deny(mut self, pattern: impl Into<String>) -> Self {
+		self.deny.push(pattern.into());
+		self
+	}
+
+	/// Sets whether symlinked entries report their target's `is_dir`, size
+	/// and modification time.
+	///
+	/// Targets outside the root are only followed when
+	/// [`ListingBuilder::allow_symlinks`] is also set; otherwise the link is
+	/// listed as is.
+	#[must_use]
+	pub fn follow_symlinks(mut self, follow: bool) -> Self {
+		self.follow_symlinks = follow;
+		self
+	}
+
+	/// Sets whether symlinks may point outside the root, with the same
+	/// meaning as the `allow_symlinks` argument of [`path::resolve`].
+	#[must_use]
+	pub fn allow_symlinks(mut self, allow: bool) -> Self {
+		self.allow_symlinks = allow;
+		self
+	}
+
+	/// Caps the number of visible entries returned.
+	#[must_use]
+	pub fn max_entries(mut self, max: usize) -> Self {
+		self.max_entries = max;
+		self
+	}
+
+	/// Caps the number of children examined, visible or not, bounding the
+	/// work done on a huge directory even when most of it is hidden or
+	/// denied. Defaults to [`DEFAULT_MAX_SCAN`].
+	#[must_use]
+	pub fn max_scan(mut self, max: usize) -> Self {
+		self.max_scan = max;
+		self
+	}
+
+	/// Whether `name` passes the hidden-file and deny rules.
+	#[must_use]
+	pub fn is_visible(&self, name: &str) -> bool {
+		(self.show_hidden || !name.starts_with('.'))
+			&& !self.deny.iter().any(|pattern| glob::matches(pattern, name))
+	}
+
+	/// Reads the directory `uri` below `root` from disk.
+	pub fn read(&self, root: impl AsRef<Path>, uri: &str) -> Result<Listing, Error> {
+		self.read_in(&StdFs, root, uri)
+	}
+
+	/// Reads the directory `uri` below `root` from `fs`.
+	pub fn read_in<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		root: impl AsRef<Path>,
+		uri: &str,
+	) -> Result<Listing, Error> {
+		let root = root.as_ref();
+		let dir = path::resolve_in(fs, root, uri, self.allow_symlinks)?;
+		self.read_at(fs, &path::canonical_root(fs, root)?, &dir)
+	}
+
+	/// Reads the already resolved directory `dir` inside `canonical_root`.
+	fn read_at<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		canonical_root: &Path,
+		dir: &Path,
+	) -> Result<Listing, Error> {
+		let mut entries = Vec::new();
+		let mut truncated = false;
+		for (scanned, child) in fs.read_dir(dir).map_err(Error::Io)?.enumerate() {
+			if scanned == self.max_scan {
+				truncated = true;
+				break;
+			}
+			let child = child.map_err(Error::Io)?;
+			let Ok(name) = child.name.into_string() else {
+				continue;
+			};
+			if !self.is_visible(&name) {
+				continue;
+			}
+			if entries.len() == self.max_entries {
+				truncated = true;
+				break;
+			}
+			let full = dir.join(&name);
+			let (meta, target) = if child.file_type == FileType::Symlink {
+				self.symlink(fs, canonical_root, &full)
+			} else {
+				(fs.symlink_metadata(&full).ok(), None)
+			};
+			entries.push(entry(name, child.file_type, target, meta));
+		}
+		listing::sort(&mut entries);
+		Ok(Listing { entries, truncated })
+	}
+
+	/// Metadata and target of a symlink. The target's metadata is used when
+	/// following, falling back to the link itself when the target is missing
+	/// or outside the jail; the target path is only exposed when inside.
+	fn symlink<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		root: &Path,
+		link: &Path,
+	) -> (Option<Metadata>, Option<PathBuf>) {
+		let inside = self.allow_symlinks
+			|| fs
+				.canonicalize(link)
+				.is_ok_and(|target| target.starts_with(root));
+		let target = if inside {
+			fs.read_link(link).ok()
+		} else {
+			None
+		};
+		let followed = if inside && self.follow_symlinks {
+			fs.metadata(link).ok()
+		} else {
+			None
+		};
+		(followed.or_else(|| fs.symlink_metadata(link).ok()), target)
+	}
+}
+
+/// Default cap on how many levels below the starting directory a
+/// [`TreeBuilder`] lists.
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// Default cap on the total number of entries in a [`Tree`].
+pub const DEFAULT_MAX_TREE_ENTRIES: usize = 100_000;
+
+/// Aggregate totals for everything listed below a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Summary {
+	/// Non-directory entries, at any depth.
+	pub files: u64,
+	/// Distinct subdirectories, at any depth. A directory reached again
+	/// through a symlink is listed but not counted twice.
+	pub dirs: u64,
+	/// Sum of the sizes of `files`.
+	pub bytes: u64,
+}
+
+/// A directory entry together with everything listed below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+	/// The entry itself. For the starting directory, its name is the last
+	/// segment of the URI, or empty at the root.
+	pub entry: Entry,
+	/// Child trees, ordered with [`listing::sort`]. Empty for files.
+	pub children: Vec<Self>,
+	/// Totals over `children`, recursively.
+	pub summary: Summary,
+	/// Whether anything below this entry was left out because of a limit or
+	/// an unreadable directory, making `children` and `summary` partial.
+	pub truncated: bool,
+}
+
+impl Tree {
+	fn leaf(entry: Entry) -> Self {
+		Self {
+			entry,
+			children: Vec::new(),
+			summary: Summary::default(),
+			truncated: false,
+		}
+	}
+}
+
+/// Walks a jailed directory recursively into a [`Tree`].
+///
+/// Each directory is read with the hidden-file, deny, symlink and
+/// per-directory rules of a [`ListingBuilder`]. On top of those:
+/// - directories deeper than [`TreeBuilder::max_depth`] are listed but not
+///   descended into
+/// - the walk stops after [`TreeBuilder::max_entries`] entries in total
+/// - every directory is descended into at most once, identified by its
+///   canonical path, so symlink loops terminate and totals never count the
+///   same directory twice
+/// - a directory whose canonical path leaves the root is never read, unless
+///   [`ListingBuilder::allow_symlinks`] is set
+///
+/// Unreadable subdirectories mark their node as truncated instead of failing
+/// the walk; errors for the starting directory are returned.
+///
+/// ```
+/// use serve_static::dir::{ListingBuilder, TreeBuilder};
+///
+/// let tree = TreeBuilder::new()
+///     .listing(ListingBuilder::new().deny("*.tmp"))
+///     .max_depth(2)
+///     .walk(std::env::temp_dir(), "/")
+///     .unwrap();
+/// assert!(tree.entry.is_dir);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeBuilder {
+	listing: ListingBuilder,
+	max_depth: usize,
+	max_entries: usize,
+}
+
+impl Default for TreeBuilder {
+	fn default() -> Self {
+		Self {
+			listing: ListingBuilder::new(),
+			max_depth: DEFAULT_MAX_DEPTH,
+			max_entries: DEFAULT_MAX_TREE_ENTRIES,
+		}
+	}
+}
+
+impl TreeBuilder {
+	/// Creates a walker with the default [`ListingBuilder`] rules, at most
+	/// [`DEFAULT_MAX_DEPTH`] levels and [`DEFAULT_MAX_TREE_ENTRIES`] entries.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the rules used to read each directory.
+	#[must_use]
+	pub fn listing(mut self, listing: ListingBuilder) -> Self {
+		self.listing = listing;
+		self
+	}
+
+	/// Caps how many levels below the starting directory are listed. With
+	/// `1`, only the direct children are listed; with `0`, none are.
+	#[must_use]
+	pub fn max_depth(mut self, depth: usize) -> Self {
+		self.max_depth = depth;
+		self
+	}
+
+	/// Caps the total number of entries in the tree.
+	#[must_use]
+	pub fn max_entries(mut self, max: usize) -> Self {
+		self.max_entries = max;
+		self
+	}
+
+	/// Whether symlinks may lead outside the root, as set on the listing
+	/// rules.
+	pub(crate) fn allows_symlinks(&self) -> bool {
+		self.listing.allow_symlinks
+	}
+
+	/// Walks the directory `uri` below `root` on disk.
+	pub fn walk(&self, root: impl AsRef<Path>, uri: &str) -> Result<Tree, Error> {
+		self.walk_in(&StdFs, root, uri)
+	}
+
+	/// Walks the directory `uri` below `root` in `fs`.
+	pub fn walk_in<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		root: impl AsRef<Path>,
+		uri: &str,
+	) -> Result<Tree, Error> {
+		self.walk_resolved(fs, root.as_ref(), uri).map(|(tree, _)| tree)
+	}
+
+	/// Walks like [`TreeBuilder::walk_in`], also returning the resolved path
+	/// of the starting directory.
+	pub(crate) fn walk_resolved<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		root: &Path,
+		uri: &str,
+	) -> Result<(Tree, PathBuf), Error> {
+		let dir = path::resolve_in(fs, root, uri, self.listing.allow_symlinks)?;
+		let canonical_root = path::canonical_root(fs, root)?;
+		let key = path::normalize(uri)?;
+		let name = key.rsplit('/').next().unwrap_or_default();
+		let meta = fs.metadata(&dir).map_err(Error::Io)?;
+
+		let mut tree = Tree::leaf(entry(name.to_owned(), meta.file_type, None, Some(meta)));
+		let mut walk = Walk {
+			remaining: self.max_entries,
+			visited: HashSet::new(),
+		};
+		if self.max_depth == 0 {
+			tree.truncated = true;
+		} else {
+			self.descend(fs, &canonical_root, &dir, 1, &mut walk, &mut tree)?;
+		}
+		Ok((tree, dir))
+	}
+
+	/// Lists `dir` into `node` and recurses into its subdirectories.
+	///
+	/// Returns `false` without listing anything when `dir` was already
+	/// walked through another path.
+	fn descend<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		root: &Path,
+		dir: &Path,
+		depth: usize,
+		walk: &mut Walk,
+		node: &mut Tree,
+	) -> Result<bool, Error> {
+		let canonical = fs.canonicalize(dir).map_err(Error::Io)?;
+		if !self.listing.allow_symlinks && !canonical.starts_with(root) {
+			return Err(Error::SymlinkTraversal);
+		}
+		if !walk.visited.insert(canonical) {
+			return Ok(false);
+		}
+
+		let listing = self.listing.read_at(fs, root, dir)?;
+		node.truncated |= listing.truncated;
+		for entry in listing.entries {
+			if walk.remaining == 0 {
+				node.truncated = true;
+				break;
+			}
+			walk.remaining -= 1;
+
+			let mut child = Tree::leaf(entry);
+			if child.entry.is_dir {
+				let path = dir.join(&child.entry.name);
+				// `None` when a limit or an error stopped the descent, and
+				// `Some(false)` when the directory was walked elsewhere, in
+				// which case it is listed but not counted again.
+				let walked = if depth < self.max_depth {
+					self
+						.descend(fs, root, &path, depth + 1, walk, &mut child)
+						.ok()
+				} else if fs
+					.canonicalize(&path)
+					.is_ok_and(|canonical| walk.visited.contains(&canonical))
+				{
+					Some(false)
+				} else {
+					None
+				};
+				if walked != Some(false) {
+					node.summary.dirs += 1;
+				}
+				child.truncated |= walked.is_none();
+				node.summary.files += child.summary.files;
+				node.summary.dirs += child.summary.dirs;
+				node.summary.bytes += child.summary.bytes;
+				node.truncated |= child.truncated;
+			} else {
+				node.summary.files += 1;
+				node.summary.bytes += child.entry.size.unwrap_or(0);
+			}
+			node.children.push(child);
+		}
+		Ok(true)
+	}
+}
+
+/// Mutable state shared across one [`TreeBuilder`] walk.
+struct Walk {
+	remaining: usize,
+	visited: HashSet<PathBuf>,
+}
+
+/// Builds an entry whose `kind` is the child's own type, with the rest
+/// taken from `meta`, which describes a symlink's target when followed.
+fn entry(name: String, kind: FileType, target: Option<PathBuf>, meta: Option<Metadata>) -> Entry {
+	let mut entry = Entry::new(name, kind, None);
+	entry.symlink_target = target;
+	let Some(meta) = meta else {
+		return entry;
+	};
+	entry.is_dir = meta.is_dir();
+	entry.modified = meta.modified;
+	entry.created = meta.created;
+	entry.readonly = Some(meta.readonly);
+	if meta.is_file() {
+		entry.size = Some(meta.len);
+		entry.mime = Some(mime::detect(&entry.name, &[]).into());
+		entry.etag = meta.modified.map(|modified| mime::etag(modified, meta.len));
+	}
+	entry
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fs::MemoryFs;
+	use std::time::{Duration, UNIX_EPOCH};
+
+	fn make_fs() -> MemoryFs {
+		let mut fs = MemoryFs::new();
+		fs.insert_file("/srv/index.html", b"<html>")
+			.insert_file("/srv/notes.tmp", b"tmp")
+			.insert_file("/srv/.env", b"SECRET=1")
+			.insert_file("/srv/docs/guide.md", b"# guide")
+			.insert_file("/etc/passwd", b"root:x:0:0")
+			.insert_dir("/etc/ssl")
+			.insert_symlink("/srv/alias.html", "index.html")
+			.insert_symlink("/srv/manual", "docs")
+			.insert_symlink("/srv/passwd", "/etc/passwd")
+			.insert_symlink("/srv/ssl", "/etc/ssl")
+			.insert_symlink("/srv/broken", "missing.txt");
+		fs
+	}
+
+	fn find<'a>(listing: &'a Listing, name: &str) -> &'a Entry {
+		listing.entries.iter().find(|e| e.name == name).unwrap()
+	}
+
+	fn names(listing: &Listing) -> Vec<&str> {
+		listing.entries.iter().map(|e| e.name.as_str()).collect()
+	}
+
+	#[test]
+	fn reads_and_sorts() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new().read_in(&fs, "/srv", "/").unwrap();
+		assert_eq!(
+			names(&listing),
+			[
+				"docs",
+				"alias.html",
+				"broken",
+				"index.html",
+				"manual",
+				"notes.tmp",
+				"passwd",
+				"ssl"
+			]
+		);
+		assert!(!listing.truncated);
+		assert_eq!(find(&listing, "index.html").size, Some(6));
+		assert_eq!(find(&listing, "docs").size, None);
+	}
+
+	#[test]
+	fn hidden_files() {
+		let fs = make_fs();
+		let hidden = ListingBuilder::new().read_in(&fs, "/srv", "/").unwrap();
+		assert!(!names(&hidden).contains(&".env"));
+		let shown = ListingBuilder::new()
+			.show_hidden(true)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(names(&shown).contains(&".env"));
+	}
+
+	#[test]
+	fn deny_rules() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new()
+			.deny("*.tmp")
+			.deny("passwd")
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		let names = names(&listing);
+		assert!(!names.contains(&"notes.tmp"));
+		assert!(!names.contains(&"passwd"));
+		assert!(names.contains(&"index.html"));
+	}
+
+	#[test]
+	fn symlinks_not_followed_by_default() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new().read_in(&fs, "/srv", "/").unwrap();
+		let manual = find(&listing, "manual");
+		assert!(!manual.is_dir);
+		assert_eq!(manual.size, None);
+	}
+
+	#[test]
+	fn symlinks_followed_inside_root() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new()
+			.follow_symlinks(true)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(find(&listing, "manual").is_dir);
+		assert_eq!(find(&listing, "alias.html").size, Some(6));
+		// Outside the jail: listed as the link itself.
+		assert!(!find(&listing, "ssl").is_dir);
+		assert_eq!(find(&listing, "passwd").size, None);
+		// Broken links do not fail the listing.
+		assert_eq!(find(&listing, "broken").size, None);
+	}
+
+	#[test]
+	fn symlinks_followed_outside_root_when_allowed() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new()
+			.follow_symlinks(true)
+			.allow_symlinks(true)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(find(&listing, "ssl").is_dir);
+		assert_eq!(find(&listing, "passwd").size, Some(10));
+	}
+
+	#[test]
+	fn entry_metadata() {
+		let mut fs = make_fs();
+		fs.set_modified("/srv/index.html", UNIX_EPOCH + Duration::from_secs(100));
+		let listing = ListingBuilder::new()
+			.follow_symlinks(true)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+
+		let index = find(&listing, "index.html");
+		assert_eq!(index.kind, FileType::File);
+		assert!(index.mime.is_some());
+		assert_eq!(
+			index.etag.as_deref(),
+			Some(mime::etag(UNIX_EPOCH + Duration::from_secs(100), 6).as_str())
+		);
+		assert_eq!(index.readonly, Some(false));
+		assert_eq!(find(&listing, "notes.tmp").etag, None);
+
+		let docs = find(&listing, "docs");
+		assert_eq!(docs.kind, FileType::Dir);
+		assert_eq!((docs.mime.as_deref(), docs.etag.as_deref()), (None, None));
+
+		// Followed links keep their own kind and report the target's data.
+		let alias = find(&listing, "alias.html");
+		assert_eq!(alias.kind, FileType::Symlink);
+		assert_eq!(
+			alias.symlink_target.as_deref(),
+			Some(Path::new("index.html"))
+		);
+		assert!(alias.etag.is_some());
+		let manual = find(&listing, "manual");
+		assert_eq!(manual.kind, FileType::Symlink);
+		assert!(manual.is_dir);
+	}
+
+	#[test]
+	fn symlink_targets_outside_root_hidden() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new().read_in(&fs, "/srv", "/").unwrap();
+		assert_eq!(
+			find(&listing, "manual").symlink_target.as_deref(),
+			Some(Path::new("docs"))
+		);
+		let passwd = find(&listing, "passwd");
+		assert_eq!(passwd.kind, FileType::Symlink);
+		assert_eq!(passwd.symlink_target, None);
+		assert_eq!(find(&listing, "broken").symlink_target, None);
+
+		let allowed = ListingBuilder::new()
+			.allow_symlinks(true)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert_eq!(
+			find(&allowed, "passwd").symlink_target.as_deref(),
+			Some(Path::new("/etc/passwd"))
+		);
+	}
+
+	#[test]
+	fn entry_cap() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new()
+			.max_entries(3)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert_eq!(listing.entries.len(), 3);
+		assert!(listing.truncated);
+
+		let exact = ListingBuilder::new()
+			.max_entries(8)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(!exact.truncated);
+	}
+
+	#[test]
+	fn scan_cap_counts_hidden_entries() {
+		let mut fs = MemoryFs::new();
+		for i in 0..20 {
+			fs.insert_file(format!("/srv/.hidden{i:02}"), b"x");
+		}
+		fs.insert_file("/srv/visible.txt", b"x");
+
+		let listing = ListingBuilder::new()
+			.max_scan(10)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(listing.entries.is_empty());
+		assert!(listing.truncated);
+
+		let full = ListingBuilder::new()
+			.max_scan(21)
+			.read_in(&fs, "/srv", "/")
+			.unwrap();
+		assert_eq!(names(&full), ["visible.txt"]);
+		assert!(!full.truncated);
+	}
+
+	#[test]
+	fn jailed_directory() {
+		let fs = make_fs();
+		let listing = ListingBuilder::new()
+			.read_in(&fs, "/srv", "/../../docs")
+			.unwrap();
+		assert_eq!(names(&listing), ["guide.md"]);
+
+		let escape = ListingBuilder::new().read_in(&fs, "/srv", "/ssl");
+		assert!(matches!(escape, Err(Error::SymlinkTraversal)));
+	}
+
+	#[test]
+	fn not_a_directory() {
+		let fs = make_fs();
+		let result = ListingBuilder::new().read_in(&fs, "/srv", "/index.html");
+		assert!(matches!(result, Err(Error::Io(_))));
+	}
+
+	#[test]
+	fn reads_std_fs() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir(dir.path().join("sub")).unwrap();
+		std::fs::write(dir.path().join("a.txt"), b"abc").unwrap();
+		std::fs::write(dir.path().join(".hidden"), b"").unwrap();
+
+		let listing = ListingBuilder::new().read(dir.path(), "/").unwrap();
+		assert_eq!(names(&listing), ["sub", "a.txt"]);
+		assert_eq!(listing.entries[1].size, Some(3));
+		assert!(listing.entries[1].modified.is_some());
+		assert!(listing.entries[1].etag.is_some());
+		assert_eq!(listing.entries[1].readonly, Some(false));
+		assert_eq!(listing.entries[0].kind, FileType::Dir);
+	}
+
+	fn tree_fs() -> MemoryFs {
+		let mut fs = MemoryFs::new();
+		fs.insert_file("/srv/a.txt", b"12345")
+			.insert_file("/srv/.hidden", b"secret")
+			.insert_file("/srv/docs/guide.md", b"# guide")
+			.insert_file("/srv/docs/api/index.html", b"<html></html>")
+			.insert_dir("/srv/empty")
+			.insert_file("/etc/passwd", b"root:x:0:0")
+			.insert_symlink("/srv/docs/api/up", "/srv/docs")
+			.insert_symlink("/srv/etc", "/etc");
+		fs
+	}
+
+	fn child<'a>(tree: &'a Tree, name: &str) -> &'a Tree {
+		tree.children.iter().find(|t| t.entry.name == name).unwrap()
+	}
+
+	#[test]
+	fn tree_structure_and_summary() {
+		let fs = tree_fs();
+		let tree = TreeBuilder::new().walk_in(&fs, "/srv", "/").unwrap();
+		assert_eq!(tree.entry.name, "");
+		assert!(tree.entry.is_dir);
+		assert!(!tree.truncated);
+
+		let names: Vec<_> = tree
+			.children
+			.iter()
+			.map(|t| t.entry.name.as_str())
+			.collect();
+		assert_eq!(names, ["docs", "empty", "a.txt", "etc"]);
+		let docs = child(&tree, "docs");
+		assert_eq!(
+			docs.summary,
+			Summary {
+				files: 3,
+				dirs: 1,
+				bytes: 7 + 13
+			}
+		);
+		let api = child(docs, "api");
+		assert_eq!(api.children.len(), 2);
+		assert_eq!(child(&tree, "empty").summary, Summary::default());
+		// Symlinks are not followed by default and count as files.
+		assert_eq!(
+			tree.summary,
+			Summary {
+				files: 5,
+				dirs: 3,
+				bytes: 5 + 7 + 13
+			}
+		);
+
+		let sub = TreeBuilder::new()
+			.walk_in(&fs, "/srv", "/docs/../docs")
+			.unwrap();
+		assert_eq!(sub.entry.name, "docs");
+		assert_eq!(sub.summary, docs.summary);
+	}
+
+	#[test]
+	fn tree_depth_limit() {
+		let fs = tree_fs();
+		let tree = TreeBuilder::new()
+			.max_depth(1)
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(tree.truncated);
+		let docs = child(&tree, "docs");
+		assert!(docs.truncated);
+		assert!(docs.children.is_empty());
+		assert_eq!(tree.summary.dirs, 2);
+
+		let none = TreeBuilder::new()
+			.max_depth(0)
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(none.children.is_empty());
+		assert!(none.truncated);
+	}
+
+	#[test]
+	fn tree_entry_limit() {
+		let fs = tree_fs();
+		let tree = TreeBuilder::new()
+			.max_entries(3)
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		assert!(tree.truncated);
+		fn count(tree: &Tree) -> usize {
+			tree.children.iter().map(|t| 1 + count(t)).sum()
+		}
+		assert_eq!(count(&tree), 3);
+	}
+
+	#[test]
+	fn tree_symlink_loop_and_jail() {
+		let fs = tree_fs();
+		let tree = TreeBuilder::new()
+			.listing(ListingBuilder::new().follow_symlinks(true))
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		// `up` points back at an ancestor: listed, but not walked again.
+		let up = child(child(child(&tree, "docs"), "api"), "up");
+		assert!(up.entry.is_dir);
+		assert!(up.children.is_empty());
+		assert!(!up.truncated);
+		// Nor is it counted: docs, docs/api and empty.
+		assert_eq!(tree.summary.dirs, 3);
+		// Not even when the depth limit stops right at it.
+		let limited = TreeBuilder::new()
+			.listing(ListingBuilder::new().follow_symlinks(true))
+			.max_depth(3)
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		let up = child(child(child(&limited, "docs"), "api"), "up");
+		assert!(up.children.is_empty());
+		assert!(!up.truncated);
+		assert_eq!(limited.summary.dirs, 3);
+		// `etc` leaves the jail, so it is not followed.
+		let etc = child(&tree, "etc");
+		assert!(!etc.entry.is_dir);
+		assert!(etc.children.is_empty());
+		assert!(!tree.truncated);
+
+		let allowed = TreeBuilder::new()
+			.listing(
+				ListingBuilder::new()
+					.follow_symlinks(true)
+					.allow_symlinks(true),
+			)
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		assert_eq!(child(&allowed, "etc").summary.files, 1);
+	}
+
+	#[test]
+	fn tree_respects_listing_rules_and_errors() {
+		let fs = tree_fs();
+		let tree = TreeBuilder::new()
+			.listing(ListingBuilder::new().show_hidden(true).deny("docs"))
+			.walk_in(&fs, "/srv", "/")
+			.unwrap();
+		let names: Vec<_> = tree
+			.children
+			.iter()
+			.map(|t| t.entry.name.as_str())
+			.collect();
+		assert_eq!(names, ["empty", ".hidden", "a.txt", "etc"]);
+
+		let file = TreeBuilder::new().walk_in(&fs, "/srv", "/a.txt");
+		assert!(matches!(file, Err(Error::Io(_))));
+		let escape = TreeBuilder::new().walk_in(&fs, "/srv", "/etc");
+		assert!(matches!(escape, Err(Error::SymlinkTraversal)));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn tree_on_disk_with_loop() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+		std::fs::write(dir.path().join("a/b/f.bin"), [0; 10]).unwrap();
+		std::os::unix::fs::symlink("..", dir.path().join("a/b/loop")).unwrap();
+		let tree = TreeBuilder::new()
+			.listing(ListingBuilder::new().follow_symlinks(true))
+			.walk(dir.path(), "/")
+			.unwrap();
+		assert_eq!(tree.summary.bytes, 10);
+		assert_eq!(tree.summary.files, 1);
+		assert_eq!(tree.summary.dirs, 2);
+		assert!(!tree.truncated);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn broken_symlink_on_disk() {
+		let dir = tempfile::tempdir().unwrap();
+		std::os::unix::fs::symlink("missing", dir.path().join("dangling")).unwrap();
+		let listing = ListingBuilder::new()
+			.follow_symlinks(true)
+			.read(dir.path(), "/")
+			.unwrap();
+		assert_eq!(names(&listing), ["dangling"]);
+		assert!(!listing.entries[0].is_dir);
+	}
+}
@@ -0,0 +1,232 @@
+/* src/disposition.rs */
+
+use std::fmt;
+use std::path::Path;
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+/// Characters percent-encoded in an RFC 8187 `ext-value`: everything
+/// except `attr-char`.
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'!')
+	.remove(b'#')
+	.remove(b'$')
+	.remove(b'&')
+	.remove(b'+')
+	.remove(b'-')
+	.remove(b'.')
+	.remove(b'^')
+	.remove(b'_')
+	.remove(b'`')
+	.remove(b'|')
+	.remove(b'~');
+
+/// Whether the browser should display the response or save it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Disposition {
+	/// Display in the browser where possible.
+	#[default]
+	Inline,
+	/// Offer a download.
+	Attachment,
+}
+
+/// Builds a `Content-Disposition` header value (RFC 6266).
+///
+/// The file name is reduced to its last path component with control
+/// characters removed. A plain ASCII name is sent as `filename` only;
+/// anything else is sent as an RFC 8187 `filename*` with a sanitized ASCII
+/// `filename` fallback for older clients.
+///
+/// ```
+/// use serve_static::disposition::ContentDisposition;
+///
+/// let value = ContentDisposition::attachment().filename("docs/中文.txt");
+/// assert_eq!(
+///     value.to_string(),
+///     "attachment; filename=\"download.txt\"; filename*=UTF-8''%E4%B8%AD%E6%96%87.txt"
+/// );
+/// assert_eq!(ContentDisposition::inline().to_string(), "inline");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ContentDisposition {
+	disposition: Disposition,
+	filename: Option<String>,
+}
+
+impl ContentDisposition {
+	/// Creates an `inline` value without a file name.
+	#[must_use]
+	pub fn inline() -> Self {
+		Self::new(Disposition::Inline)
+	}
+
+	/// Creates an `attachment` value without a file name.
+	#[must_use]
+	pub fn attachment() -> Self {
+		Self::new(Disposition::Attachment)
+	}
+
+	/// Creates a value of the given disposition without a file name.
+	#[must_use]
+	pub fn new(disposition: Disposition) -> Self {
+		Self {
+			disposition,
+			filename: None,
+		}
+	}
+
+	/// Sets the file name from a resolved path. Only the last component is
+	/// used; a name that is empty after sanitizing is left out.
+	#[must_use]
+	pub fn filename(mut self, path: impl AsRef<Path>) -> Self {
+		let path = path.as_ref().to_string_lossy();
+		let name = path.rsplit(['/', '\\']).next().unwrap_or_default();
+		let name: String = name.chars().filter(|c| !c.is_control()).collect();
+		let name = name.trim();
+		self.filename = (!name.is_empty() && name != "." && name != "..").then(|| name.to_owned());
+		self
+	}
+
+	/// The disposition type.
+	#[must_use]
+	pub fn disposition(&self) -> Disposition {
+		self.disposition
+	}
+
+	/// The sanitized file name, if any.
+	#[must_use]
+	pub fn name(&self) -> Option<&str> {
+		self.filename.as_deref()
+	}
+
+	/// The ASCII `filename` parameter: the name with anything outside
+	/// printable ASCII, and the `"`, `\` and `%` characters that clients
+	/// treat inconsistently, replaced by `_`. A stem left with no letters
+	/// or digits becomes `download`.
+	#[must_use]
+	pub fn ascii_fallback(&self) -> Option<String> {
+		let name = self.filename.as_deref()?;
+		let ascii: String = name
+			.chars()
+			.map(|c| match c {
+				'"' | '\\' | '%' => '_',
+				' '..='~' => c,
+				_ => '_',
+			})
+			.collect();
+		let (stem, extension) = match ascii.rsplit_once('.') {
+			Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+			_ => (ascii.as_str(), None),
+		};
+		if stem.chars().any(|c| c.is_ascii_alphanumeric()) {
+			return Some(ascii);
+		}
+		Some(match extension {
+			Some(extension) => format!("download.{extension}"),
+			None => "download".to_owned(),
+		})
+	}
+}
+
+impl fmt::Display for ContentDisposition {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self.disposition {
+			Disposition::Inline => "inline",
+			Disposition::Attachment => "attachment",
+		})?;
+		let (Some(name), Some(fallback)) = (&self.filename, self.ascii_fallback()) else {
+			return Ok(());
+		};
+		write!(f, "; filename=\"{fallback}\"")?;
+		if *name != fallback {
+			write!(
+				f,
+				"; filename*=UTF-8''{}",
+				utf8_percent_encode(name, ATTR_CHAR)
+			)?;
+		}
+		Ok(())
+	}
+}
+
+impl From<ContentDisposition> for String {
+	fn from(value: ContentDisposition) -> Self {
+		value.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn without_filename() {
+		assert_eq!(ContentDisposition::inline().to_string(), "inline");
+		assert_eq!(ContentDisposition::attachment().to_string(), "attachment");
+		assert_eq!(
+			ContentDisposition::default().disposition(),
+			Disposition::Inline
+		);
+	}
+
+	#[test]
+	fn ascii_names() {
+		let value = ContentDisposition::inline().filename("/srv/site/report-2024.pdf");
+		assert_eq!(value.name(), Some("report-2024.pdf"));
+		assert_eq!(value.to_string(), "inline; filename=\"report-2024.pdf\"");
+
+		let value = ContentDisposition::attachment().filename("my file (1).txt");
+		assert_eq!(
+			value.to_string(),
+			"attachment; filename=\"my file (1).txt\""
+		);
+	}
+
+	#[test]
+	fn unicode_names() {
+		let value = ContentDisposition::attachment().filename("中文.txt");
+		assert_eq!(
+			value.to_string(),
+			"attachment; filename=\"download.txt\"; filename*=UTF-8''%E4%B8%AD%E6%96%87.txt"
+		);
+
+		let value = ContentDisposition::attachment().filename("café menu.pdf");
+		assert_eq!(
+			value.to_string(),
+			"attachment; filename=\"caf_ menu.pdf\"; filename*=UTF-8''caf%C3%A9%20menu.pdf"
+		);
+
+		let value = ContentDisposition::attachment().filename("Ωμέγα");
+		assert_eq!(value.ascii_fallback().as_deref(), Some("download"));
+	}
+
+	#[test]
+	fn hostile_names() {
+		// A backslash is treated as a separator, and `%` is not trusted in
+		// the fallback.
+		let value = ContentDisposition::attachment().filename("a\\b%41.txt");
+		assert_eq!(value.name(), Some("b%41.txt"));
+		assert_eq!(
+			value.to_string(),
+			"attachment; filename=\"b_41.txt\"; filename*=UTF-8''b%2541.txt"
+		);
+
+		let value = ContentDisposition::attachment().filename("evil\r\nSet-Cookie: x=1.txt");
+		let header = value.to_string();
+		assert!(!header.contains(['\r', '\n']));
+		assert_eq!(value.name(), Some("evilSet-Cookie: x=1.txt"));
+
+		let value = ContentDisposition::attachment().filename("say \"hi\".txt");
+		assert_eq!(
+			value.to_string(),
+			"attachment; filename=\"say _hi_.txt\"; filename*=UTF-8''say%20%22hi%22.txt"
+		);
+
+		for empty in ["", "/", "dir/", "..", " \t "] {
+			let value = ContentDisposition::attachment().filename(empty);
+			assert_eq!(value.name(), None, "{empty:?}");
+			assert_eq!(value.to_string(), "attachment");
+		}
+	}
+}
@@ -0,0 +1,947 @@
+/* src/download.rs */
+
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::dir::{Tree, TreeBuilder};
+use crate::error::Error;
+use crate::fs::{FileSystem, StdFs};
+use crate::path;
+use crate::time::DateTime;
+
+/// The container format of a folder download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveFormat {
+	/// POSIX ustar, with PAX headers for long names.
+	Tar,
+	/// ZIP with every entry stored uncompressed.
+	Zip,
+}
+
+impl ArchiveFormat {
+	/// The `Content-Type` header value.
+	#[must_use]
+	pub const fn content_type(self) -> &'static str {
+		match self {
+			Self::Tar => "application/x-tar",
+			Self::Zip => "application/zip",
+		}
+	}
+
+	/// The file extension, without the dot.
+	#[must_use]
+	pub const fn extension(self) -> &'static str {
+		match self {
+			Self::Tar => "tar",
+			Self::Zip => "zip",
+		}
+	}
+}
+
+/// One file or directory in a [`Download`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+	/// Path inside the archive, `/`-separated, with a trailing `/` for
+	/// directories.
+	name: String,
+	/// Where the contents are read from. Unused for directories.
+	source: PathBuf,
+	size: u64,
+	modified: Option<SystemTime>,
+	is_dir: bool,
+}
+
+/// A planned archive of a jailed directory, streamed without temp files.
+///
+/// [`Download::plan`] walks the directory once with a [`TreeBuilder`], so
+/// the same jail, hidden-file, deny, symlink and limit rules apply as for
+/// listings. Only regular files (including symlinks followed to one) are
+/// archived; links that are not followed are left out. Entries sit inside a
+/// folder named after the directory, or at the top level for the root.
+///
+/// Sizes are fixed at planning time, which makes [`Download::len`] exact:
+/// a file that has grown by the time it is streamed is cut off, and one that
+/// has shrunk is padded with zeros. The jail is checked again when each file
+/// is opened, so a file swapped for a symlink leading outside the root after
+/// planning fails the stream instead of leaking the target.
+///
+/// ```
+/// use std::io::Read;
+/// use serve_static::dir::TreeBuilder;
+/// use serve_static::download::{ArchiveFormat, Download};
+/// use serve_static::fs::MemoryFs;
+///
+/// let mut fs = MemoryFs::new();
+/// fs.insert_file("/srv/docs/a.txt", b"hello");
+/// let download = Download::plan_in(&TreeBuilder::new(), &fs, "/srv", "/docs").unwrap();
+/// assert_eq!(download.file_name(ArchiveFormat::Tar), "docs.tar");
+///
+/// let mut body = Vec::new();
+/// download.stream(&fs, ArchiveFormat::Tar).unwrap().read_to_end(&mut body).unwrap();
+/// assert_eq!(Some(body.len() as u64), download.len(ArchiveFormat::Tar));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+	name: String,
+	items: Vec<Item>,
+	truncated: bool,
+	/// The canonical root files must stay inside, or `None` when symlinks
+	/// may leave it.
+	jail: Option<PathBuf>,
+}
+
+impl Download {
+	/// Plans a download of the directory `uri` below `root` on disk.
+	pub fn plan(tree: &TreeBuilder, root: impl AsRef<Path>, uri: &str) -> Result<Self, Error> {
+		Self::plan_in(tree, &StdFs, root, uri)
+	}
+
+	/// Plans a download of the directory `uri` below `root` in `fs`.
+	pub fn plan_in<F: FileSystem + ?Sized>(
+		tree: &TreeBuilder,
+		fs: &F,
+		root: impl AsRef<Path>,
+		uri: &str,
+	) -> Result<Self, Error> {
+		let jail = if tree.allows_symlinks() {
+			None
+		} else {
+			Some(path::canonical_root(fs, root.as_ref())?)
+		};
+		let (tree, dir) = tree.walk_resolved(fs, root.as_ref(), uri)?;
+		let name = tree.entry.name.clone();
+		let mut items = Vec::new();
+		let prefix = if name.is_empty() {
+			String::new()
+		} else {
+			items.push(Item {
+				name: format!("{name}/"),
+				source: dir.clone(),
+				size: 0,
+				modified: tree.entry.modified,
+				is_dir: true,
+			});
+			format!("{name}/")
+		};
+		collect(&tree, &prefix, &dir, &mut items);
+		Ok(Self {
+			name,
+			items,
+			truncated: tree.truncated,
+			jail,
+		})
+	}
+
+	/// The name of the archived directory, empty for the root.
+	#[must_use]
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// A file name for the archive, such as `docs.zip`, or `download.zip`
+	/// for the root.
+	#[must_use]
+	pub fn file_name(&self, format: ArchiveFormat) -> String {
+		let stem = if self.name.is_empty() {
+			"download"
+		} else {
+			&self.name
+		};
+		format!("{stem}.{}", format.extension())
+	}
+
+	/// Whether a depth or entry limit left part of the directory out.
+	#[must_use]
+	pub fn truncated(&self) -> bool {
+		self.truncated
+	}
+
+	/// The number of files and directories in the archive.
+	#[must_use]
+	pub fn entries(&self) -> usize {
+		self.items.len()
+	}
+
+	/// The exact length of the archive in bytes, for `Content-Length`.
+	///
+	/// Returns `None` for a ZIP that would need ZIP64: more than 65535
+	/// entries, or a file or archive of 4 GiB or more.
+	#[must_use]
+	pub fn len(&self, format: ArchiveFormat) -> Option<u64> {
+		match format {
+			ArchiveFormat::Tar => Some(
+				self
+					.items
+					.iter()
+					.map(|item| tar_header(item).len() as u64 + padded(item.size))
+					.sum::<u64>()
+					+ 1024,
+			),
+			ArchiveFormat::Zip => self.zip_len(),
+		}
+	}
+
+	fn zip_len(&self) -> Option<u64> {
+		if self.items.len() > usize::from(u16::MAX) {
+			return None;
+		}
+		let mut local = 0u64;
+		let mut central = 0u64;
+		for item in &self.items {
+			if item.size >= u64::from(u32::MAX) || local >= u64::from(u32::MAX) {
+				return None;
+			}
+			let name = item.name.len() as u64;
+			local += 30 + name + item.size + if item.is_dir { 0 } else { 16 };
+			central += 46 + name;
+		}
+		let total = local + central + 22;
+		(local < u64::from(u32::MAX) && central < u64::from(u32::MAX)).then_some(total)
+	}
+
+	/// Streams the archive, reading files from `fs` as it goes.
+	///
+	/// Fails with [`io::ErrorKind::Unsupported`] when a ZIP would need
+	/// ZIP64, see [`Download::len`]. Errors opening or reading a file
+	/// surface from [`Read::read`], aborting the stream; a file that now
+	/// resolves outside the root fails with
+	/// [`io::ErrorKind::PermissionDenied`].
+	pub fn stream<'a, F: FileSystem + ?Sized>(
+		&'a self,
+		fs: &'a F,
+		format: ArchiveFormat,
+	) -> Result<Stream<'a, F>, Error> {
+		if format == ArchiveFormat::Zip && self.zip_len().is_none() {
+			return Err(Error::Io(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"archive needs ZIP64",
+			)));
+		}
+		Ok(Stream {
+			fs,
+			format,
+			items: &self.items,
+			jail: self.jail.as_deref(),
+			next: 0,
+			buf: Vec::new(),
+			pos: 0,
+			file: None,
+			remaining: 0,
+			crc: Crc32::new(),
+			produced: 0,
+			central: Vec::new(),
+			finished: false,
+		})
+	}
+}
+
+/// Flattens `tree` into archive items below `prefix`.
+fn collect(tree: &Tree, prefix: &str, dir: &Path, items: &mut Vec<Item>) {
+	for child in &tree.children {
+		let entry = &child.entry;
+		let source = dir.join(&entry.name);
+		if entry.is_dir {
+			let name = format!("{prefix}{}/", entry.name);
+			items.push(Item {
+				name: name.clone(),
+				source: source.clone(),
+				size: 0,
+				modified: entry.modified,
+				is_dir: true,
+			});
+			collect(child, &name, &source, items);
+		} else if let Some(size) = entry.size {
+			items.push(Item {
+				name: format!("{prefix}{}", entry.name),
+				source,
+				size,
+				modified: entry.modified,
+				is_dir: false,
+			});
+		}
+	}
+}
+
+/// A [`Download`] being streamed. Implements [`Read`].
+pub struct Stream<'a, F: FileSystem + ?Sized> {
+	fs: &'a F,
+	format: ArchiveFormat,
+	items: &'a [Item],
+	jail: Option<&'a Path>,
+	next: usize,
+	/// Header or trailer bytes waiting to be read.
+	buf: Vec<u8>,
+	pos: usize,
+	file: Option<F::File>,
+	/// File bytes still owed for the current item.
+	remaining: u64,
+	crc: Crc32,
+	/// Bytes emitted so far, for ZIP header offsets.
+	produced: u64,
+	/// ZIP central directory, emitted after the last entry.
+	central: Vec<u8>,
+	finished: bool,
+}
+
+impl<F: FileSystem + ?Sized> std::fmt::Debug for Stream<'_, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Stream")
+			.field("format", &self.format)
+			.field("next", &self.next)
+			.field("produced", &self.produced)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<F: FileSystem + ?Sized> Stream<'_, F> {
+	fn emit(&mut self, bytes: &[u8]) {
+		self.buf.extend_from_slice(bytes);
+		self.produced += bytes.len() as u64;
+	}
+
+	/// Queues the header of the next item, or the archive trailer. Returns
+	/// `false` once everything has been queued.
+	fn advance(&mut self) -> io::Result<bool> {
+		self.buf.clear();
+		self.pos = 0;
+		let Some(item) = self.items.get(self.next) else {
+			if self.finished {
+				return Ok(false);
+			}
+			self.finished = true;
+			match self.format {
+				ArchiveFormat::Tar => self.emit(&[0; 1024]),
+				ArchiveFormat::Zip => {
+					let offset = self.produced;
+					let central = std::mem::take(&mut self.central);
+					self.emit(&central);
+					let count = u16::try_from(self.items.len()).unwrap_or(u16::MAX);
+					let mut end = Vec::with_capacity(22);
+					end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+					end.extend_from_slice(&[0; 4]);
+					end.extend_from_slice(&count.to_le_bytes());
+					end.extend_from_slice(&count.to_le_bytes());
+					end.extend_from_slice(&u32_field(central.len() as u64).to_le_bytes());
+					end.extend_from_slice(&u32_field(offset).to_le_bytes());
+					end.extend_from_slice(&[0; 2]);
+					self.emit(&end);
+				}
+			}
+			return Ok(true);
+		};
+		self.next += 1;
+		self.crc = Crc32::new();
+		self.remaining = item.size;
+		self.file = None;
+
+		match self.format {
+			ArchiveFormat::Tar => {
+				let header = tar_header(item);
+				self.emit(&header);
+			}
+			ArchiveFormat::Zip => {
+				let offset = self.produced;
+				let header = zip_local_header(item);
+				self.emit(&header);
+				if item.is_dir {
+					self.central.extend(zip_central_header(item, 0, offset));
+				} else {
+					// The CRC is only known after streaming; stash the offset.
+					self.central.extend(offset.to_le_bytes());
+				}
+			}
+		}
+		if !item.is_dir && item.size > 0 {
+			self.file = Some(self.open(&item.source)?);
+		} else if !item.is_dir {
+			self.finish_file();
+		}
+		Ok(true)
+	}
+
+	/// Opens a planned file, checking again that it resolves inside the
+	/// jail, since it may have been replaced by a symlink after planning.
+	fn open(&self, source: &Path) -> io::Result<F::File> {
+		let Some(jail) = self.jail else {
+			return self.fs.open(source);
+		};
+		let canonical = self.fs.canonicalize(source)?;
+		if !canonical.starts_with(jail) {
+			return Err(io::Error::new(
+				io::ErrorKind::PermissionDenied,
+				"file resolves outside the root",
+			));
+		}
+		self.fs.open(&canonical)
+	}
+
+	/// Queues what follows a file's contents: tar padding, or the ZIP data
+	/// descriptor and central directory record.
+	fn finish_file(&mut self) {
+		self.file = None;
+		let item = &self.items[self.next - 1];
+		match self.format {
+			ArchiveFormat::Tar => {
+				let pad = (padded(item.size) - item.size) as usize;
+				self.emit(&vec![0; pad]);
+			}
+			ArchiveFormat::Zip => {
+				let crc = self.crc.finish();
+				let mut descriptor = Vec::with_capacity(16);
+				descriptor.extend_from_slice(&0x0807_4b50u32.to_le_bytes());
+				descriptor.extend_from_slice(&crc.to_le_bytes());
+				descriptor.extend_from_slice(&u32_field(item.size).to_le_bytes());
+				descriptor.extend_from_slice(&u32_field(item.size).to_le_bytes());
+				self.emit(&descriptor);
+
+				let at = self.central.len() - 8;
+				let mut offset = [0; 8];
+				offset.copy_from_slice(&self.central[at..]);
+				self.central.truncate(at);
+				let record = zip_central_header(item, crc, u64::from_le_bytes(offset));
+				self.central.extend(record);
+			}
+		}
+	}
+}
+
+impl<F: FileSystem + ?Sized> Read for Stream<'_, F> {
+	fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+		if out.is_empty() {
+			return Ok(0);
+		}
+		loop {
+			if self.pos < self.buf.len() {
+				let n = (self.buf.len() - self.pos).min(out.len());
+				out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+				self.pos += n;
+				return Ok(n);
+			}
+			if let Some(file) = &mut self.file {
+				if self.remaining > 0 {
+					let want = usize::try_from(self.remaining).map_or(out.len(), |r| r.min(out.len()));
+					let mut n = file.read(&mut out[..want])?;
+					if n == 0 {
+						// The file shrank since planning: pad to the promised size.
+						out[..want].fill(0);
+						n = want;
+					}
+					self.crc.update(&out[..n]);
+					self.remaining -= n as u64;
+					self.produced += n as u64;
+					return Ok(n);
+				}
+				self.buf.clear();
+				self.pos = 0;
+				self.finish_file();
+				continue;
+			}
+			if !self.advance()? {
+				return Ok(0);
+			}
+		}
+	}
+}
+
+/// `size` rounded up to whole 512-byte tar blocks.
+fn padded(size: u64) -> u64 {
+	size.div_ceil(512) * 512
+}
+
+fn mtime_secs(modified: Option<SystemTime>) -> u64 {
+	modified
+		.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+		.map_or(0, |d| d.as_secs())
+}
+
+/// The header blocks for `item`: a PAX extended header when the name does
+/// not fit ustar, then the ustar header.
+fn tar_header(item: &Item) -> Vec<u8> {
+	let name = item.name.as_bytes();
+	let split = ustar_split(name);
+	let mut out = Vec::with_capacity(512);
+	if split.is_none() {
+		let record = pax_record("path", &item.name);
+		let mut pax = ustar_block(b"././@PaxHeader", b"", record.len() as u64, 0, b'x', 0o644);
+		pax.extend_from_slice(record.as_bytes());
+		pax.resize(512 + padded(record.len() as u64) as usize, 0);
+		out.extend(pax);
+	}
+	let (prefix, short) = split.unwrap_or((b"", &name[..name.len().min(100)]));
+	let (kind, mode, size) = if item.is_dir {
+		(b'5', 0o755, 0)
+	} else {
+		(b'0', 0o644, item.size)
+	};
+	out.extend(ustar_block(
+		short,
+		prefix,
+		size,
+		mtime_secs(item.modified),
+		kind,
+		mode,
+	));
+	out
+}
+
+/// Splits a name into the ustar `prefix` and `name` fields, if it fits.
+fn ustar_split(name: &[u8]) -> Option<(&[u8], &[u8])> {
+	if name.len() <= 100 {
+		return Some((b"", name));
+	}
+	// Split at a `/` leaving at most 155 bytes before it and 100 after,
+	// ignoring a directory's trailing slash.
+	let body = name.strip_suffix(b"/").unwrap_or(name);
+	(1..body.len())
+		.rev()
+		.filter(|&i| body[i] == b'/')
+		.find(|&i| i <= 155 && name.len() - i - 1 <= 100)
+		.map(|i| (&name[..i], &name[i + 1..]))
+}
+
+/// A PAX `length key=value\n` record, where `length` counts itself.
+fn pax_record(key: &str, value: &str) -> String {
+	let body = key.len() + value.len() + 3;
+	let mut len = body + 1;
+	while len != body + len.to_string().len() {
+		len = body + len.to_string().len();
+	}
+	format!("{len} {key}={value}\n")
+}
+
+/// One 512-byte ustar header block.
+fn ustar_block(name: &[u8], prefix: &[u8], size: u64, mtime: u64, kind: u8, mode: u32) -> Vec<u8> {
+	let mut block = vec![0u8; 512];
+	block[..name.len()].copy_from_slice(name);
+	octal(&mut block[100..108], u64::from(mode));
+	octal(&mut block[108..116], 0);
+	octal(&mut block[116..124], 0);
+	numeric(&mut block[124..136], size);
+	numeric(&mut block[136..148], mtime);
+	block[156] = kind;
+	block[257..263].copy_from_slice(b"ustar\0");
+	block[263..265].copy_from_slice(b"00");
+	block[345..345 + prefix.len()].copy_from_slice(prefix);
+
+	block[148..156].fill(b' ');
+	let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+	octal(&mut block[148..155], u64::from(sum));
+	block
+}
+
+/// Writes `value` as zero-padded octal with a trailing NUL.
+fn octal(field: &mut [u8], value: u64) {
+	let digits = format!("{value:0width$o}", width = field.len() - 1);
+	field[..digits.len()].copy_from_slice(digits.as_bytes());
+	field[digits.len()] = 0;
+}
+
+/// Writes a numeric field as octal, or as GNU base-256 when too large.
+fn numeric(field: &mut [u8], value: u64) {
+	if value < 8u64.pow(field.len() as u32 - 1) {
+		octal(field, value);
+	} else {
+		field.fill(0);
+		let bytes = value.to_be_bytes();
+		let len = field.len();
+		field[len - 8..].copy_from_slice(&bytes);
+		field[0] |= 0x80;
+	}
+}
+
+/// General purpose flags: bit 3 defers the CRC and sizes to a data
+/// descriptor, bit 11 marks names as UTF-8.
+fn zip_flags(item: &Item) -> u16 {
+	if item.is_dir { 0x0800 } else { 0x0808 }
+}
+
+fn zip_local_header(item: &Item) -> Vec<u8> {
+	let (time, date) = dos_time(item.modified);
+	let mut out = Vec::with_capacity(30 + item.name.len());
+	out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+	out.extend_from_slice(&10u16.to_le_bytes());
+	out.extend_from_slice(&zip_flags(item).to_le_bytes());
+	out.extend_from_slice(&0u16.to_le_bytes());
+	out.extend_from_slice(&time.to_le_bytes());
+	out.extend_from_slice(&date.to_le_bytes());
+	// CRC and sizes: zero here, they follow in the data descriptor.
+	out.extend_from_slice(&[0; 12]);
+	out.extend_from_slice(&(item.name.len() as u16).to_le_bytes());
+	out.extend_from_slice(&0u16.to_le_bytes());
+	out.extend_from_slice(item.name.as_bytes());
+	out
+}
+
+fn zip_central_header(item: &Item, crc: u32, offset: u64) -> Vec<u8> {
+	let (time, date) = dos_time(item.modified);
+	let size = u32_field(item.size);
+	let attributes: u32 = if item.is_dir {
+		(0o040_755 << 16) | 0x10
+	} else {
+		0o100_644 << 16
+	};
+	let mut out = Vec::with_capacity(46 + item.name.len());
+	out.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+	// Made by: Unix, spec 2.0; needed to extract: 1.0.
+	out.extend_from_slice(&0x0314u16.to_le_bytes());
+	out.extend_from_slice(&10u16.to_le_bytes());
+	out.extend_from_slice(&zip_flags(item).to_le_bytes());
+	out.extend_from_slice(&0u16.to_le_bytes());
+	out.extend_from_slice(&time.to_le_bytes());
+	out.extend_from_slice(&date.to_le_bytes());
+	out.extend_from_slice(&crc.to_le_bytes());
+	out.extend_from_slice(&size.to_le_bytes());
+	out.extend_from_slice(&size.to_le_bytes());
+	out.extend_from_slice(&(item.name.len() as u16).to_le_bytes());
+	// Extra field, comment, disk number and internal attributes.
+	out.extend_from_slice(&[0; 8]);
+	out.extend_from_slice(&attributes.to_le_bytes());
+	out.extend_from_slice(&u32_field(offset).to_le_bytes());
+	out.extend_from_slice(item.name.as_bytes());
+	out
+}
+
+/// A 32-bit ZIP field. Callers check [`Download::len`] first, so values
+/// never actually saturate.
+fn u32_field(value: u64) -> u32 {
+	u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+/// MS-DOS `(time, date)`, clamped to the 1980 to 2107 range it can hold.
+fn dos_time(modified: Option<SystemTime>) -> (u16, u16) {
+	let dt = DateTime::from_system_time(modified.unwrap_or(UNIX_EPOCH));
+	if dt.year < 1980 {
+		return (0, (1 << 5) | 1);
+	}
+	let year = u16::try_from(dt.year - 1980).unwrap_or(127).min(127);
+	let time = (dt.hour << 11) | (dt.minute << 5) | (dt.second / 2);
+	let date = (u32::from(year) << 9) | (dt.month << 5) | dt.day;
+	(time as u16, date as u16)
+}
+
+/// CRC-32 (IEEE 802.3), as used by ZIP.
+#[derive(Debug, Clone, Copy)]
+struct Crc32(u32);
+
+const CRC_TABLE: [u32; 256] = {
+	let mut table = [0u32; 256];
+	let mut i = 0;
+	while i < 256 {
+		let mut crc = i as u32;
+		let mut bit = 0;
+		while bit < 8 {
+			crc = if crc & 1 == 1 {
+				(crc >> 1) ^ 0xedb8_8320
+			} else {
+				crc >> 1
+			};
+			bit += 1;
+		}
+		table[i] = crc;
+		i += 1;
+	}
+	table
+};
+
+impl Crc32 {
+	fn new() -> Self {
+		Self(0xffff_ffff)
+	}
+
+	fn update(&mut self, data: &[u8]) {
+		for &byte in data {
+			self.0 = CRC_TABLE[((self.0 ^ u32::from(byte)) & 0xff) as usize] ^ (self.0 >> 8);
+		}
+	}
+
+	fn finish(self) -> u32 {
+		!self.0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::dir::ListingBuilder;
+	use crate::fs::MemoryFs;
+	use std::time::Duration;
+
+	fn make_fs() -> MemoryFs {
+		let mut fs = MemoryFs::new();
+		fs.insert_file("/srv/site/index.html", b"<html></html>")
+			.insert_file("/srv/site/.env", b"SECRET=1")
+			.insert_file("/srv/site/notes.tmp", b"scratch")
+			.insert_file("/srv/site/css/app.css", b"body{}")
+			.insert_file("/srv/site/empty.txt", b"")
+			.insert_dir("/srv/site/img")
+			.insert_file("/etc/passwd", b"root:x:0:0")
+			.insert_symlink("/srv/site/passwd", "/etc/passwd")
+			.set_modified(
+				"/srv/site/index.html",
+				UNIX_EPOCH + Duration::from_secs(1_710_505_810),
+			);
+		fs
+	}
+
+	fn plan(fs: &MemoryFs, uri: &str) -> Download {
+		let tree = TreeBuilder::new().listing(ListingBuilder::new().deny("*.tmp"));
+		Download::plan_in(&tree, fs, "/srv", uri).unwrap()
+	}
+
+	fn read_all(download: &Download, fs: &MemoryFs, format: ArchiveFormat) -> Vec<u8> {
+		let mut out = Vec::new();
+		let mut stream = download.stream(fs, format).unwrap();
+		// Small reads exercise every boundary between headers and data.
+		let mut chunk = [0; 7];
+		loop {
+			let n = stream.read(&mut chunk).unwrap();
+			if n == 0 {
+				break;
+			}
+			out.extend_from_slice(&chunk[..n]);
+		}
+		out
+	}
+
+	fn names(download: &Download) -> Vec<&str> {
+		download.items.iter().map(|i| i.name.as_str()).collect()
+	}
+
+	#[test]
+	fn plan_follows_listing_rules() {
+		let fs = make_fs();
+		let download = plan(&fs, "/site");
+		assert_eq!(download.name(), "site");
+		assert_eq!(download.file_name(ArchiveFormat::Zip), "site.zip");
+		assert!(!download.truncated());
+		assert_eq!(
+			names(&download),
+			[
+				"site/",
+				"site/css/",
+				"site/css/app.css",
+				"site/img/",
+				"site/empty.txt",
+				"site/index.html"
+			]
+		);
+		assert_eq!(download.entries(), 6);
+
+		let root = plan(&fs, "/");
+		assert_eq!(root.file_name(ArchiveFormat::Tar), "download.tar");
+		assert_eq!(names(&root)[0], "site/");
+	}
+
+	#[test]
+	fn plan_is_jailed() {
+		let fs = make_fs();
+		let result = Download::plan_in(&TreeBuilder::new(), &fs, "/srv", "/site/passwd/..");
+		assert!(result.is_ok());
+		let escape = Download::plan_in(&TreeBuilder::new(), &fs, "/srv/site", "/passwd");
+		assert!(escape.is_err());
+	}
+
+	#[test]
+	fn tar_layout() {
+		let fs = make_fs();
+		let download = plan(&fs, "/site");
+		let tar = read_all(&download, &fs, ArchiveFormat::Tar);
+		assert_eq!(Some(tar.len() as u64), download.len(ArchiveFormat::Tar));
+		assert_eq!(tar.len() % 512, 0);
+		assert!(tar[tar.len() - 1024..].iter().all(|&b| b == 0));
+
+		// First header: the folder itself.
+		assert_eq!(&tar[..5], b"site/");
+		assert_eq!(tar[156], b'5');
+		assert_eq!(&tar[257..263], b"ustar\0");
+		let sum: u32 = tar[..512]
+			.iter()
+			.enumerate()
+			.map(|(i, &b)| {
+				if (148..156).contains(&i) {
+					32
+				} else {
+					u32::from(b)
+				}
+			})
+			.sum();
+		let stored = u32::from_str_radix(std::str::from_utf8(&tar[148..154]).unwrap(), 8).unwrap();
+		assert_eq!(sum, stored);
+
+		let html = tar.windows(13).position(|w| w == b"<html></html>").unwrap();
+		assert_eq!(html % 512, 0);
+		let header = &tar[html - 512..html];
+		assert_eq!(&header[..15], b"site/index.html");
+		assert_eq!(&header[124..136], b"00000000015\0");
+		assert_eq!(&header[136..148], b"14575037522\0");
+	}
+
+	#[test]
+	fn tar_long_names() {
+		let mut fs = MemoryFs::new();
+		let deep = format!("/srv/{}/{}.txt", "d".repeat(120), "f".repeat(90));
+		let long = format!("/srv/{}.txt", "x".repeat(150));
+		fs.insert_file(&deep, b"deep").insert_file(&long, b"long");
+		let download = Download::plan_in(&TreeBuilder::new(), &fs, "/srv", "/").unwrap();
+		let tar = read_all(&download, &fs, ArchiveFormat::Tar);
+		assert_eq!(Some(tar.len() as u64), download.len(ArchiveFormat::Tar));
+		// The deep path fits the ustar prefix field; the long one needs PAX.
+		let record = pax_record("path", &format!("{}.txt", "x".repeat(150)));
+		assert!(tar.windows(record.len()).any(|w| w == record.as_bytes()));
+		assert!(tar.windows(90).any(|w| w == "f".repeat(90).as_bytes()));
+	}
+
+	#[test]
+	fn pax_record_length_counts_itself() {
+		for len in [1, 90, 94, 95, 96, 995, 996] {
+			let record = pax_record("path", &"a".repeat(len));
+			let (prefix, _) = record.split_once(' ').unwrap();
+			assert_eq!(prefix.parse::<usize>().unwrap(), record.len(), "{len}");
+		}
+	}
+
+	#[test]
+	fn numeric_fields() {
+		let mut field = [0; 12];
+		numeric(&mut field, 0o777);
+		assert_eq!(&field, b"00000000777\0");
+		numeric(&mut field, 10 << 30);
+		assert_eq!(field[0], 0x80);
+		assert_eq!(u64::from_be_bytes(field[4..].try_into().unwrap()), 10 << 30);
+	}
+
+	#[test]
+	fn zip_layout() {
+		let fs = make_fs();
+		let download = plan(&fs, "/site");
+		let zip = read_all(&download, &fs, ArchiveFormat::Zip);
+		assert_eq!(Some(zip.len() as u64), download.len(ArchiveFormat::Zip));
+		assert_eq!(&zip[..4], b"PK\x03\x04");
+
+		let end = &zip[zip.len() - 22..];
+		assert_eq!(&end[..4], b"PK\x05\x06");
+		assert_eq!(u16::from_le_bytes([end[10], end[11]]), 6);
+		let cd_size = u32::from_le_bytes(end[12..16].try_into().unwrap()) as usize;
+		let cd_offset = u32::from_le_bytes(end[16..20].try_into().unwrap()) as usize;
+		assert_eq!(cd_offset + cd_size + 22, zip.len());
+
+		// Every central record points at its local header and carries the
+		// CRC of the stored bytes.
+		let mut at = cd_offset;
+		let mut crcs = Vec::new();
+		while at < cd_offset + cd_size {
+			let record = &zip[at..];
+			assert_eq!(&record[..4], b"PK\x01\x02");
+			let crc = u32::from_le_bytes(record[16..20].try_into().unwrap());
+			let name_len = usize::from(u16::from_le_bytes([record[28], record[29]]));
+			let local = u32::from_le_bytes(record[42..46].try_into().unwrap()) as usize;
+			assert_eq!(&zip[local..local + 4], b"PK\x03\x04");
+			assert_eq!(
+				&zip[local + 30..local + 30 + name_len],
+				&record[46..46 + name_len]
+			);
+			crcs.push((
+				String::from_utf8(record[46..46 + name_len].to_vec()).unwrap(),
+				crc,
+			));
+			at += 46 + name_len;
+		}
+		let mut crc = Crc32::new();
+		crc.update(b"<html></html>");
+		assert!(crcs.contains(&("site/index.html".to_owned(), crc.finish())));
+		assert!(crcs.contains(&("site/empty.txt".to_owned(), 0)));
+	}
+
+	#[test]
+	fn crc32_known_value() {
+		let mut crc = Crc32::new();
+		crc.update(b"123456789");
+		assert_eq!(crc.finish(), 0xcbf4_3926);
+	}
+
+	#[test]
+	fn dos_time_fields() {
+		let (time, date) = dos_time(Some(UNIX_EPOCH + Duration::from_secs(1_710_505_810)));
+		assert_eq!(date, (44 << 9) | (3 << 5) | 15);
+		assert_eq!(time, (12 << 11) | (30 << 5) | 5);
+		assert_eq!(dos_time(None), (0, 33));
+	}
+
+	#[test]
+	fn changed_files_keep_planned_length() {
+		let mut fs = make_fs();
+		let download = plan(&fs, "/site");
+		fs.insert_file("/srv/site/index.html", b"<h1>");
+		fs.insert_file("/srv/site/css/app.css", b"body{color:red}");
+		for format in [ArchiveFormat::Tar, ArchiveFormat::Zip] {
+			let body = read_all(&download, &fs, format);
+			assert_eq!(Some(body.len() as u64), download.len(format));
+		}
+	}
+
+	#[test]
+	fn missing_file_fails_stream() {
+		let fs = make_fs();
+		let download = plan(&fs, "/site");
+		let mut other = MemoryFs::new();
+		other.insert_dir("/srv/site");
+		let mut body = Vec::new();
+		let result = download
+			.stream(&other, ArchiveFormat::Tar)
+			.unwrap()
+			.read_to_end(&mut body);
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn swapped_symlink_fails_stream() {
+		let mut fs = make_fs();
+		let download = plan(&fs, "/site");
+		fs.insert_symlink("/srv/site/index.html", "/etc/passwd");
+		for format in [ArchiveFormat::Tar, ArchiveFormat::Zip] {
+			let mut body = Vec::new();
+			let err = download
+				.stream(&fs, format)
+				.unwrap()
+				.read_to_end(&mut body)
+				.unwrap_err();
+			assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+			assert!(!body.windows(4).any(|w| w == b"root"));
+		}
+
+		// A link that stays inside the root is still streamed.
+		let mut fs = make_fs();
+		let download = plan(&fs, "/site");
+		fs.insert_symlink("/srv/site/index.html", "empty.txt");
+		let body = read_all(&download, &fs, ArchiveFormat::Tar);
+		assert_eq!(Some(body.len() as u64), download.len(ArchiveFormat::Tar));
+	}
+
+	#[cfg(feature = "archive")]
+	#[test]
+	fn readable_by_tar_and_zip_crates() {
+		let fs = make_fs();
+		let download = plan(&fs, "/site");
+
+		let tar = read_all(&download, &fs, ArchiveFormat::Tar);
+		let mut archive = tar::Archive::new(tar.as_slice());
+		let mut files = Vec::new();
+		for entry in archive.entries().unwrap() {
+			let mut entry = entry.unwrap();
+			let path = entry.path().unwrap().to_string_lossy().into_owned();
+			let mut data = Vec::new();
+			entry.read_to_end(&mut data).unwrap();
+			files.push((path, data));
+		}
+		assert!(files.contains(&("site/css/app.css".to_owned(), b"body{}".to_vec())));
+
+		let zip = read_all(&download, &fs, ArchiveFormat::Zip);
+		let mut archive = zip::ZipArchive::new(io::Cursor::new(zip)).unwrap();
+		assert_eq!(archive.len(), 6);
+		let mut file = archive.by_name("site/index.html").unwrap();
+		let mut data = Vec::new();
+		file.read_to_end(&mut data).unwrap();
+		assert_eq!(data, b"<html></html>");
+	}
+}
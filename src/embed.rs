@@ -0,0 +1,750 @@
+/* src/embed.rs */
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::fs::{DirEntry, FileSystem, FileType, Metadata};
+use crate::listing::{self, Entry};
+use crate::{mime, path};
+
+/// Read access to a tree of static assets that may not live on disk.
+///
+/// Paths are normalized keys as produced by [`path::normalize`]: relative,
+/// `/`-separated, with the root being the empty string.
+pub trait Backend {
+	/// Looks up a file by its normalized path.
+	fn file(&self, path: &str) -> Option<Asset<'_>>;
+
+	/// Lists the direct children of a normalized directory path, ordered
+	/// with [`listing::sort`]. Returns `None` when the directory does not
+	/// exist.
+	fn list(&self, dir: &str) -> Option<Vec<Entry>>;
+
+	/// Normalizes `uri` and looks up the file it names.
+	///
+	/// Returns `Ok(None)` when the path is safe but no file exists, matching
+	/// the 404-is-the-caller's-job contract of [`path::resolve`].
+	fn get(&self, uri: &str) -> Result<Option<Asset<'_>>, Error> {
+		let key = path::normalize(uri)?;
+		Ok(self.file(&key))
+	}
+}
+
+/// A file served from a [`Backend`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Asset<'a> {
+	/// Normalized path of the asset.
+	pub path: &'a str,
+	/// The full file contents.
+	pub data: &'a [u8],
+	/// Last modification time, when known.
+	pub modified: Option<SystemTime>,
+}
+
+impl Asset<'_> {
+	/// File size in bytes.
+	#[must_use]
+	pub fn size(&self) -> u64 {
+		self.data.len() as u64
+	}
+
+	/// Detects the MIME type with [`mime::detect`].
+	#[must_use]
+	pub fn mime(&self) -> mime::MediaType {
+		mime::detect(self.path, self.data)
+	}
+
+	/// Generates a weak ETag.
+	///
+	/// Uses [`mime::etag`] when the modification time is known. Embedded
+	/// assets often have none, so the tag falls back to a hash of the
+	/// contents instead of colliding on size alone.
+	#[must_use]
+	pub fn etag(&self) -> String {
+		match self.modified {
+			Some(modified) => mime::etag(modified, self.size()),
+			None => format!("W/\"{:x}-{:x}\"", fnv1a(self.data), self.size()),
+		}
+	}
+
+	/// Builds the listing entry for this asset, including its MIME type and
+	/// ETag. Embedded assets are always read-only.
+	#[must_use]
+	pub fn entry(&self) -> Entry {
+		Entry::file(
+			self.path.rsplit('/').next().unwrap_or_default(),
+			self.size(),
+		)
+		.with_modified(self.modified)
+		.with_mime(self.mime())
+		.with_etag(self.etag())
+		.with_readonly(true)
+	}
+}
+
+/// 64-bit FNV-1a, used for content-based ETags.
+fn fnv1a(data: &[u8]) -> u64 {
+	data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+		(hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EmbeddedFile {
+	data: Cow<'static, [u8]>,
+	modified: Option<SystemTime>,
+}
+
+/// An in-memory [`Backend`]: a map from normalized path to bytes.
+///
+/// Directories are implicit; any prefix of a stored path is a directory.
+/// Populate it at runtime with [`Embedded::insert`] or [`Embedded::from_dir`],
+/// at compile time with [`embed_assets!`](crate::embed_assets), or from a
+/// build script with [`generate`]. It also implements [`FileSystem`], so the
+/// `*_in` functions of [`path`] and the `dir` and `download` modules can
+/// serve it like a directory on disk.
+///
+/// ```
+/// use serve_static::embed::{Backend, Embedded};
+///
+/// let mut assets = Embedded::new();
+/// assets.insert("/index.html", &b"<html></html>"[..], None);
+/// assets.insert("js/app.js", &b"console.log(1)"[..], None);
+///
+/// let asset = assets.get("/js/../index.html").unwrap().unwrap();
+/// assert_eq!(asset.path, "index.html");
+/// assert_eq!(asset.mime(), "text/html");
+///
+/// let root = assets.list("").unwrap();
+/// assert_eq!(root[0].name, "js");
+/// assert!(root[0].is_dir);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Embedded {
+	files: BTreeMap<String, EmbeddedFile>,
+}
+
+impl Embedded {
+	/// Creates an empty asset store.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Stores a file, replacing any previous contents at the same path.
+	///
+	/// `path` is a file name, not a URI: empty, `.` and `..` segments are
+	/// resolved lexically, but nothing is percent-decoded, so a file named
+	/// `a%2Fb.txt` is served for the URI `/a%252Fb.txt`. Paths that contain
+	/// a NUL byte or resolve to the root are ignored and `false` is returned.
+	pub fn insert(
+		&mut self,
+		path: &str,
+		data: impl Into<Cow<'static, [u8]>>,
+		modified: Option<SystemTime>,
+	) -> bool {
+		let key = store_key(path);
+		if key.is_empty() || key.contains('\0') {
+			return false;
+		}
+		let file = EmbeddedFile {
+			data: data.into(),
+			modified,
+		};
+		self.files.insert(key, file);
+		true
+	}
+
+	/// Loads every regular file below `dir` into memory.
+	///
+	/// Symlinks are followed, but a directory reached again through a link
+	/// and a link whose target is missing are skipped. Entries whose names are not valid UTF-8 yield an
+	/// [`std::io::ErrorKind::InvalidData`] error.
+	pub fn from_dir(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+		let mut assets = Self::new();
+		for (key, file) in walk(dir.as_ref())? {
+			let modified = std::fs::metadata(&file)?.modified().ok();
+			assets.insert(&key, std::fs::read(&file)?, modified);
+		}
+		Ok(assets)
+	}
+
+	/// Number of stored files.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.files.len()
+	}
+
+	/// Whether the store holds no files.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.files.is_empty()
+	}
+
+	/// Normalized paths of all stored files, in lexicographic order.
+	pub fn paths(&self) -> impl Iterator<Item = &str> {
+		self.files.keys().map(String::as_str)
+	}
+
+	/// Whether `dir` is an implicit directory of this store.
+	#[must_use]
+	pub fn is_dir(&self, dir: &str) -> bool {
+		if dir.is_empty() {
+			return true;
+		}
+		let prefix = format!("{dir}/");
+		self
+			.files
+			.range(prefix.clone()..)
+			.next()
+			.is_some_and(|(key, _)| key.starts_with(&prefix))
+	}
+}
+
+impl Backend for Embedded {
+	fn file(&self, path: &str) -> Option<Asset<'_>> {
+		self.files.get_key_value(path).map(|(key, file)| Asset {
+			path: key,
+			data: &file.data,
+			modified: file.modified,
+		})
+	}
+
+	fn list(&self, dir: &str) -> Option<Vec<Entry>> {
+		if !self.is_dir(dir) {
+			return None;
+		}
+		Some(listing::children(&self.files, dir, |name, file| {
+			Asset {
+				path: name,
+				data: &file.data,
+				modified: file.modified,
+			}
+			.entry()
+		}))
+	}
+}
+
+/// Serves the store as a read-only filesystem rooted at `/`, so path
+/// resolution, directory listings and downloads work on embedded assets.
+///
+/// Stored paths appear as absolute paths (`js/app.js` is `/js/app.js`) and
+/// implicit directories as directories. There are no symlinks, and every
+/// node is read-only.
+impl FileSystem for Embedded {
+	type File = io::Cursor<Cow<'static, [u8]>>;
+	type ReadDir = std::vec::IntoIter<io::Result<DirEntry>>;
+
+	fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+		let key = fs_key(path)?;
+		let (file_type, len, modified) = match self.files.get(&key) {
+			Some(file) => (FileType::File, file.data.len() as u64, file.modified),
+			None if self.is_dir(&key) => (FileType::Dir, 0, None),
+			None => return Err(io::ErrorKind::NotFound.into()),
+		};
+		Ok(Metadata {
+			file_type,
+			len,
+			modified,
+			created: None,
+			readonly: true,
+		})
+	}
+
+	fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+		self.metadata(path)
+	}
+
+	fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+		let key = fs_key(path)?;
+		if !self.is_dir(&key) {
+			return Err(io::Error::other("not a directory"));
+		}
+		let children = listing::children(&self.files, &key, |name, _| Entry::file(name, 0));
+		Ok(
+			children
+				.into_iter()
+				.map(|entry| {
+					Ok(DirEntry {
+						name: entry.name.into(),
+						file_type: if entry.is_dir {
+							FileType::Dir
+						} else {
+							FileType::File
+						},
+					})
+				})
+				.collect::<Vec<_>>()
+				.into_iter(),
+		)
+	}
+
+	fn open(&self, path: &Path) -> io::Result<Self::File> {
+		match self.files.get(&fs_key(path)?) {
+			Some(file) => Ok(io::Cursor::new(file.data.clone())),
+			None => Err(io::ErrorKind::NotFound.into()),
+		}
+	}
+
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+		let key = fs_key(path)?;
+		if !self.files.contains_key(&key) && !self.is_dir(&key) {
+			return Err(io::ErrorKind::NotFound.into());
+		}
+		Ok(Path::new("/").join(key))
+	}
+
+	fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+		Err(io::Error::new(io::ErrorKind::InvalidInput, "not a symlink"))
+	}
+}
+
+/// Resolves empty, `.` and `..` segments of a file name lexically, keeping
+/// everything else literal.
+fn store_key(path: &str) -> String {
+	let mut segments: Vec<&str> = Vec::new();
+	for segment in path.split('/') {
+		match segment {
+			"" | "." => {}
+			".." => {
+				segments.pop();
+			}
+			_ => segments.push(segment),
+		}
+	}
+	segments.join("/")
+}
+
+/// Maps an absolute filesystem path to a store key, applying `..`
+/// lexically since the store has no symlinks.
+fn fs_key(path: &Path) -> io::Result<String> {
+	if !path.is_absolute() {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			"embedded paths must be absolute",
+		));
+	}
+	let mut segments = Vec::new();
+	for component in path.components() {
+		match component {
+			Component::Normal(name) => {
+				segments.push(name.to_str().ok_or(io::ErrorKind::NotFound)?);
+			}
+			Component::ParentDir => {
+				segments.pop();
+			}
+			Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+		}
+	}
+	Ok(segments.join("/"))
+}
+
+/// Embeds files into the binary as an [`Embedded`] store.
+///
+/// Each entry maps a URL path to a file path, which `include_bytes!`
+/// resolves relative to the invoking source file.
+///
+/// ```ignore
+/// let assets = serve_static::embed_assets! {
+///     "index.html" => "../ui/index.html",
+///     "js/app.js" => "../ui/js/app.js",
+/// };
+/// ```
+#[macro_export]
+macro_rules! embed_assets {
+	($($path:expr => $file:expr),* $(,)?) => {{
+		let mut assets = $crate::embed::Embedded::new();
+		$(
+			assets.insert($path, &include_bytes!($file)[..], None);
+		)*
+		assets
+	}};
+}
+
+/// Generates Rust source that embeds every file below `dir`.
+///
+/// Intended for build scripts: write the output to a file in `OUT_DIR` and
+/// `include!` it where an [`Embedded`] value is expected. Modification times
+/// are preserved to the nanosecond, so [`Asset::etag`] stays stable between
+/// builds of unchanged files and matches [`Embedded::from_dir`].
+///
+/// ```no_run
+/// // build.rs
+/// let code = serve_static::embed::generate("ui").unwrap();
+/// let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("assets.rs");
+/// std::fs::write(out, code).unwrap();
+/// println!("cargo:rerun-if-changed=ui");
+///
+/// // src/main.rs
+/// // let assets = include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+/// ```
+pub fn generate(dir: impl AsRef<Path>) -> std::io::Result<String> {
+	let dir = dir.as_ref().canonicalize()?;
+	let mut code = String::from("{\n\tlet mut assets = ::serve_static::embed::Embedded::new();\n");
+	for (key, file) in walk(&dir)? {
+		let modified = std::fs::metadata(&file)?
+			.modified()
+			.ok()
+			.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+			.map_or_else(
+				|| "None".to_owned(),
+				|d| {
+					format!(
+						"Some(::std::time::UNIX_EPOCH + ::std::time::Duration::new({}, {}))",
+						d.as_secs(),
+						d.subsec_nanos()
+					)
+				},
+			);
+		let file = file.to_str().ok_or_else(|| invalid_name(&file))?;
+		let _ = writeln!(
+			code,
+			"\tassets.insert({key:?}, &include_bytes!({file:?})[..], {modified});"
+		);
+	}
+	code.push_str("\tassets\n}\n");
+	Ok(code)
+}
+
+/// Recursively collects `(store key, file path)` pairs in sorted order.
+///
+/// Each directory is read at most once, identified by its canonical path,
+/// so symlink loops are skipped rather than followed until the path gets
+/// too long.
+fn walk(root: &Path) -> std::io::Result<Vec<(String, std::path::PathBuf)>> {
+	let mut files = Vec::new();
+	let mut visited = HashSet::new();
+	let mut pending = vec![(String::new(), root.to_path_buf())];
+	while let Some((prefix, dir)) = pending.pop() {
+		if !visited.insert(dir.canonicalize()?) {
+			continue;
+		}
+		for entry in std::fs::read_dir(&dir)? {
+			let entry = entry?;
+			let path = entry.path();
+			let name = entry
+				.file_name()
+				.into_string()
+				.map_err(|_| invalid_name(&path))?;
+			let key = if prefix.is_empty() {
+				name
+			} else {
+				format!("{prefix}/{name}")
+			};
+			let mut meta = std::fs::symlink_metadata(&path)?;
+			if meta.is_symlink() {
+				// A dangling link or one that loops on itself has nothing to embed.
+				let Ok(target) = std::fs::metadata(&path) else {
+					continue;
+				};
+				meta = target;
+			}
+			if meta.is_dir() {
+				pending.push((key, path));
+			} else if meta.is_file() {
+				files.push((key, path));
+			}
+		}
+	}
+	files.sort();
+	Ok(files)
+}
+
+fn invalid_name(path: &Path) -> std::io::Error {
+	std::io::Error::new(
+		std::io::ErrorKind::InvalidData,
+		format!("non UTF-8 file name: {}", path.display()),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn from_unix_secs(secs: u64) -> SystemTime {
+		UNIX_EPOCH + Duration::from_secs(secs)
+	}
+
+	fn make_assets() -> Embedded {
+		let mut assets = Embedded::new();
+		assets.insert("/index.html", &b"<html></html>"[..], None);
+		assets.insert("css/site.css", &b"body {}"[..], Some(from_unix_secs(100)));
+		assets.insert("js/app.js", &b"console.log(1)"[..], None);
+		assets.insert("js/vendor/lib.js", b"lib".to_vec(), None);
+		assets.insert("README", &b"plain text"[..], None);
+		assets
+	}
+
+	#[test]
+	fn get_normalizes_uri() {
+		let assets = make_assets();
+		let asset = assets.get("/js/../../css//site.css").unwrap().unwrap();
+		assert_eq!(asset.path, "css/site.css");
+		assert_eq!(asset.data, b"body {}");
+	}
+
+	#[test]
+	fn missing_asset_is_none() {
+		let assets = make_assets();
+		assert!(assets.get("/missing.txt").unwrap().is_none());
+		// Directories are not files.
+		assert!(assets.get("/js").unwrap().is_none());
+	}
+
+	#[test]
+	fn get_rejects_null_byte() {
+		let assets = make_assets();
+		assert!(matches!(assets.get("/index%00.html"), Err(Error::NullByte)));
+	}
+
+	#[test]
+	fn insert_rejects_root() {
+		let mut assets = Embedded::new();
+		assert!(!assets.insert("/", &b"x"[..], None));
+		assert!(!assets.insert("/..", &b"x"[..], None));
+		assert!(!assets.insert("a\0b", &b"x"[..], None));
+		assert!(assets.is_empty());
+	}
+
+	#[test]
+	fn insert_replaces() {
+		let mut assets = make_assets();
+		let before = assets.len();
+		assets.insert("index.html", &b"new"[..], None);
+		assert_eq!(assets.len(), before);
+		assert_eq!(assets.file("index.html").unwrap().data, b"new");
+	}
+
+	#[test]
+	#[cfg(feature = "extension")]
+	fn mime_by_extension() {
+		let assets = make_assets();
+		assert_eq!(assets.file("css/site.css").unwrap().mime(), "text/css");
+	}
+
+	#[test]
+	fn mime_by_content() {
+		let assets = make_assets();
+		assert_eq!(assets.file("README").unwrap().mime(), "text/plain");
+	}
+
+	#[test]
+	fn etag_uses_mtime_when_known() {
+		let assets = make_assets();
+		let asset = assets.file("css/site.css").unwrap();
+		assert_eq!(asset.etag(), mime::etag(from_unix_secs(100), 7));
+	}
+
+	#[test]
+	fn etag_hashes_content_without_mtime() {
+		let mut assets = Embedded::new();
+		assets.insert("a.txt", &b"aaaa"[..], None);
+		assets.insert("b.txt", &b"bbbb"[..], None);
+		let a = assets.file("a.txt").unwrap().etag();
+		let b = assets.file("b.txt").unwrap().etag();
+		assert!(a.starts_with("W/\""));
+		assert_ne!(a, b);
+	}
+
+	#[test]
+	fn list_root() {
+		let assets = make_assets();
+		let names: Vec<_> = assets
+			.list("")
+			.unwrap()
+			.into_iter()
+			.map(|e| (e.name, e.is_dir))
+			.collect();
+		assert_eq!(
+			names,
+			[
+				("css".to_owned(), true),
+				("js".to_owned(), true),
+				("index.html".to_owned(), false),
+				("README".to_owned(), false),
+			]
+		);
+	}
+
+	#[test]
+	fn list_nested() {
+		let assets = make_assets();
+		let entries = assets.list("js").unwrap();
+		assert_eq!(entries.len(), 2);
+		assert_eq!(entries[0].name, "vendor");
+		assert!(entries[0].is_dir);
+		assert_eq!(entries[1].name, "app.js");
+		assert_eq!(entries[1].size, Some(14));
+	}
+
+	#[test]
+	fn list_missing_or_file() {
+		let assets = make_assets();
+		assert!(assets.list("nope").is_none());
+		assert!(assets.list("index.html").is_none());
+		// A sibling sharing a name prefix is not a child.
+		assert!(assets.list("j").is_none());
+	}
+
+	#[test]
+	fn asset_entry() {
+		let assets = make_assets();
+		let entry = assets.file("js/vendor/lib.js").unwrap().entry();
+		assert_eq!(entry.name, "lib.js");
+		assert_eq!(entry.size, Some(3));
+	}
+
+	#[test]
+	fn filesystem_view() {
+		let assets = make_assets();
+		let path = path::resolve_in(&assets, "/", "/js/../css/site.css", false).unwrap();
+		assert_eq!(path, Path::new("/css/site.css"));
+
+		let meta = assets.metadata(&path).unwrap();
+		assert!(meta.is_file());
+		assert_eq!(meta.len, 7);
+		assert_eq!(meta.modified, Some(from_unix_secs(100)));
+		assert!(meta.readonly);
+		assert!(assets.metadata(Path::new("/js/vendor")).unwrap().is_dir());
+		assert!(assets.metadata(Path::new("/")).unwrap().is_dir());
+
+		let mut body = String::new();
+		io::Read::read_to_string(&mut assets.open(&path).unwrap(), &mut body).unwrap();
+		assert_eq!(body, "body {}");
+
+		let children: Vec<_> = assets
+			.read_dir(Path::new("/js"))
+			.unwrap()
+			.map(|child| {
+				let child = child.unwrap();
+				(child.name.into_string().unwrap(), child.file_type)
+			})
+			.collect();
+		assert_eq!(
+			children,
+			[
+				("vendor".to_owned(), FileType::Dir),
+				("app.js".to_owned(), FileType::File),
+			]
+		);
+	}
+
+	#[test]
+	fn filesystem_errors() {
+		let assets = make_assets();
+		let kind = |result: io::Result<Metadata>| result.unwrap_err().kind();
+		assert_eq!(
+			kind(assets.metadata(Path::new("/missing"))),
+			io::ErrorKind::NotFound
+		);
+		assert_eq!(
+			kind(assets.metadata(Path::new("index.html"))),
+			io::ErrorKind::InvalidInput
+		);
+		assert!(assets.open(Path::new("/js")).is_err());
+		assert!(assets.read_dir(Path::new("/index.html")).is_err());
+		assert!(assets.canonicalize(Path::new("/j")).is_err());
+		assert!(assets.read_link(Path::new("/index.html")).is_err());
+		// Like on disk, a missing file resolves and the 404 is left to the caller.
+		let missing = path::resolve_in(&assets, "/", "/missing.txt", false).unwrap();
+		assert_eq!(missing, Path::new("/missing.txt"));
+	}
+
+	#[test]
+	#[cfg(feature = "dir")]
+	fn directory_listing() {
+		let assets = make_assets();
+		let listing = crate::dir::ListingBuilder::new()
+			.read_in(&assets, "/", "/js/")
+			.unwrap();
+		let names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+		assert_eq!(names, ["vendor", "app.js"]);
+		assert_eq!(listing.entries[1].size, Some(14));
+		assert_eq!(listing.entries[1].readonly, Some(true));
+	}
+
+	#[test]
+	fn macro_embeds_files() {
+		let assets = crate::embed_assets! {
+			"lib.rs" => "lib.rs",
+			"/src/embed.rs" => "embed.rs",
+		};
+		assert_eq!(assets.len(), 2);
+		assert!(assets.file("src/embed.rs").is_some());
+	}
+
+	#[test]
+	fn from_dir_and_generate() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir_all(dir.path().join("css")).unwrap();
+		std::fs::write(dir.path().join("index.html"), b"<html>").unwrap();
+		std::fs::write(dir.path().join("css/site.css"), b"body").unwrap();
+
+		let assets = Embedded::from_dir(dir.path()).unwrap();
+		let paths: Vec<_> = assets.paths().collect();
+		assert_eq!(paths, ["css/site.css", "index.html"]);
+		assert!(assets.file("index.html").unwrap().modified.is_some());
+
+		let code = generate(dir.path()).unwrap();
+		assert!(code.contains("assets.insert(\"css/site.css\", &include_bytes!("));
+		let modified = assets.file("index.html").unwrap().modified.unwrap();
+		let since = modified.duration_since(UNIX_EPOCH).unwrap();
+		let expected = format!(
+			"Duration::new({}, {})",
+			since.as_secs(),
+			since.subsec_nanos()
+		);
+		assert!(code.contains(&expected));
+		assert!(code.trim_end().ends_with('}'));
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn from_dir_skips_symlink_loops() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::create_dir_all(dir.path().join("a")).unwrap();
+		std::fs::write(dir.path().join("a/file.txt"), b"x").unwrap();
+		std::os::unix::fs::symlink("..", dir.path().join("a/up")).unwrap();
+
+		let assets = Embedded::from_dir(dir.path()).unwrap();
+		let paths: Vec<_> = assets.paths().collect();
+		assert_eq!(paths, ["a/file.txt"]);
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn from_dir_skips_dangling_symlinks() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("file.txt"), b"x").unwrap();
+		std::os::unix::fs::symlink("missing.txt", dir.path().join("broken.txt")).unwrap();
+		std::os::unix::fs::symlink("file.txt", dir.path().join("alias.txt")).unwrap();
+
+		let assets = Embedded::from_dir(dir.path()).unwrap();
+		let paths: Vec<_> = assets.paths().collect();
+		assert_eq!(paths, ["alias.txt", "file.txt"]);
+		assert!(generate(dir.path()).is_ok());
+	}
+
+	#[test]
+	fn disk_names_stay_literal() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("a%2Fb.txt"), b"x").unwrap();
+
+		let assets = Embedded::from_dir(dir.path()).unwrap();
+		let paths: Vec<_> = assets.paths().collect();
+		assert_eq!(paths, ["a%2Fb.txt"]);
+		assert!(assets.get("/a%252Fb.txt").unwrap().is_some());
+		assert!(assets.get("/a%2Fb.txt").unwrap().is_none());
+		assert!(
+			generate(dir.path())
+				.unwrap()
+				.contains("assets.insert(\"a%2Fb.txt\"")
+		);
+	}
+}
@@ -28,7 +28,58 @@ pub enum Error {
 	#[error("path traversal detected via symlink")]
 	SymlinkTraversal,
 
+	/// Strict path resolution rejected the URI instead of normalizing it.
+	#[error("URI rejected by strict resolution: {0}")]
+	StrictViolation(StrictViolation),
+
+	/// No mount prefix matches the requested URI.
+	#[error("no mount matches URI")]
+	NoMount,
+
+	/// An archive could not be opened or indexed.
+	#[error("invalid archive '{path}': {source}")]
+	InvalidArchive {
+		/// The archive file.
+		path: PathBuf,
+		/// The underlying I/O or format error.
+		source: std::io::Error,
+	},
+
+	/// A media type could not be parsed.
+	#[error("invalid media type '{0}'")]
+	InvalidMediaType(String),
+
+	/// A `mime.types` file or nginx `types` block could not be parsed.
+	#[error("invalid MIME types file at line {line}: {message}")]
+	InvalidTypes {
+		/// The 1-based line of the problem.
+		line: usize,
+		/// What was wrong.
+		message: String,
+	},
+
+	/// An I/O error while reading a resolved path.
+	#[error("I/O error: {0}")]
+	Io(std::io::Error),
+
 	/// An I/O error during path resolution that is not NotFound.
 	#[error("path resolution security error: {0}")]
 	SecurityIo(std::io::Error),
 }
+
+/// The reason [`crate::path::resolve_strict`] rejected a URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum StrictViolation {
+	/// A `..` segment would climb above the root directory.
+	#[error("parent segment escapes root")]
+	EscapesRoot,
+	/// A dot segment was hidden behind percent-encoding (e.g. `%2e%2e`).
+	#[error("percent-encoded dot segment")]
+	EncodedDotSegment,
+	/// A literal `.` segment.
+	#[error("current-directory segment")]
+	DotSegment,
+	/// An empty segment, as produced by `//`.
+	#[error("empty segment")]
+	EmptySegment,
+}
@@ -0,0 +1,444 @@
+/* src/format.rs */
+
+use std::fmt::Write as _;
+
+use crate::html;
+use crate::listing::Entry;
+use crate::page::Page;
+use crate::time::DateTime;
+
+/// An output format for directory listings.
+///
+/// Use [`Format::negotiate`] to pick one from a request's `Accept` header,
+/// so a single endpoint can serve both browsers and scripts. Responses that
+/// depend on negotiation should carry `Vary: Accept`.
+///
+/// ```
+/// use serve_static::format::Format;
+/// use serve_static::listing::Entry;
+///
+/// let entries = vec![Entry::file("a.txt", 3)];
+/// let format = Format::negotiate(Some("application/json")).unwrap();
+/// assert_eq!(format, Format::Json);
+/// assert_eq!(format.content_type(), "application/json");
+/// assert_eq!(
+///     format.render(&entries, "docs"),
+///     r#"{"path":"/docs","entries":[{"name":"a.txt","type":"file","size":3,"modified":null}]}"#,
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+	/// An HTML index page, see [`html::Renderer`].
+	Html,
+	/// A JSON document, see [`json`].
+	Json,
+	/// Newline-delimited names, see [`text`].
+	Text,
+}
+
+impl Format {
+	/// All formats, in server preference order.
+	pub const ALL: [Self; 3] = [Self::Html, Self::Json, Self::Text];
+
+	/// The media type without parameters.
+	#[must_use]
+	pub const fn media_type(self) -> &'static str {
+		match self {
+			Self::Html => "text/html",
+			Self::Json => "application/json",
+			Self::Text => "text/plain",
+		}
+	}
+
+	/// The `Content-Type` header value, including a charset for text types.
+	#[must_use]
+	pub const fn content_type(self) -> &'static str {
+		match self {
+			Self::Html => "text/html; charset=utf-8",
+			Self::Json => "application/json",
+			Self::Text => "text/plain; charset=utf-8",
+		}
+	}
+
+	/// Picks the format best matching an `Accept` header value.
+	///
+	/// Each format takes the quality of the most specific media range that
+	/// matches it (`type/subtype` over `type/*` over `*/*`). The highest
+	/// non-zero quality wins; ties go to the earlier entry in
+	/// [`Format::ALL`]. A missing, empty or unparseable header selects
+	/// [`Format::Html`]. Returns `None` when every format is refused, which
+	/// maps to `406 Not Acceptable`.
+	///
+	/// ```
+	/// use serve_static::format::Format;
+	///
+	/// let browser = "text/html,application/xhtml+xml,*/*;q=0.8";
+	/// assert_eq!(Format::negotiate(Some(browser)), Some(Format::Html));
+	/// assert_eq!(Format::negotiate(Some("text/*;q=0.5, text/plain")), Some(Format::Text));
+	/// assert_eq!(Format::negotiate(Some("image/png")), None);
+	/// assert_eq!(Format::negotiate(None), Some(Format::Html));
+	/// ```
+	#[must_use]
+	pub fn negotiate(accept: Option<&str>) -> Option<Self> {
+		let ranges: Vec<MediaRange<'_>> = accept
+			.unwrap_or_default()
+			.split(',')
+			.filter_map(MediaRange::parse)
+			.collect();
+		if ranges.is_empty() {
+			return Some(Self::Html);
+		}
+
+		let mut best: Option<(Self, u16)> = None;
+		for format in Self::ALL {
+			let (kind, subtype) = format.media_type().split_once('/').unwrap_or_default();
+			let q = ranges
+				.iter()
+				.filter_map(|range| range.specificity(kind, subtype).map(|s| (s, range.q)))
+				.max()
+				.map_or(0, |(_, q)| q);
+			if q > 0 && best.is_none_or(|(_, best_q)| q > best_q) {
+				best = Some((format, q));
+			}
+		}
+		best.map(|(format, _)| format)
+	}
+
+	/// Renders `entries` as the listing of `dir` in this format.
+	///
+	/// HTML uses the default [`html::Renderer`]; match on [`Format::Html`]
+	/// to render with a configured one instead.
+	#[must_use]
+	pub fn render(self, entries: &[Entry], dir: &str) -> String {
+		match self {
+			Self::Html => html::render(entries, dir),
+			Self::Json => json(entries, dir),
+			Self::Text => text(entries),
+		}
+	}
+}
+
+/// One parsed element of an `Accept` header.
+struct MediaRange<'a> {
+	kind: &'a str,
+	subtype: &'a str,
+	/// Quality in thousandths, `0..=1000`.
+	q: u16,
+}
+
+impl<'a> MediaRange<'a> {
+	fn parse(text: &'a str) -> Option<Self> {
+		let mut parts = text.split(';');
+		let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+		if kind.is_empty() || subtype.is_empty() || (kind == "*" && subtype != "*") {
+			return None;
+		}
+		let mut q = 1000;
+		for param in parts {
+			if let Some((name, value)) = param.split_once('=')
+				&& name.trim().eq_ignore_ascii_case("q")
+			{
+				q = parse_q(value.trim())?;
+			}
+		}
+		Some(Self { kind, subtype, q })
+	}
+
+	/// How specifically this range matches `kind/subtype`: 2 for an exact
+	/// match, 1 for `kind/*`, 0 for `*/*`.
+	fn specificity(&self, kind: &str, subtype: &str) -> Option<u8> {
+		if self.kind == "*" {
+			Some(0)
+		} else if !self.kind.eq_ignore_ascii_case(kind) {
+			None
+		} else if self.subtype == "*" {
+			Some(1)
+		} else {
+			self.subtype.eq_ignore_ascii_case(subtype).then_some(2)
+		}
+	}
+}
+
+/// Parses a `qvalue` (RFC 9110 section 12.4.2) into thousandths.
+fn parse_q(value: &str) -> Option<u16> {
+	let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+	if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+		return None;
+	}
+	let frac = format!("{frac:0<3}").parse::<u16>().ok()?;
+	match int {
+		"0" => Some(frac),
+		"1" if frac == 0 => Some(1000),
+		_ => None,
+	}
+}
+
+/// Serializes a listing as JSON.
+///
+/// The schema is stable:
+///
+/// ```json
+/// {
+///   "path": "/docs",
+///   "entries": [
+///     {"name": "api", "type": "dir", "size": null, "modified": null},
+///     {"name": "guide.md", "type": "file", "size": 1024, "modified": "2024-03-15T12:30:10Z"}
+///   ]
+/// }
+/// ```
+///
+/// - `path` is `dir` with `.` and `..` clamped, starting with `/`
+/// - `type` is `"dir"` or `"file"`
+/// - `size` is a byte count, or `null` for directories and unknown sizes
+/// - `modified` is an RFC 3339 UTC timestamp, or `null` when unknown
+///
+/// Entries keep the given order. The output is compact, with no whitespace
+/// between tokens.
+#[must_use]
+pub fn json(entries: &[Entry], dir: &str) -> String {
+	let mut out = String::from("{\"path\":");
+	push_json_string(&mut out, &format!("/{}", html::segments(dir).join("/")));
+	push_entries(&mut out, entries);
+	out.push('}');
+	out
+}
+
+/// Serializes one [`Page`] of a listing as JSON.
+///
+/// The schema extends that of [`json`] with the page position:
+///
+/// ```json
+/// {"path": "/logs", "total": 1200, "offset": 100, "next": "61...", "prev": "62...", "entries": []}
+/// ```
+///
+/// - `total` is the number of entries across all pages
+/// - `offset` is the position of the first entry on this page
+/// - `next` and `prev` are [`crate::page::Cursor`] strings, or `null` at
+///   either end
+#[must_use]
+pub fn json_page(page: &Page, dir: &str) -> String {
+	let mut out = String::from("{\"path\":");
+	push_json_string(&mut out, &format!("/{}", html::segments(dir).join("/")));
+	let _ = write!(out, ",\"total\":{},\"offset\":{}", page.total, page.offset);
+	for (key, cursor) in [("next", &page.next), ("prev", &page.prev)] {
+		let _ = write!(out, ",\"{key}\":");
+		match cursor {
+			Some(cursor) => push_json_string(&mut out, cursor.as_str()),
+			None => out.push_str("null"),
+		}
+	}
+	push_entries(&mut out, &page.entries);
+	out.push('}');
+	out
+}
+
+/// Appends the `"entries"` member shared by [`json`] and [`json_page`].
+fn push_entries(out: &mut String, entries: &[Entry]) {
+	out.push_str(",\"entries\":[");
+	for (i, entry) in entries.iter().enumerate() {
+		if i > 0 {
+			out.push(',');
+		}
+		out.push_str("{\"name\":");
+		push_json_string(out, &entry.name);
+		out.push_str(",\"type\":");
+		out.push_str(if entry.is_dir { "\"dir\"" } else { "\"file\"" });
+		out.push_str(",\"size\":");
+		match entry.size.filter(|_| !entry.is_dir) {
+			Some(size) => {
+				let _ = write!(out, "{size}");
+			}
+			None => out.push_str("null"),
+		}
+		out.push_str(",\"modified\":");
+		match entry.modified {
+			Some(time) => {
+				let _ = write!(out, "\"{}\"", DateTime::from_system_time(time).rfc3339());
+			}
+			None => out.push_str("null"),
+		}
+		out.push('}');
+	}
+	out.push(']');
+}
+
+/// Serializes a listing as plain text, one name per line.
+///
+/// Directory names end with `/`. Backslashes, line feeds and carriage
+/// returns in names are written as `\\`, `\n` and `\r`, so every entry
+/// stays on exactly one line.
+///
+/// ```
+/// use serve_static::listing::Entry;
+///
+/// let entries = vec![Entry::dir("docs"), Entry::file("a\nb.txt", 1)];
+/// assert_eq!(serve_static::format::text(&entries), "docs/\na\\nb.txt\n");
+/// ```
+#[must_use]
+pub fn text(entries: &[Entry]) -> String {
+	let mut out = String::new();
+	for entry in entries {
+		for c in entry.name.chars() {
+			match c {
+				'\\' => out.push_str("\\\\"),
+				'\n' => out.push_str("\\n"),
+				'\r' => out.push_str("\\r"),
+				_ => out.push(c),
+			}
+		}
+		if entry.is_dir {
+			out.push('/');
+		}
+		out.push('\n');
+	}
+	out
+}
+
+/// Appends `value` as a quoted JSON string.
+fn push_json_string(out: &mut String, value: &str) {
+	out.push('"');
+	for c in value.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c < ' ' => {
+				let _ = write!(out, "\\u{:04x}", u32::from(c));
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, UNIX_EPOCH};
+
+	fn file(name: &str, size: u64) -> Entry {
+		Entry::file(name, size)
+	}
+
+	fn dir(name: &str) -> Entry {
+		Entry::dir(name)
+	}
+
+	#[test]
+	fn json_schema() {
+		let mut guide = file("guide.md", 1024);
+		guide.modified = Some(UNIX_EPOCH + Duration::from_secs(1_710_505_810));
+		assert_eq!(
+			json(&[dir("api"), guide], "docs/"),
+			"{\"path\":\"/docs\",\"entries\":[\
+			{\"name\":\"api\",\"type\":\"dir\",\"size\":null,\"modified\":null},\
+			{\"name\":\"guide.md\",\"type\":\"file\",\"size\":1024,\"modified\":\"2024-03-15T12:30:10Z\"}]}"
+		);
+	}
+
+	#[test]
+	fn json_empty_and_clamped_path() {
+		assert_eq!(json(&[], ""), "{\"path\":\"/\",\"entries\":[]}");
+		assert_eq!(json(&[], "../a/./b"), "{\"path\":\"/a/b\",\"entries\":[]}");
+	}
+
+	#[test]
+	fn json_escapes_strings() {
+		let out = json(&[file("a\"b\\c\n\u{1}é.txt", 1)], "");
+		assert!(out.contains("\"name\":\"a\\\"b\\\\c\\n\\u0001é.txt\""));
+	}
+
+	#[test]
+	fn json_page_schema() {
+		use crate::page::Paginator;
+
+		let entries = [file("a", 1), file("b", 2), file("c", 3)];
+		let first = Paginator::new(2).page(&entries);
+		let out = json_page(&first, "logs");
+		let next = first.next.as_ref().unwrap().as_str();
+		assert_eq!(
+			out,
+			format!(
+				"{{\"path\":\"/logs\",\"total\":3,\"offset\":0,\"next\":\"{next}\",\"prev\":null,\"entries\":[\
+				{{\"name\":\"a\",\"type\":\"file\",\"size\":1,\"modified\":null}},\
+				{{\"name\":\"b\",\"type\":\"file\",\"size\":2,\"modified\":null}}]}}"
+			)
+		);
+	}
+
+	#[test]
+	fn text_lines() {
+		assert_eq!(text(&[dir("d"), file("a.txt", 1)]), "d/\na.txt\n");
+		assert_eq!(text(&[]), "");
+		assert_eq!(text(&[file("x\\r\r", 1)]), "x\\\\r\\r\n");
+	}
+
+	#[test]
+	fn negotiate_prefers_quality() {
+		let n = |accept| Format::negotiate(Some(accept));
+		assert_eq!(n("application/json"), Some(Format::Json));
+		assert_eq!(n("text/plain"), Some(Format::Text));
+		assert_eq!(n("text/html;q=0.5, application/json"), Some(Format::Json));
+		assert_eq!(
+			n("text/plain;q=0.9, application/json;q=0.9"),
+			Some(Format::Json)
+		);
+		assert_eq!(n("*/*"), Some(Format::Html));
+		assert_eq!(n("Application/JSON"), Some(Format::Json));
+	}
+
+	#[test]
+	fn negotiate_specific_range_wins() {
+		let n = |accept| Format::negotiate(Some(accept));
+		// text/html is refused explicitly, so the wildcard does not cover it.
+		assert_eq!(n("text/html;q=0, */*"), Some(Format::Json));
+		assert_eq!(n("text/*, text/html;q=0"), Some(Format::Text));
+		assert_eq!(n("*/*;q=0.1, text/plain"), Some(Format::Text));
+	}
+
+	#[test]
+	fn negotiate_refused_and_fallback() {
+		let n = Format::negotiate;
+		assert_eq!(n(Some("image/png")), None);
+		assert_eq!(n(Some("*/*;q=0")), None);
+		assert_eq!(n(None), Some(Format::Html));
+		assert_eq!(n(Some("")), Some(Format::Html));
+		assert_eq!(n(Some("garbage, ;;")), Some(Format::Html));
+	}
+
+	#[test]
+	fn q_values() {
+		assert_eq!(parse_q("1"), Some(1000));
+		assert_eq!(parse_q("1.000"), Some(1000));
+		assert_eq!(parse_q("0.8"), Some(800));
+		assert_eq!(parse_q("0.025"), Some(25));
+		assert_eq!(parse_q("0"), Some(0));
+		assert_eq!(parse_q("1.5"), None);
+		assert_eq!(parse_q("0.1234"), None);
+		assert_eq!(parse_q("2"), None);
+		assert_eq!(parse_q("-0"), None);
+		// Ranges with an invalid quality are ignored.
+		assert_eq!(
+			Format::negotiate(Some("application/json;q=abc, text/plain")),
+			Some(Format::Text)
+		);
+	}
+
+	#[test]
+	fn render_dispatch() {
+		let entries = [file("a.txt", 1)];
+		assert!(
+			Format::Html
+				.render(&entries, "")
+				.starts_with("<!DOCTYPE html>")
+		);
+		assert!(
+			Format::Json
+				.render(&entries, "")
+				.starts_with("{\"path\":\"/\"")
+		);
+		assert_eq!(Format::Text.render(&entries, ""), "a.txt\n");
+	}
+}
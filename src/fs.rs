@@ -0,0 +1,587 @@
+/* src/fs.rs */
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// The kind of a filesystem node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FileType {
+	/// A regular file.
+	#[default]
+	File,
+	/// A directory.
+	Dir,
+	/// A symbolic link (only reported when symlinks are not followed).
+	Symlink,
+	/// Anything else: sockets, FIFOs, device nodes.
+	Other,
+}
+
+impl From<std::fs::FileType> for FileType {
+	fn from(ft: std::fs::FileType) -> Self {
+		if ft.is_symlink() {
+			Self::Symlink
+		} else if ft.is_dir() {
+			Self::Dir
+		} else if ft.is_file() {
+			Self::File
+		} else {
+			Self::Other
+		}
+	}
+}
+
+/// The subset of file metadata that static file serving needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+	/// The kind of node.
+	pub file_type: FileType,
+	/// Size in bytes.
+	pub len: u64,
+	/// Last modification time, when the backend records one.
+	pub modified: Option<SystemTime>,
+	/// Creation time, when the backend records one.
+	pub created: Option<SystemTime>,
+	/// Whether the node lacks write permission.
+	pub readonly: bool,
+}
+
+impl Metadata {
+	/// Whether this is a directory.
+	#[must_use]
+	pub fn is_dir(&self) -> bool {
+		self.file_type == FileType::Dir
+	}
+
+	/// Whether this is a regular file.
+	#[must_use]
+	pub fn is_file(&self) -> bool {
+		self.file_type == FileType::File
+	}
+
+	/// Whether this is a symbolic link.
+	#[must_use]
+	pub fn is_symlink(&self) -> bool {
+		self.file_type == FileType::Symlink
+	}
+}
+
+impl From<std::fs::Metadata> for Metadata {
+	fn from(meta: std::fs::Metadata) -> Self {
+		Self {
+			file_type: meta.file_type().into(),
+			len: meta.len(),
+			modified: meta.modified().ok(),
+			created: meta.created().ok(),
+			readonly: meta.permissions().readonly(),
+		}
+	}
+}
+
+/// A single child returned by [`FileSystem::read_dir`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+	/// File name without any path prefix.
+	pub name: OsString,
+	/// The kind of node, without following symlinks.
+	pub file_type: FileType,
+}
+
+/// The filesystem operations that path resolution and listings rely on.
+///
+/// [`StdFs`] forwards to `std::fs`; [`MemoryFs`] is an in-memory fake with
+/// symlink support for tests. Implement it to serve from archives or
+/// content-addressed stores.
+pub trait FileSystem {
+	/// The handle returned by [`FileSystem::open`].
+	type File: Read + Seek;
+
+	/// The iterator returned by [`FileSystem::read_dir`].
+	///
+	/// Children are yielded lazily so callers can stop early in huge
+	/// directories.
+	type ReadDir: Iterator<Item = io::Result<DirEntry>>;
+
+	/// Queries metadata, following symlinks.
+	fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+	/// Queries metadata without following a final symlink.
+	fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+	/// Lists the children of a directory, in no particular order.
+	fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir>;
+
+	/// Opens a file for reading.
+	fn open(&self, path: &Path) -> io::Result<Self::File>;
+
+	/// Returns the absolute path with every symlink resolved.
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+	/// Reads the target of a symlink.
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+}
+
+/// The default [`FileSystem`], backed by `std::fs`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StdFs;
+
+/// The [`FileSystem::ReadDir`] iterator of [`StdFs`].
+#[derive(Debug)]
+pub struct StdReadDir(std::fs::ReadDir);
+
+impl Iterator for StdReadDir {
+	type Item = io::Result<DirEntry>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let entry = match self.0.next()? {
+			Ok(entry) => entry,
+			Err(e) => return Some(Err(e)),
+		};
+		Some(entry.file_type().map(|file_type| DirEntry {
+			name: entry.file_name(),
+			file_type: file_type.into(),
+		}))
+	}
+}
+
+impl FileSystem for StdFs {
+	type File = std::fs::File;
+	type ReadDir = StdReadDir;
+
+	fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+		std::fs::metadata(path).map(Into::into)
+	}
+
+	fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+		std::fs::symlink_metadata(path).map(Into::into)
+	}
+
+	fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+		std::fs::read_dir(path).map(StdReadDir)
+	}
+
+	fn open(&self, path: &Path) -> io::Result<Self::File> {
+		std::fs::File::open(path)
+	}
+
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+		path.canonicalize()
+	}
+
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+		std::fs::read_link(path)
+	}
+}
+
+/// Maximum symlinks followed while resolving one path, mirroring `ELOOP`.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+	File {
+		data: Vec<u8>,
+		modified: Option<SystemTime>,
+	},
+	Dir {
+		modified: Option<SystemTime>,
+	},
+	Symlink(PathBuf),
+}
+
+/// An in-memory [`FileSystem`] for tests, including symlink simulation.
+///
+/// All paths are absolute. Parent directories are created implicitly, and
+/// symlink targets may be relative (to the link's directory) or absolute,
+/// dangling, or looping, just like on disk.
+///
+/// ```
+/// use std::path::Path;
+/// use serve_static::fs::{FileSystem, MemoryFs};
+///
+/// let mut fs = MemoryFs::new();
+/// fs.insert_file("/srv/index.html", b"<html>");
+/// fs.insert_symlink("/srv/home.html", "index.html");
+///
+/// let canonical = fs.canonicalize(Path::new("/srv/home.html")).unwrap();
+/// assert_eq!(canonical, Path::new("/srv/index.html"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryFs {
+	nodes: BTreeMap<PathBuf, Node>,
+}
+
+impl Default for MemoryFs {
+	fn default() -> Self {
+		let mut nodes = BTreeMap::new();
+		nodes.insert(PathBuf::from("/"), Node::Dir { modified: None });
+		Self { nodes }
+	}
+}
+
+impl MemoryFs {
+	/// Creates a filesystem containing only the root directory.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a file, along with any missing parent directories.
+	pub fn insert_file(&mut self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) -> &mut Self {
+		let node = Node::File {
+			data: data.into(),
+			modified: None,
+		};
+		self.insert(path.as_ref(), node)
+	}
+
+	/// Creates a directory, along with any missing parents.
+	pub fn insert_dir(&mut self, path: impl AsRef<Path>) -> &mut Self {
+		self.insert(path.as_ref(), Node::Dir { modified: None })
+	}
+
+	/// Creates a symlink at `path` pointing to `target`.
+	pub fn insert_symlink(
+		&mut self,
+		path: impl AsRef<Path>,
+		target: impl Into<PathBuf>,
+	) -> &mut Self {
+		self.insert(path.as_ref(), Node::Symlink(target.into()))
+	}
+
+	/// Sets the modification time of an existing file or directory.
+	pub fn set_modified(&mut self, path: impl AsRef<Path>, time: SystemTime) -> &mut Self {
+		if let Some(Node::File { modified, .. } | Node::Dir { modified }) =
+			self.nodes.get_mut(path.as_ref())
+		{
+			*modified = Some(time);
+		}
+		self
+	}
+
+	fn insert(&mut self, path: &Path, node: Node) -> &mut Self {
+		let path = lexical(path);
+		for ancestor in path.ancestors().skip(1) {
+			self
+				.nodes
+				.entry(ancestor.to_path_buf())
+				.or_insert(Node::Dir { modified: None });
+		}
+		self.nodes.insert(path, node);
+		self
+	}
+
+	/// Resolves symlinks in every component, and in the last one only when
+	/// `follow_last` is set.
+	fn resolve(&self, path: &Path, follow_last: bool) -> io::Result<PathBuf> {
+		if !path.is_absolute() {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"MemoryFs paths must be absolute",
+			));
+		}
+
+		// Remaining components, stored in reverse so `pop` yields the next.
+		let mut pending: Vec<OsString> = components(path);
+		pending.reverse();
+		let mut current = PathBuf::from("/");
+		let mut hops = 0;
+
+		while let Some(name) = pending.pop() {
+			if name == ".." {
+				current.pop();
+				continue;
+			}
+			let next = current.join(&name);
+			match self.nodes.get(&next) {
+				None => return Err(io::ErrorKind::NotFound.into()),
+				Some(Node::Symlink(target)) if follow_last || !pending.is_empty() => {
+					hops += 1;
+					if hops > MAX_SYMLINK_HOPS {
+						return Err(io::Error::other("too many levels of symbolic links"));
+					}
+					if target.is_absolute() {
+						current = PathBuf::from("/");
+					}
+					let mut target = components(target);
+					target.reverse();
+					pending.extend(target);
+				}
+				Some(Node::File { .. } | Node::Symlink(_)) if !pending.is_empty() => {
+					return Err(io::ErrorKind::NotFound.into());
+				}
+				Some(_) => current = next,
+			}
+		}
+		Ok(current)
+	}
+
+	fn node(&self, path: &Path, follow_last: bool) -> io::Result<(PathBuf, &Node)> {
+		let resolved = self.resolve(path, follow_last)?;
+		let node = self
+			.nodes
+			.get(&resolved)
+			.ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+		Ok((resolved, node))
+	}
+}
+
+/// Splits a path into normal and `..` components, dropping `/` and `.`.
+fn components(path: &Path) -> Vec<OsString> {
+	path
+		.components()
+		.filter_map(|c| match c {
+			Component::Normal(name) => Some(name.to_os_string()),
+			Component::ParentDir => Some("..".into()),
+			Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+		})
+		.collect()
+}
+
+/// Normalizes an absolute path without resolving symlinks.
+fn lexical(path: &Path) -> PathBuf {
+	let mut out = PathBuf::from("/");
+	for name in components(path) {
+		if name == ".." {
+			out.pop();
+		} else {
+			out.push(name);
+		}
+	}
+	out
+}
+
+fn metadata_of(node: &Node) -> Metadata {
+	match node {
+		Node::File { data, modified } => Metadata {
+			file_type: FileType::File,
+			len: data.len() as u64,
+			modified: *modified,
+			created: None,
+			readonly: false,
+		},
+		Node::Dir { modified } => Metadata {
+			file_type: FileType::Dir,
+			len: 0,
+			modified: *modified,
+			created: None,
+			readonly: false,
+		},
+		Node::Symlink(target) => Metadata {
+			file_type: FileType::Symlink,
+			len: target.as_os_str().len() as u64,
+			modified: None,
+			created: None,
+			readonly: false,
+		},
+	}
+}
+
+fn file_type_of(node: &Node) -> FileType {
+	metadata_of(node).file_type
+}
+
+impl FileSystem for MemoryFs {
+	type File = io::Cursor<Vec<u8>>;
+	type ReadDir = std::vec::IntoIter<io::Result<DirEntry>>;
+
+	fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+		self.node(path, true).map(|(_, node)| metadata_of(node))
+	}
+
+	fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+		self.node(path, false).map(|(_, node)| metadata_of(node))
+	}
+
+	fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+		let (dir, node) = self.node(path, true)?;
+		if !matches!(node, Node::Dir { .. }) {
+			return Err(io::Error::other("not a directory"));
+		}
+		Ok(
+			self
+				.nodes
+				.range(dir.clone()..)
+				.skip(1)
+				.take_while(|(p, _)| p.starts_with(&dir))
+				.filter(|(p, _)| p.parent() == Some(dir.as_path()))
+				.filter_map(|(p, node)| {
+					Some(Ok(DirEntry {
+						name: p.file_name()?.to_os_string(),
+						file_type: file_type_of(node),
+					}))
+				})
+				.collect::<Vec<_>>()
+				.into_iter(),
+		)
+	}
+
+	fn open(&self, path: &Path) -> io::Result<Self::File> {
+		match self.node(path, true)? {
+			(_, Node::File { data, .. }) => Ok(io::Cursor::new(data.clone())),
+			_ => Err(io::Error::other("not a file")),
+		}
+	}
+
+	fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+		self.node(path, true).map(|(resolved, _)| resolved)
+	}
+
+	fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+		match self.node(path, false)? {
+			(_, Node::Symlink(target)) => Ok(target.clone()),
+			_ => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a symlink")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn make_fs() -> MemoryFs {
+		let mut fs = MemoryFs::new();
+		fs.insert_file("/srv/index.html", b"<html>")
+			.insert_file("/srv/assets/app.js", b"js")
+			.insert_file("/etc/passwd", b"root")
+			.insert_symlink("/srv/home.html", "index.html")
+			.insert_symlink("/srv/escape", "/etc")
+			.insert_symlink("/srv/dangling", "missing.txt")
+			.insert_symlink("/srv/loop_a", "loop_b")
+			.insert_symlink("/srv/loop_b", "loop_a");
+		fs
+	}
+
+	fn names(entries: impl Iterator<Item = io::Result<DirEntry>>) -> Vec<(String, FileType)> {
+		let mut entries: Vec<_> = entries.map(Result::unwrap).collect();
+		entries.sort_by(|a, b| a.name.cmp(&b.name));
+		entries
+			.into_iter()
+			.map(|e| (e.name.into_string().unwrap(), e.file_type))
+			.collect()
+	}
+
+	#[test]
+	fn implicit_parents() {
+		let fs = make_fs();
+		assert!(fs.metadata(Path::new("/srv/assets")).unwrap().is_dir());
+		assert!(fs.metadata(Path::new("/")).unwrap().is_dir());
+	}
+
+	#[test]
+	fn file_metadata_and_open() {
+		let mut fs = make_fs();
+		let t = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(5);
+		fs.set_modified("/srv/index.html", t);
+		let meta = fs.metadata(Path::new("/srv/index.html")).unwrap();
+		assert!(meta.is_file());
+		assert_eq!(meta.len, 6);
+		assert_eq!(meta.modified, Some(t));
+
+		let mut buf = String::new();
+		fs.open(Path::new("/srv/index.html"))
+			.unwrap()
+			.read_to_string(&mut buf)
+			.unwrap();
+		assert_eq!(buf, "<html>");
+	}
+
+	#[test]
+	fn relative_symlink_followed() {
+		let fs = make_fs();
+		let canonical = fs.canonicalize(Path::new("/srv/home.html")).unwrap();
+		assert_eq!(canonical, Path::new("/srv/index.html"));
+		assert!(fs.metadata(Path::new("/srv/home.html")).unwrap().is_file());
+		assert!(
+			fs.symlink_metadata(Path::new("/srv/home.html"))
+				.unwrap()
+				.is_symlink()
+		);
+	}
+
+	#[test]
+	fn absolute_symlink_in_middle() {
+		let fs = make_fs();
+		let canonical = fs.canonicalize(Path::new("/srv/escape/passwd")).unwrap();
+		assert_eq!(canonical, Path::new("/etc/passwd"));
+	}
+
+	#[test]
+	fn dangling_and_looping_symlinks() {
+		let fs = make_fs();
+		let err = fs.canonicalize(Path::new("/srv/dangling")).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::NotFound);
+		assert!(fs.symlink_metadata(Path::new("/srv/dangling")).is_ok());
+
+		let err = fs.metadata(Path::new("/srv/loop_a")).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::Other);
+	}
+
+	#[test]
+	fn read_link() {
+		let fs = make_fs();
+		assert_eq!(
+			fs.read_link(Path::new("/srv/escape")).unwrap(),
+			Path::new("/etc")
+		);
+		assert!(fs.read_link(Path::new("/srv/index.html")).is_err());
+	}
+
+	#[test]
+	fn read_dir_direct_children_only() {
+		let fs = make_fs();
+		let entries = names(fs.read_dir(Path::new("/srv")).unwrap());
+		assert_eq!(
+			entries,
+			[
+				("assets".to_owned(), FileType::Dir),
+				("dangling".to_owned(), FileType::Symlink),
+				("escape".to_owned(), FileType::Symlink),
+				("home.html".to_owned(), FileType::Symlink),
+				("index.html".to_owned(), FileType::File),
+				("loop_a".to_owned(), FileType::Symlink),
+				("loop_b".to_owned(), FileType::Symlink),
+			]
+		);
+	}
+
+	#[test]
+	fn read_dir_through_symlink() {
+		let fs = make_fs();
+		let entries = names(fs.read_dir(Path::new("/srv/escape")).unwrap());
+		assert_eq!(entries, [("passwd".to_owned(), FileType::File)]);
+	}
+
+	#[test]
+	fn errors() {
+		let fs = make_fs();
+		assert!(fs.read_dir(Path::new("/srv/index.html")).is_err());
+		assert!(fs.open(Path::new("/srv")).is_err());
+		assert!(fs.metadata(Path::new("relative")).is_err());
+		// A file cannot be traversed as a directory.
+		let err = fs.metadata(Path::new("/srv/index.html/x")).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::NotFound);
+	}
+
+	#[test]
+	fn std_fs_matches_disk() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("a.txt"), b"abc").unwrap();
+		std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+		let meta = StdFs.metadata(&dir.path().join("a.txt")).unwrap();
+		assert!(meta.is_file());
+		assert_eq!(meta.len, 3);
+
+		let entries = names(StdFs.read_dir(dir.path()).unwrap());
+		assert_eq!(
+			entries,
+			[
+				("a.txt".to_owned(), FileType::File),
+				("sub".to_owned(), FileType::Dir),
+			]
+		);
+	}
+}
@@ -0,0 +1,88 @@
+/* src/glob.rs */
+
+/// Matches `text` against a shell-style glob pattern.
+///
+/// Supported syntax:
+/// - `?` matches one character other than `/`
+/// - `*` matches any run of characters other than `/`
+/// - `**` matches any run of characters, including `/`
+///
+/// Everything else matches literally.
+pub(crate) fn matches(pattern: &str, text: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let text: Vec<char> = text.chars().collect();
+
+	// `row[j]` holds whether pattern[..i] matches text[..j].
+	let mut row = vec![false; text.len() + 1];
+	row[0] = true;
+	let mut i = 0;
+	while i < pattern.len() {
+		let mut next = vec![false; text.len() + 1];
+		match pattern[i] {
+			'*' => {
+				let crosses = pattern.get(i + 1) == Some(&'*');
+				next[0] = row[0];
+				for j in 1..=text.len() {
+					next[j] = row[j] || (next[j - 1] && (crosses || text[j - 1] != '/'));
+				}
+				if crosses {
+					i += 1;
+				}
+			}
+			'?' => {
+				for j in 1..=text.len() {
+					next[j] = row[j - 1] && text[j - 1] != '/';
+				}
+			}
+			c => {
+				for j in 1..=text.len() {
+					next[j] = row[j - 1] && text[j - 1] == c;
+				}
+			}
+		}
+		row = next;
+		i += 1;
+	}
+	row[text.len()]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn literal() {
+		assert!(matches("index.html", "index.html"));
+		assert!(!matches("index.html", "index.htm"));
+		assert!(matches("", ""));
+		assert!(!matches("", "a"));
+	}
+
+	#[test]
+	fn star_within_segment() {
+		assert!(matches("*.log", "error.log"));
+		assert!(matches("*.log", ".log"));
+		assert!(!matches("*.log", "logs/error.log"));
+		assert!(matches("app.*.js", "app.3f9a2c.js"));
+	}
+
+	#[test]
+	fn double_star_crosses_segments() {
+		assert!(matches("**.log", "logs/2024/error.log"));
+		assert!(matches("assets/**", "assets/img/logo.png"));
+		assert!(!matches("assets/**", "static/logo.png"));
+	}
+
+	#[test]
+	fn question_mark() {
+		assert!(matches("?.txt", "a.txt"));
+		assert!(!matches("?.txt", "ab.txt"));
+		assert!(!matches("a?b", "a/b"));
+	}
+
+	#[test]
+	fn unicode() {
+		assert!(matches("*.txt", "中文.txt"));
+		assert!(matches("?文.txt", "中文.txt"));
+	}
+}
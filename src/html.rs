@@ -0,0 +1,554 @@
+/* src/html.rs */
+
+use std::fmt::Write as _;
+use std::time::SystemTime;
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+use crate::listing::Entry;
+use crate::page::{Cursor, Page};
+use crate::time::DateTime;
+
+/// Characters percent-encoded in a path segment: everything except the
+/// RFC 3986 unreserved set.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'-')
+	.remove(b'.')
+	.remove(b'_')
+	.remove(b'~');
+
+const DEFAULT_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em}\
+table{border-collapse:collapse}\
+th,td{padding:.2em 1.5em .2em 0;text-align:left}\
+td.size{text-align:right}\
+nav a{text-decoration:none}\
+nav.pages{margin-top:1em}\
+nav.pages a{margin:0 .5em}";
+
+const DEFAULT_TEMPLATE: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{{title}}</title>
+{{head}}
+</head>
+<body>
+<h1>Index of <nav>{{breadcrumbs}}</nav></h1>
+<table>
+<thead><tr><th>Name</th><th>Size</th><th>Modified</th></tr></thead>
+<tbody>
+{{rows}}
+</tbody>
+</table>
+{{pagination}}
+</body>
+</html>
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Stylesheet {
+	Inline(String),
+	Href(String),
+}
+
+/// Renders `&[Entry]` as an HTML directory index.
+///
+/// Every name is HTML-escaped and every link is built from percent-encoded
+/// segments, so filenames such as `<script>.html` or `a#b?.txt` are shown
+/// and linked verbatim. Links are absolute paths below [`Renderer::base`],
+/// and the parent link is omitted at the mount root, so no link ever points
+/// outside the mount.
+///
+/// Entries are rendered in the given order; call [`crate::listing::sort`]
+/// first for the canonical order. [`Renderer::render_page`] renders one
+/// [`Page`] with previous and next links that carry its cursors.
+///
+/// ```
+/// use serve_static::html::Renderer;
+/// use serve_static::listing::Entry;
+///
+/// let entries = vec![Entry::file("<b>.txt", 2048)];
+/// let page = Renderer::new().base("/static").render(&entries, "docs");
+/// assert!(page.contains("&lt;b&gt;.txt"));
+/// assert!(page.contains("href=\"/static/docs/%3Cb%3E.txt\""));
+/// assert!(page.contains("2.0 KiB"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+	base: String,
+	stylesheet: Stylesheet,
+	template: Option<String>,
+	cursor_param: String,
+}
+
+impl Default for Renderer {
+	fn default() -> Self {
+		Self {
+			base: String::new(),
+			stylesheet: Stylesheet::Inline(DEFAULT_STYLE.to_owned()),
+			template: None,
+			cursor_param: "cursor".to_owned(),
+		}
+	}
+}
+
+impl Renderer {
+	/// Creates a renderer with the built-in template and stylesheet, linking
+	/// below `/`.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the URL prefix the listing is served under, such as a mount
+	/// prefix. It is percent-encoded segment by segment.
+	#[must_use]
+	pub fn base(mut self, prefix: &str) -> Self {
+		self.base = encode_path(&segments(prefix));
+		self
+	}
+
+	/// Replaces the built-in stylesheet with inline CSS.
+	///
+	/// The CSS is inserted as is and must come from a trusted source.
+	#[must_use]
+	pub fn stylesheet(mut self, css: impl Into<String>) -> Self {
+		self.stylesheet = Stylesheet::Inline(css.into());
+		self
+	}
+
+	/// Replaces the built-in stylesheet with a `<link>` to an external one.
+	#[must_use]
+	pub fn stylesheet_href(mut self, href: impl Into<String>) -> Self {
+		self.stylesheet = Stylesheet::Href(href.into());
+		self
+	}
+
+	/// Replaces the built-in page template.
+	///
+	/// The template is trusted HTML with these placeholders, each already
+	/// escaped where needed:
+	/// - `{{title}}`: `Index of /dir/`
+	/// - `{{head}}`: the `<style>` or `<link>` element
+	/// - `{{breadcrumbs}}`: links to each ancestor directory
+	/// - `{{rows}}`: one `<tr>` per entry, parent link first
+	/// - `{{pagination}}`: the page links of [`Renderer::render_page`],
+	///   empty for [`Renderer::render`]
+	#[must_use]
+	pub fn template(mut self, template: impl Into<String>) -> Self {
+		self.template = Some(template.into());
+		self
+	}
+
+	/// Sets the query parameter that page links pass the cursor in, `cursor`
+	/// by default. Parse it back with [`Cursor::parse`].
+	#[must_use]
+	pub fn cursor_param(mut self, name: &str) -> Self {
+		self.cursor_param = utf8_percent_encode(name, SEGMENT).to_string();
+		self
+	}
+
+	/// Renders `entries` as the listing of `dir`.
+	///
+	/// `dir` is the decoded directory path relative to the base, as produced
+	/// by [`crate::path::normalize`]. Any `.` or `..` segments are clamped
+	/// the same way.
+	#[must_use]
+	pub fn render(&self, entries: &[Entry], dir: &str) -> String {
+		self.render_with(entries, dir, |_| String::new())
+	}
+
+	/// Renders one page of the listing of `dir`, followed by a `<nav>` with
+	/// the entry range, the total and links to the previous and next pages.
+	///
+	/// ```
+	/// use serve_static::html::Renderer;
+	/// use serve_static::listing::Entry;
+	/// use serve_static::page::Paginator;
+	///
+	/// let entries: Vec<Entry> = (0..5).map(|i| Entry::file(format!("f{i}"), 1)).collect();
+	/// let page = Paginator::new(2).page(&entries);
+	/// let html = Renderer::new().render_page(&page, "logs");
+	/// let next = page.next.unwrap();
+	/// assert!(html.contains(&format!("<a rel=\"next\" href=\"/logs/?cursor={next}\">")));
+	/// assert!(html.contains("1&ndash;2 of 5"));
+	/// ```
+	#[must_use]
+	pub fn render_page(&self, page: &Page, dir: &str) -> String {
+		self.render_with(&page.entries, dir, |dir_href| {
+			let link = |rel: &str, label: &str, cursor: &Cursor| {
+				format!(
+					"<a rel=\"{rel}\" href=\"{dir_href}?{}={cursor}\">{label}</a>",
+					self.cursor_param
+				)
+			};
+			let mut html = String::from("<nav class=\"pages\">");
+			if let Some(prev) = &page.prev {
+				html.push_str(&link("prev", "&larr; Previous", prev));
+			}
+			if page.entries.is_empty() {
+				let _ = write!(html, "<span>0 of {}</span>", page.total);
+			} else {
+				let _ = write!(
+					html,
+					"<span>{}&ndash;{} of {}</span>",
+					page.offset + 1,
+					page.offset + page.entries.len(),
+					page.total
+				);
+			}
+			if let Some(next) = &page.next {
+				html.push_str(&link("next", "Next &rarr;", next));
+			}
+			html.push_str("</nav>");
+			html
+		})
+	}
+
+	/// Renders the page, with `pagination` building the `{{pagination}}`
+	/// value from the encoded directory link.
+	fn render_with(
+		&self,
+		entries: &[Entry],
+		dir: &str,
+		pagination: impl FnOnce(&str) -> String,
+	) -> String {
+		let segments = segments(dir);
+		let dir_href = format!("{}{}/", self.base, encode_path(&segments));
+
+		let mut title = String::from("Index of /");
+		for segment in &segments {
+			title.push_str(segment);
+			title.push('/');
+		}
+
+		let head = match &self.stylesheet {
+			Stylesheet::Inline(css) => format!("<style>{css}</style>"),
+			Stylesheet::Href(href) => format!("<link rel=\"stylesheet\" href=\"{}\">", escape(href)),
+		};
+
+		let mut rows = String::new();
+		if !segments.is_empty() {
+			let parent = encode_path(&segments[..segments.len() - 1]);
+			let _ = writeln!(
+				rows,
+				"<tr><td><a href=\"{}{parent}/\">../</a></td><td class=\"size\">-</td><td>-</td></tr>",
+				self.base
+			);
+		}
+		for entry in entries {
+			let slash = if entry.is_dir { "/" } else { "" };
+			let size = match (entry.is_dir, entry.size) {
+				(false, Some(size)) => format_size(size),
+				_ => "-".to_owned(),
+			};
+			let modified = entry.modified.map_or_else(|| "-".to_owned(), format_time);
+			let _ = writeln!(
+				rows,
+				"<tr><td><a href=\"{dir_href}{}{slash}\">{}{slash}</a></td><td class=\"size\">{size}</td><td>{modified}</td></tr>",
+				utf8_percent_encode(&entry.name, SEGMENT),
+				escape(&entry.name),
+			);
+		}
+
+		fill(
+			self.template.as_deref().unwrap_or(DEFAULT_TEMPLATE),
+			&[
+				("title", &escape(&title)),
+				("head", &head),
+				("breadcrumbs", &self.breadcrumbs(&segments)),
+				("rows", rows.trim_end()),
+				("pagination", &pagination(&dir_href)),
+			],
+		)
+	}
+
+	fn breadcrumbs(&self, segments: &[&str]) -> String {
+		let mut html = format!("<a href=\"{}/\">/</a>", self.base);
+		for depth in 1..=segments.len() {
+			let _ = write!(
+				html,
+				"<a href=\"{}{}/\">{}</a>/",
+				self.base,
+				encode_path(&segments[..depth]),
+				escape(segments[depth - 1]),
+			);
+		}
+		html
+	}
+}
+
+/// Substitutes `{{name}}` placeholders in one pass, so values that happen
+/// to contain placeholder syntax are never expanded again.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+	let mut out = String::with_capacity(template.len());
+	let mut rest = template;
+	while let Some(start) = rest.find("{{") {
+		out.push_str(&rest[..start]);
+		let after = &rest[start + 2..];
+		let value = after.find("}}").and_then(|end| {
+			let key = &after[..end];
+			values
+				.iter()
+				.find(|(name, _)| *name == key)
+				.map(|(_, value)| (*value, end))
+		});
+		if let Some((value, end)) = value {
+			out.push_str(value);
+			rest = &after[end + 2..];
+		} else {
+			out.push_str("{{");
+			rest = after;
+		}
+	}
+	out.push_str(rest);
+	out
+}
+
+/// Renders `entries` as the listing of `dir` with the default [`Renderer`].
+#[must_use]
+pub fn render(entries: &[Entry], dir: &str) -> String {
+	Renderer::new().render(entries, dir)
+}
+
+/// Escapes text for use in HTML element content and quoted attributes.
+///
+/// ```
+/// assert_eq!(serve_static::html::escape("<a href='x'>&"), "&lt;a href=&#39;x&#39;&gt;&amp;");
+/// ```
+#[must_use]
+pub fn escape(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => out.push_str("&amp;"),
+			'<' => out.push_str("&lt;"),
+			'>' => out.push_str("&gt;"),
+			'"' => out.push_str("&quot;"),
+			'\'' => out.push_str("&#39;"),
+			_ => out.push(c),
+		}
+	}
+	out
+}
+
+/// Formats a byte count with binary units and one decimal place.
+///
+/// ```
+/// assert_eq!(serve_static::html::format_size(512), "512 B");
+/// assert_eq!(serve_static::html::format_size(1536), "1.5 KiB");
+/// ```
+#[must_use]
+pub fn format_size(bytes: u64) -> String {
+	const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+	if bytes < 1024 {
+		return format!("{bytes} B");
+	}
+	let mut value = bytes as f64 / 1024.0;
+	let mut unit = 0;
+	while value >= 1024.0 && unit < UNITS.len() - 1 {
+		value /= 1024.0;
+		unit += 1;
+	}
+	format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Formats a modification time as `YYYY-MM-DD HH:MM` in UTC.
+fn format_time(time: SystemTime) -> String {
+	let dt = DateTime::from_system_time(time);
+	format!(
+		"{:04}-{:02}-{:02} {:02}:{:02}",
+		dt.year, dt.month, dt.day, dt.hour, dt.minute
+	)
+}
+
+/// Splits a decoded path into segments, clamping `..` at the root.
+pub(crate) fn segments(path: &str) -> Vec<&str> {
+	let mut segments = Vec::new();
+	for segment in path.split('/') {
+		match segment {
+			"" | "." => {}
+			".." => {
+				segments.pop();
+			}
+			_ => segments.push(segment),
+		}
+	}
+	segments
+}
+
+/// Joins segments into `/a/b`, percent-encoding each one. Empty for none.
+fn encode_path(segments: &[&str]) -> String {
+	let mut out = String::new();
+	for segment in segments {
+		out.push('/');
+		out.extend(utf8_percent_encode(segment, SEGMENT));
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, UNIX_EPOCH};
+
+	fn file(name: &str, size: u64) -> Entry {
+		Entry::file(name, size)
+	}
+
+	fn dir(name: &str) -> Entry {
+		Entry::dir(name)
+	}
+
+	#[test]
+	fn escapes_names() {
+		let page = render(&[file("<script>alert(1)</script>", 1)], "");
+		assert!(!page.contains("<script>"));
+		assert!(page.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+	}
+
+	#[test]
+	fn encodes_hrefs() {
+		let page = render(&[file("a b#c?.txt", 1), file("中文.txt", 1)], "");
+		assert!(page.contains("href=\"/a%20b%23c%3F.txt\""));
+		assert!(page.contains("href=\"/%E4%B8%AD%E6%96%87.txt\""));
+		assert!(page.contains(">中文.txt</a>"));
+	}
+
+	#[test]
+	fn quote_in_name_cannot_break_attribute() {
+		let page = render(&[file("x\" onmouseover=\"alert(1)", 1)], "");
+		assert!(!page.contains("\" onmouseover"));
+		assert!(page.contains("x%22%20onmouseover%3D%22alert%281%29"));
+	}
+
+	#[test]
+	fn directories_get_trailing_slash() {
+		let page = render(&[dir("assets")], "");
+		assert!(page.contains("<a href=\"/assets/\">assets/</a>"));
+	}
+
+	#[test]
+	fn parent_link_only_below_root() {
+		let root = Renderer::new().base("/static").render(&[], "");
+		assert!(!root.contains("../"));
+
+		let nested = Renderer::new().base("/static").render(&[], "docs/api");
+		assert!(nested.contains("<a href=\"/static/docs/\">../</a>"));
+
+		let top = Renderer::new().base("/static").render(&[], "docs");
+		assert!(top.contains("<a href=\"/static/\">../</a>"));
+	}
+
+	#[test]
+	fn traversal_in_dir_is_clamped() {
+		let page = Renderer::new()
+			.base("/static")
+			.render(&[file("x", 1)], "../../etc");
+		assert!(page.contains("href=\"/static/etc/x\""));
+		assert!(page.contains("<a href=\"/static/\">../</a>"));
+		assert!(!page.contains("href=\"/etc"));
+	}
+
+	#[test]
+	fn breadcrumbs() {
+		let page = Renderer::new().base("static/").render(&[], "a b/c");
+		assert!(page.contains(
+			"<nav><a href=\"/static/\">/</a><a href=\"/static/a%20b/\">a b</a>/<a href=\"/static/a%20b/c/\">c</a>/</nav>"
+		));
+		assert!(page.contains("<title>Index of /a b/c/</title>"));
+	}
+
+	#[test]
+	fn title_is_escaped() {
+		let page = render(&[], "<x>");
+		assert!(page.contains("<title>Index of /&lt;x&gt;/</title>"));
+	}
+
+	#[test]
+	fn sizes_and_dates() {
+		let mut entry = file("big.iso", 3 * 1024 * 1024 * 1024);
+		entry.modified = Some(UNIX_EPOCH + Duration::from_secs(1_710_505_810));
+		let page = render(&[entry, dir("d")], "");
+		assert!(page.contains("<td class=\"size\">3.0 GiB</td><td>2024-03-15 12:30</td>"));
+		assert!(page.contains("<td class=\"size\">-</td><td>-</td>"));
+	}
+
+	#[test]
+	fn format_size_units() {
+		assert_eq!(format_size(0), "0 B");
+		assert_eq!(format_size(1023), "1023 B");
+		assert_eq!(format_size(1024), "1.0 KiB");
+		assert_eq!(format_size(10 * 1024 * 1024), "10.0 MiB");
+		assert_eq!(format_size(u64::MAX), "16.0 EiB");
+	}
+
+	#[test]
+	fn custom_template_and_stylesheet() {
+		let page = Renderer::new()
+			.template("<h1>{{title}}</h1>{{head}}<ul>{{rows}}</ul>")
+			.stylesheet_href("/theme.css?v=\"1\"")
+			.render(&[file("a.txt", 1)], "");
+		assert!(page.starts_with(
+			"<h1>Index of /</h1><link rel=\"stylesheet\" href=\"/theme.css?v=&quot;1&quot;\">"
+		));
+		assert!(page.contains("<a href=\"/a.txt\">a.txt</a>"));
+		assert!(!page.contains("<style>"));
+
+		let inline = Renderer::new().stylesheet("body{}").render(&[], "");
+		assert!(inline.contains("<style>body{}</style>"));
+	}
+
+	#[test]
+	fn placeholders_in_names_not_expanded() {
+		let page = render(&[file("{{head}}", 1)], "{{rows}}");
+		assert!(page.contains("<title>Index of /{{rows}}/</title>"));
+		assert!(page.contains(">{{head}}</a>"));
+	}
+
+	#[test]
+	fn unknown_placeholders_kept() {
+		let page = Renderer::new().template("{{nope}} {{title").render(&[], "");
+		assert_eq!(page, "{{nope}} {{title");
+	}
+
+	#[test]
+	fn page_links() {
+		use crate::page::Paginator;
+
+		let entries: Vec<_> = (0..5).map(|i| file(&format!("f{i}"), 1)).collect();
+		let renderer = Renderer::new().base("/static");
+		let first = Paginator::new(2).page(&entries);
+		let html = renderer.render_page(&first, "my logs");
+		let next = first.next.as_ref().unwrap();
+		assert!(html.contains(&format!(
+			"<nav class=\"pages\"><span>1&ndash;2 of 5</span>\
+			<a rel=\"next\" href=\"/static/my%20logs/?cursor={next}\">Next &rarr;</a></nav>"
+		)));
+		assert!(!html.contains("rel=\"prev\""));
+		assert!(html.contains(">f1</a>") && !html.contains(">f2</a>"));
+
+		let last = Paginator::new(2).offset(4).page(&entries);
+		let html = renderer.cursor_param("after").render_page(&last, "");
+		let prev = last.prev.as_ref().unwrap();
+		assert!(html.contains(&format!(
+			"<a rel=\"prev\" href=\"/static/?after={prev}\">&larr; Previous</a>\
+			<span>5&ndash;5 of 5</span></nav>"
+		)));
+		assert!(!html.contains("rel=\"next\""));
+
+		let empty = Paginator::new(2).page(&[]);
+		let html = Renderer::new().render_page(&empty, "");
+		assert!(html.contains("<span>0 of 0</span>"));
+		assert!(!render(&entries, "").contains("<nav class=\"pages\">"));
+	}
+
+	#[test]
+	fn default_page_is_complete() {
+		let page = render(&[file("a.txt", 1)], "");
+		assert!(page.starts_with("<!DOCTYPE html>"));
+		assert!(page.contains("<meta charset=\"utf-8\">"));
+		assert!(!page.contains("{{"));
+	}
+}
@@ -1,15 +1,46 @@
 /* src/lib.rs */
 #![doc = "Headless utilities for static file serving."]
 
+/// Archive-backed roots serving ZIP and tar files.
+#[cfg(feature = "archive")]
+pub mod archive;
+/// `Cache-Control` policies for static assets.
+pub mod cache;
+/// Filesystem-backed directory listing builder.
+#[cfg(feature = "dir")]
+pub mod dir;
+/// `Content-Disposition` header values with RFC 8187 file names.
+pub mod disposition;
+/// Streaming tar and zip downloads of jailed directories.
+#[cfg(feature = "dir")]
+pub mod download;
+/// In-memory asset backend for embedded files.
+pub mod embed;
 /// Unified error types for serve_static.
 pub mod error;
+/// JSON and plain-text listing formats with `Accept` negotiation.
+pub mod format;
+/// Pluggable filesystem abstraction with `std::fs` and in-memory backends.
+pub mod fs;
+mod glob;
+/// HTML directory listing renderer.
+pub mod html;
 /// Directory entry data model and sorting utilities.
 pub mod listing;
 /// MIME type detection and ETag generation.
 pub mod mime;
+/// URL prefix mounts mapping to jailed roots.
+pub mod mount;
+/// Layered roots where the top-most layer containing a file wins.
+pub mod overlay;
+/// Filtering and cursor-based pagination of listing entries.
+pub mod page;
 /// Safe path resolution with directory traversal protection.
 pub mod path;
 /// HTTP Range header parsing (RFC 9110).
 pub mod range;
+/// Status, header and byte region planning for static file responses.
+pub mod response;
+mod time;
 
 pub use error::Error;
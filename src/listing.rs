@@ -1,70 +1,614 @@
 /* src/listing.rs */
 
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
 use std::time::SystemTime;
 
+use crate::fs::FileType;
+
 /// A single directory entry for use in directory listings.
 ///
 /// Callers populate these from their own I/O layer, then pass them
-/// to [`sort`] for canonical ordering.
+/// to [`sort`] for canonical ordering. Entries are built with
+/// [`Entry::file`] or [`Entry::dir`] and the `with_*` setters, or as a
+/// struct literal ending in `..Default::default()`, which leaves the
+/// optional metadata unset.
 ///
 /// ```
-/// let entry = serve_static::listing::Entry {
-///     name: "readme.txt".to_owned(),
-///     is_dir: false,
-///     size: Some(1024),
-///     modified: None,
-/// };
+/// use serve_static::listing::Entry;
+///
+/// let entry = Entry::file("readme.txt", 1024)
+///     .with_mime("text/plain")
+///     .with_readonly(true);
 /// assert!(!entry.is_dir);
+/// assert_eq!(entry.size, Some(1024));
+/// assert_eq!(entry.readonly, Some(true));
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Entry {
 	/// File or directory name without any path prefix.
 	pub name: String,
-	/// Whether this entry is a directory.
+	/// Whether this entry is a directory, or a symlink followed to one.
 	pub is_dir: bool,
 	/// File size in bytes. `None` for directories.
 	pub size: Option<u64>,
 	/// Last modification time.
 	pub modified: Option<SystemTime>,
+	/// The kind of the entry itself. A symlink stays [`FileType::Symlink`]
+	/// even when its target's metadata is reported.
+	pub kind: FileType,
+	/// Where a symlink points, as stored in the link.
+	pub symlink_target: Option<PathBuf>,
+	/// MIME type, as from [`crate::mime::detect`]. `None` for directories.
+	pub mime: Option<String>,
+	/// Entity tag, as from [`crate::mime::etag`]. `None` for directories.
+	pub etag: Option<String>,
+	/// Whether the entry is read-only, when the backend reports it.
+	pub readonly: Option<bool>,
+	/// Creation time, when the backend records one.
+	pub created: Option<SystemTime>,
+}
+
+impl Entry {
+	/// Creates a regular file entry of `size` bytes.
+	#[must_use]
+	pub fn file(name: impl Into<String>, size: u64) -> Self {
+		Self::new(name.into(), FileType::File, Some(size))
+	}
+
+	/// Creates a directory entry.
+	#[must_use]
+	pub fn dir(name: impl Into<String>) -> Self {
+		Self::new(name.into(), FileType::Dir, None)
+	}
+
+	pub(crate) fn new(name: String, kind: FileType, size: Option<u64>) -> Self {
+		Self {
+			name,
+			is_dir: kind == FileType::Dir,
+			size,
+			modified: None,
+			kind,
+			symlink_target: None,
+			mime: None,
+			etag: None,
+			readonly: None,
+			created: None,
+		}
+	}
+
+	/// Sets whether the entry is listed as a directory.
+	#[must_use]
+	pub fn with_is_dir(mut self, is_dir: bool) -> Self {
+		self.is_dir = is_dir;
+		self
+	}
+
+	/// Sets the size in bytes.
+	#[must_use]
+	pub fn with_size(mut self, size: Option<u64>) -> Self {
+		self.size = size;
+		self
+	}
+
+	/// Sets the last modification time.
+	#[must_use]
+	pub fn with_modified(mut self, modified: Option<SystemTime>) -> Self {
+		self.modified = modified;
+		self
+	}
+
+	/// Sets the kind of the entry itself, without changing `is_dir`.
+	#[must_use]
+	pub fn with_kind(mut self, kind: FileType) -> Self {
+		self.kind = kind;
+		self
+	}
+
+	/// Marks the entry as a symlink pointing at `target`.
+	#[must_use]
+	pub fn with_symlink_target(mut self, target: impl Into<PathBuf>) -> Self {
+		self.kind = FileType::Symlink;
+		self.symlink_target = Some(target.into());
+		self
+	}
+
+	/// Sets the MIME type.
+	#[must_use]
+	pub fn with_mime(mut self, mime: impl Into<String>) -> Self {
+		self.mime = Some(mime.into());
+		self
+	}
+
+	/// Sets the entity tag.
+	#[must_use]
+	pub fn with_etag(mut self, etag: impl Into<String>) -> Self {
+		self.etag = Some(etag.into());
+		self
+	}
+
+	/// Sets whether the entry is read-only.
+	#[must_use]
+	pub fn with_readonly(mut self, readonly: bool) -> Self {
+		self.readonly = Some(readonly);
+		self
+	}
+
+	/// Sets the creation time.
+	#[must_use]
+	pub fn with_created(mut self, created: Option<SystemTime>) -> Self {
+		self.created = created;
+		self
+	}
 }
 
 /// Sorts entries with directories first, then alphabetically (case-insensitive).
 ///
+/// Equivalent to [`sort_by`] with [`Sort::default`].
+///
 /// ```
 /// use serve_static::listing::{Entry, sort};
 ///
-/// let mut entries = vec![
-///     Entry { name: "z.txt".to_owned(), is_dir: false, size: Some(10), modified: None },
-///     Entry { name: "a_dir".to_owned(), is_dir: true, size: None, modified: None },
-/// ];
+/// let mut entries = vec![Entry::file("z.txt", 10), Entry::dir("a_dir")];
 /// sort(&mut entries);
 /// assert!(entries[0].is_dir);
 /// assert_eq!(entries[0].name, "a_dir");
 /// ```
 pub fn sort(entries: &mut [Entry]) {
-	entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+	sort_by(entries, Sort::default());
+}
+
+/// The column a listing is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortKey {
+	/// Case-insensitive name.
+	#[default]
+	Name,
+	/// File size in bytes.
+	Size,
+	/// Last modification time.
+	Modified,
+	/// Case-insensitive extension: the text after the last `.`, ignoring a
+	/// leading dot, so `.env` has none.
+	Extension,
+}
+
+/// The direction of a [`SortKey`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortOrder {
+	/// Smallest, oldest or alphabetically first entries first.
+	#[default]
+	Ascending,
+	/// Largest, newest or alphabetically last entries first.
+	Descending,
+}
+
+impl SortOrder {
+	fn apply(self, ordering: Ordering) -> Ordering {
+		match self {
+			Self::Ascending => ordering,
+			Self::Descending => ordering.reverse(),
+		}
+	}
+}
+
+/// How names are compared when sorting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Collation {
+	/// Compares lowercased code points, so `é` sorts after `z`.
+	#[default]
+	CaseInsensitive,
+	/// Compares names by base letter first, ignoring case, accents and
+	/// compatibility forms (`Émile` sorts with `emile`, `ﬁle` with `file`),
+	/// then by accents, then by case.
+	///
+	/// This follows the shape of the Unicode Collation Algorithm without its
+	/// per-locale tailoring tables. Without the `collation` feature it falls
+	/// back to [`Collation::CaseInsensitive`], so the variant is available
+	/// regardless of which crates in the build enable the feature.
+	Unicode,
+}
+
+/// A complete listing order, used by [`sort_by`].
+///
+/// Entries are ordered by `key` in `order`, with directories grouped first
+/// when `dirs_first` is set. Names are compared with `collation`, and with
+/// runs of ASCII digits compared by numeric value when `natural` is set, so
+/// `file2.txt` sorts before `file10.txt`. The result is deterministic:
+/// - entries missing a size or modification time sort after all entries
+///   that have one, in either order
+/// - ties are broken by name, ascending
+/// - names equal under the collation (`README` and `readme`, or `v01` and
+///   `v1`) are finally ordered by code point, so no two distinct names ever
+///   compare equal
+///
+/// ```
+/// use serve_static::listing::{Sort, SortKey, SortOrder};
+///
+/// let sort = Sort::from_query("C=M;O=D");
+/// assert_eq!(sort.key, SortKey::Modified);
+/// assert_eq!(sort.order, SortOrder::Descending);
+/// assert_eq!(sort.to_query(), "C=M;O=D");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sort {
+	/// The column compared first.
+	pub key: SortKey,
+	/// The direction of `key`.
+	pub order: SortOrder,
+	/// Whether directories come before files regardless of `key`.
+	pub dirs_first: bool,
+	/// Whether digit runs in names compare numerically.
+	pub natural: bool,
+	/// How the letters of names compare.
+	pub collation: Collation,
+}
+
+impl Default for Sort {
+	fn default() -> Self {
+		Self {
+			key: SortKey::Name,
+			order: SortOrder::Ascending,
+			dirs_first: true,
+			natural: false,
+			collation: Collation::CaseInsensitive,
+		}
+	}
+}
+
+impl Sort {
+	/// Creates a directories-first order by `key` in `order`.
+	#[must_use]
+	pub fn new(key: SortKey, order: SortOrder) -> Self {
+		Self {
+			key,
+			order,
+			..Self::default()
+		}
+	}
+
+	/// Sets whether digit runs in names compare numerically.
+	#[must_use]
+	pub fn natural(mut self, natural: bool) -> Self {
+		self.natural = natural;
+		self
+	}
+
+	/// Sets how the letters of names compare.
+	#[must_use]
+	pub fn collation(mut self, collation: Collation) -> Self {
+		self.collation = collation;
+		self
+	}
+
+	/// Parses Apache `mod_autoindex` style query parameters.
+	///
+	/// `C` selects the column (`N` name, `S` size, `M` modified, plus `E`
+	/// extension), `O` the order (`A` or `D`) and `V=1` natural ("version")
+	/// name ordering. Parameters may be separated by `;` or `&`. Unknown
+	/// parameters and values are ignored, keeping the default for that part,
+	/// so any query string yields a usable order.
+	#[must_use]
+	pub fn from_query(query: &str) -> Self {
+		let mut sort = Self::default();
+		for param in query.trim_start_matches('?').split([';', '&']) {
+			match param.split_once('=') {
+				Some(("C", value)) => {
+					sort.key = match value {
+						"N" | "n" => SortKey::Name,
+						"S" | "s" => SortKey::Size,
+						"M" | "m" => SortKey::Modified,
+						"E" | "e" => SortKey::Extension,
+						_ => sort.key,
+					};
+				}
+				Some(("O", value)) => {
+					sort.order = match value {
+						"A" | "a" => SortOrder::Ascending,
+						"D" | "d" => SortOrder::Descending,
+						_ => sort.order,
+					};
+				}
+				Some(("V", value)) => {
+					sort.natural = match value {
+						"0" => false,
+						"1" => true,
+						_ => sort.natural,
+					};
+				}
+				_ => {}
+			}
+		}
+		sort
+	}
+
+	/// Formats the key and order as a query string accepted by
+	/// [`Sort::from_query`], such as `C=S;O=A`, with `;V=1` appended for
+	/// natural ordering.
+	#[must_use]
+	pub fn to_query(&self) -> String {
+		let column = match self.key {
+			SortKey::Name => 'N',
+			SortKey::Size => 'S',
+			SortKey::Modified => 'M',
+			SortKey::Extension => 'E',
+		};
+		let order = match self.order {
+			SortOrder::Ascending => 'A',
+			SortOrder::Descending => 'D',
+		};
+		let version = if self.natural { ";V=1" } else { "" };
+		format!("C={column};O={order}{version}")
+	}
+
+	/// Compares two entries in this order.
+	#[must_use]
+	pub fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+		Keyed::new(a, *self).cmp(&Keyed::new(b, *self))
+	}
+}
+
+/// Sorts entries in the given order. The sort is stable.
+///
+/// Comparison keys are computed once per entry, so collation and natural
+/// ordering cost no more per comparison than the default.
+///
+/// ```
+/// use serve_static::listing::{Entry, Sort, SortKey, SortOrder, sort_by};
+///
+/// let mut entries = vec![Entry::file("small", 1), Entry::file("big", 100)];
+/// sort_by(&mut entries, Sort::new(SortKey::Size, SortOrder::Descending));
+/// assert_eq!(entries[0].name, "big");
+///
+/// let mut entries = vec![Entry::file("v10", 0), Entry::file("v9", 0)];
+/// sort_by(&mut entries, Sort::default().natural(true));
+/// assert_eq!(entries[0].name, "v9");
+/// ```
+pub fn sort_by(entries: &mut [Entry], sort: Sort) {
+	entries.sort_by_cached_key(|entry| Keyed::new(entry, sort));
+}
+
+/// An entry's precomputed comparison key under a [`Sort`].
+struct Keyed {
+	sort: Sort,
+	is_dir: bool,
+	size: Option<u64>,
+	modified: Option<SystemTime>,
+	extension: Vec<Chunk>,
+	name: NameKey,
+}
+
+impl Keyed {
+	fn new(entry: &Entry, sort: Sort) -> Self {
+		let extension = if sort.key == SortKey::Extension {
+			chunks(&extension(&entry.name).to_lowercase(), sort.natural)
+		} else {
+			Vec::new()
+		};
+		Self {
+			sort,
+			is_dir: entry.is_dir,
+			size: entry.size,
+			modified: entry.modified,
+			extension,
+			name: NameKey::new(&entry.name, sort),
+		}
+	}
+}
+
+impl Ord for Keyed {
+	fn cmp(&self, other: &Self) -> Ordering {
+		let order = self.sort.order;
+		let dirs = if self.sort.dirs_first {
+			other.is_dir.cmp(&self.is_dir)
+		} else {
+			Ordering::Equal
+		};
+		let by_key = match self.sort.key {
+			SortKey::Name => order.apply(self.name.cmp(&other.name)),
+			SortKey::Size => compare_known(self.size, other.size, order),
+			SortKey::Modified => compare_known(self.modified, other.modified, order),
+			SortKey::Extension => order.apply(self.extension.cmp(&other.extension)),
+		};
+		dirs.then(by_key).then_with(|| self.name.cmp(&other.name))
+	}
+}
+
+impl PartialOrd for Keyed {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl PartialEq for Keyed {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Keyed {}
+
+/// A name split into comparison levels, most significant first, with the
+/// exact name as the final tie-breaker.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct NameKey {
+	levels: Vec<Vec<Chunk>>,
+	exact: String,
+}
+
+impl NameKey {
+	fn new(name: &str, sort: Sort) -> Self {
+		let levels = match sort.collation {
+			Collation::CaseInsensitive => vec![chunks(&name.to_lowercase(), sort.natural)],
+			Collation::Unicode => unicode_levels(name, sort.natural),
+		};
+		Self {
+			levels,
+			exact: name.to_owned(),
+		}
+	}
+}
+
+/// Comparison levels for [`Collation::Unicode`]: base letters, then
+/// accents, then case.
+#[cfg(feature = "collation")]
+fn unicode_levels(name: &str, natural: bool) -> Vec<Vec<Chunk>> {
+	use unicode_normalization::UnicodeNormalization as _;
+	use unicode_normalization::char::is_combining_mark;
+
+	let base: String = name
+		.nfkd()
+		.filter(|c| !is_combining_mark(*c))
+		.flat_map(char::to_lowercase)
+		.collect();
+	let accents: String = name.nfd().flat_map(char::to_lowercase).collect();
+	let case: String = name.nfd().collect();
+	vec![
+		chunks(&base, natural),
+		chunks(&accents, natural),
+		chunks(&case, natural),
+	]
+}
+
+/// Without the `collation` feature, [`Collation::Unicode`] compares like
+/// [`Collation::CaseInsensitive`].
+#[cfg(not(feature = "collation"))]
+fn unicode_levels(name: &str, natural: bool) -> Vec<Vec<Chunk>> {
+	vec![chunks(&name.to_lowercase(), natural)]
+}
+
+/// One unit of a name's comparison key.
+#[derive(PartialEq, Eq)]
+enum Chunk {
+	/// A whole name, used when numbers are not compared naturally.
+	Text(String),
+	/// A single non-digit character.
+	Char(char),
+	/// A run of ASCII digits without leading zeros; shorter means smaller.
+	Number { len: usize, digits: String },
+}
+
+impl Ord for Chunk {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self, other) {
+			(Self::Text(a), Self::Text(b)) => a.cmp(b),
+			(Self::Char(a), Self::Char(b)) => a.cmp(b),
+			(
+				Self::Number { len, digits },
+				Self::Number {
+					len: other_len,
+					digits: other_digits,
+				},
+			) => len.cmp(other_len).then_with(|| digits.cmp(other_digits)),
+			// A number sorts where its digits would: after `.`, before letters.
+			(Self::Number { .. }, Self::Char(c)) => '0'.cmp(c),
+			(Self::Char(c), Self::Number { .. }) => c.cmp(&'0'),
+			// Whole names never meet split ones under the same `Sort`.
+			(Self::Text(_), _) => Ordering::Greater,
+			(_, Self::Text(_)) => Ordering::Less,
+		}
+	}
+}
+
+impl PartialOrd for Chunk {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Splits `text` into chunks, keeping it whole unless `natural` is set.
+fn chunks(text: &str, natural: bool) -> Vec<Chunk> {
+	if !natural {
+		return vec![Chunk::Text(text.to_owned())];
+	}
+	let mut chunks = Vec::new();
+	let mut rest = text;
+	while let Some(first) = rest.chars().next() {
+		if first.is_ascii_digit() {
+			let end = rest
+				.find(|c: char| !c.is_ascii_digit())
+				.unwrap_or(rest.len());
+			let digits = rest[..end].trim_start_matches('0');
+			chunks.push(Chunk::Number {
+				len: digits.len(),
+				digits: digits.to_owned(),
+			});
+			rest = &rest[end..];
+		} else {
+			chunks.push(Chunk::Char(first));
+			rest = &rest[first.len_utf8()..];
+		}
+	}
+	chunks
+}
+
+/// Compares optional values, placing `None` last in either order.
+fn compare_known<T: Ord>(a: Option<T>, b: Option<T>, order: SortOrder) -> Ordering {
+	match (a, b) {
+		(Some(a), Some(b)) => order.apply(a.cmp(&b)),
+		(Some(_), None) => Ordering::Less,
+		(None, Some(_)) => Ordering::Greater,
+		(None, None) => Ordering::Equal,
+	}
+}
+
+/// The part of `name` after its last dot, or `""` for names without one,
+/// dotfiles such as `.profile` and names ending in a dot.
+pub(crate) fn extension(name: &str) -> &str {
+	match name.rfind('.') {
+		Some(dot) if dot > 0 => &name[dot + 1..],
+		_ => "",
+	}
+}
+
+/// Collects the sorted direct children of `dir` from a map keyed by
+/// normalized, `/`-separated paths.
+///
+/// Keys nested deeper than one level produce implicit directory entries;
+/// `to_entry` builds the entry for keys that are direct children.
+pub(crate) fn children<T>(
+	map: &BTreeMap<String, T>,
+	dir: &str,
+	to_entry: impl Fn(&str, &T) -> Entry,
+) -> Vec<Entry> {
+	let prefix = if dir.is_empty() {
+		String::new()
+	} else {
+		format!("{dir}/")
+	};
+
+	let mut entries = Vec::new();
+	let mut seen = BTreeSet::new();
+	for (key, value) in map.range(prefix.clone()..) {
+		let Some(rest) = key.strip_prefix(&prefix) else {
+			break;
+		};
+		let name = rest.split_once('/').map_or(rest, |(child, _)| child);
+		if !seen.insert(name) {
+			continue;
+		}
+		if name.len() == rest.len() {
+			entries.push(to_entry(name, value));
+		} else {
+			entries.push(Entry::dir(name));
+		}
+	}
+	sort(&mut entries);
+	entries
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::time::{Duration, UNIX_EPOCH};
 
 	fn file(name: &str) -> Entry {
-		Entry {
-			name: name.to_owned(),
-			is_dir: false,
-			size: Some(100),
-			modified: None,
-		}
+		Entry::file(name, 100)
 	}
 
 	fn dir(name: &str) -> Entry {
-		Entry {
-			name: name.to_owned(),
-			is_dir: true,
-			size: None,
-			modified: None,
-		}
+		Entry::dir(name)
 	}
 
 	#[test]
@@ -93,6 +637,18 @@ mod tests {
 		assert!(entries.is_empty());
 	}
 
+	#[test]
+	fn struct_literal_with_defaults() {
+		let entry = Entry {
+			name: "a.txt".to_owned(),
+			is_dir: false,
+			size: Some(1),
+			modified: None,
+			..Default::default()
+		};
+		assert_eq!(entry, Entry::file("a.txt", 1));
+	}
+
 	#[test]
 	fn single_entry() {
 		let mut entries = vec![file("only.txt")];
@@ -147,14 +703,234 @@ mod tests {
 		assert_eq!(entries[2].name, "Zebra.rs");
 	}
 
+	fn sized(name: &str, size: Option<u64>, modified: Option<u64>) -> Entry {
+		Entry::file(name, 0)
+			.with_size(size)
+			.with_modified(modified.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)))
+	}
+
+	fn names(entries: &[Entry]) -> Vec<&str> {
+		entries.iter().map(|e| e.name.as_str()).collect()
+	}
+
+	#[test]
+	fn sort_by_size() {
+		let mut entries = vec![
+			sized("b", Some(10), None),
+			sized("none", None, None),
+			sized("a", Some(10), None),
+			sized("c", Some(5), None),
+			dir("d"),
+		];
+		sort_by(&mut entries, Sort::new(SortKey::Size, SortOrder::Ascending));
+		assert_eq!(names(&entries), ["d", "c", "a", "b", "none"]);
+		sort_by(
+			&mut entries,
+			Sort::new(SortKey::Size, SortOrder::Descending),
+		);
+		// Ties still break by ascending name; unknown sizes stay last.
+		assert_eq!(names(&entries), ["d", "a", "b", "c", "none"]);
+	}
+
+	#[test]
+	fn sort_by_modified() {
+		let mut entries = vec![
+			sized("old", Some(1), Some(100)),
+			sized("unknown", Some(1), None),
+			sized("new", Some(1), Some(200)),
+		];
+		let mut sort = Sort::new(SortKey::Modified, SortOrder::Descending);
+		sort_by(&mut entries, sort);
+		assert_eq!(names(&entries), ["new", "old", "unknown"]);
+		sort.order = SortOrder::Ascending;
+		sort_by(&mut entries, sort);
+		assert_eq!(names(&entries), ["old", "new", "unknown"]);
+	}
+
+	#[test]
+	fn sort_by_extension() {
+		let mut entries = vec![
+			file("b.TXT"),
+			file("a.txt"),
+			file("Makefile"),
+			file(".env"),
+			file("x.tar.gz"),
+		];
+		sort_by(
+			&mut entries,
+			Sort::new(SortKey::Extension, SortOrder::Ascending),
+		);
+		assert_eq!(
+			names(&entries),
+			[".env", "Makefile", "x.tar.gz", "a.txt", "b.TXT"]
+		);
+	}
+
+	#[test]
+	fn sort_by_name_descending_without_grouping() {
+		let mut entries = vec![file("a"), dir("b"), file("C")];
+		let sort = Sort {
+			dirs_first: false,
+			..Sort::new(SortKey::Name, SortOrder::Descending)
+		};
+		sort_by(&mut entries, sort);
+		assert_eq!(names(&entries), ["C", "b", "a"]);
+	}
+
+	#[test]
+	fn query_round_trip() {
+		for query in ["C=N;O=A", "C=S;O=D", "C=M;O=A", "C=E;O=D"] {
+			assert_eq!(Sort::from_query(query).to_query(), query);
+		}
+		let sort = Sort::from_query("?O=D&C=S");
+		assert_eq!(sort, Sort::new(SortKey::Size, SortOrder::Descending));
+	}
+
+	#[test]
+	fn query_ignores_unknown() {
+		assert_eq!(Sort::from_query(""), Sort::default());
+		assert_eq!(Sort::from_query("C=X;O=Z;P=*.txt"), Sort::default());
+		assert_eq!(Sort::from_query("C=M;junk").key, SortKey::Modified);
+	}
+
 	#[test]
 	fn names_equal_after_lowercase() {
-		// Names that collapse to the same lowercase key must not panic
+		// Names that collapse to the same lowercase key are ordered by code
+		// point, whatever order they arrive in.
+		let mut entries = vec![
+			file("readme"),
+			file("Readme"),
+			file("README"),
+			file("Readme.md"),
+		];
+		sort(&mut entries);
+		assert_eq!(names(&entries), ["README", "Readme", "readme", "Readme.md"]);
+		entries.reverse();
+		sort(&mut entries);
+		assert_eq!(names(&entries), ["README", "Readme", "readme", "Readme.md"]);
+
 		let mut entries = vec![file("README"), file("readme"), dir("Readme")];
 		sort(&mut entries);
-		// Directory first, then the two files in stable-ish order
-		assert!(entries[0].is_dir);
-		assert!(!entries[1].is_dir);
-		assert!(!entries[2].is_dir);
+		assert_eq!(names(&entries), ["Readme", "README", "readme"]);
+	}
+
+	#[test]
+	fn natural_numbers() {
+		let mut entries = vec![
+			file("file10.txt"),
+			file("file2.txt"),
+			file("file1.txt"),
+			file("file02.txt"),
+			file("file.txt"),
+		];
+		sort_by(&mut entries, Sort::default().natural(true));
+		assert_eq!(
+			names(&entries),
+			[
+				"file.txt",
+				"file1.txt",
+				"file02.txt",
+				"file2.txt",
+				"file10.txt"
+			]
+		);
+
+		sort(&mut entries);
+		assert_eq!(
+			names(&entries),
+			[
+				"file.txt",
+				"file02.txt",
+				"file1.txt",
+				"file10.txt",
+				"file2.txt"
+			]
+		);
+	}
+
+	#[test]
+	fn natural_versions_and_long_numbers() {
+		let mut entries = vec![
+			file("v1.10.0"),
+			file("v1.9.3"),
+			file("v1.9.10"),
+			file("99999999999999999999999"),
+			file("100000000000000000000000"),
+		];
+		sort_by(&mut entries, Sort::default().natural(true));
+		assert_eq!(
+			names(&entries),
+			[
+				"99999999999999999999999",
+				"100000000000000000000000",
+				"v1.9.3",
+				"v1.9.10",
+				"v1.10.0"
+			]
+		);
+	}
+
+	#[test]
+	fn natural_extension_and_query() {
+		let sort = Sort::from_query("C=N;O=D;V=1");
+		assert!(sort.natural);
+		assert_eq!(sort.to_query(), "C=N;O=D;V=1");
+		let mut entries = vec![file("a9"), file("a10")];
+		sort_by(&mut entries, sort);
+		assert_eq!(names(&entries), ["a10", "a9"]);
+	}
+
+	#[test]
+	fn accents_sort_after_z_by_default() {
+		let mut entries = vec![file("zebra"), file("émile"), file("apple")];
+		sort(&mut entries);
+		assert_eq!(names(&entries), ["apple", "zebra", "émile"]);
+	}
+
+	#[cfg(feature = "collation")]
+	#[test]
+	fn unicode_collation() {
+		let unicode = Sort::default().collation(Collation::Unicode);
+		let mut entries = vec![
+			file("zebra"),
+			file("Émile"),
+			file("emile"),
+			file("émile"),
+			file("Emile"),
+			file("apple"),
+			file("ﬁle"),
+			file("fig"),
+		];
+		sort_by(&mut entries, unicode);
+		assert_eq!(
+			names(&entries),
+			[
+				"apple", "Emile", "emile", "Émile", "émile", "fig", "ﬁle", "zebra"
+			]
+		);
+	}
+
+	#[cfg(not(feature = "collation"))]
+	#[test]
+	fn unicode_collation_falls_back() {
+		let unicode = Sort::default().collation(Collation::Unicode);
+		let mut entries = vec![file("zebra"), file("émile"), file("Apple")];
+		sort_by(&mut entries, unicode);
+		assert_eq!(names(&entries), ["Apple", "zebra", "émile"]);
+	}
+
+	#[cfg(feature = "collation")]
+	#[test]
+	fn unicode_collation_is_total() {
+		// Precomposed and decomposed forms look identical but still get a
+		// fixed order, independent of input order.
+		let unicode = Sort::default().collation(Collation::Unicode).natural(true);
+		let mut entries = vec![file("e\u{301}2"), file("\u{e9}2"), file("é10")];
+		sort_by(&mut entries, unicode);
+		let first = names(&entries).join(",");
+		entries.reverse();
+		sort_by(&mut entries, unicode);
+		assert_eq!(names(&entries).join(","), first);
+		assert_eq!(entries[2].name, "é10");
 	}
 }
@@ -1,18 +1,297 @@
 /* src/mime.rs */
 
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{self, Read};
 use std::path::Path;
+use std::str::FromStr;
 use std::time::SystemTime;
 
+use crate::error::Error;
+use crate::glob;
+
+/// A parsed media type such as `text/html; charset=utf-8`.
+///
+/// The type, subtype and parameter names are lowercased; parameter values
+/// keep their case. Compares equal to a string holding the same rendering,
+/// so `detect(..) == "text/html"` reads naturally.
+///
+/// ```
+/// use serve_static::mime::MediaType;
+///
+/// let mt = MediaType::parse("Text/HTML; Charset=\"UTF-8\"").unwrap();
+/// assert_eq!(mt.essence(), "text/html");
+/// assert_eq!(mt.param("charset"), Some("UTF-8"));
+/// assert_eq!(mt.to_string(), "text/html; charset=UTF-8");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MediaType {
+	essence: String,
+	slash: usize,
+	params: Vec<(String, String)>,
+}
+
+impl MediaType {
+	/// Creates a media type from its type and subtype, lowercased.
+	///
+	/// Returns `None` unless both are non-empty RFC 9110 tokens.
+	#[must_use]
+	pub fn new(type_: &str, subtype: &str) -> Option<Self> {
+		if !is_token(type_) || !is_token(subtype) {
+			return None;
+		}
+		Some(Self {
+			essence: format!("{type_}/{subtype}").to_ascii_lowercase(),
+			slash: type_.len(),
+			params: Vec::new(),
+		})
+	}
+
+	/// Parses a `Content-Type` style value with optional parameters.
+	///
+	/// Parameter values may be quoted. Malformed parameters are skipped;
+	/// a malformed type or subtype yields `None`.
+	#[must_use]
+	pub fn parse(value: &str) -> Option<Self> {
+		let (essence, mut rest) = value.split_once(';').unwrap_or((value, ""));
+		let (type_, subtype) = essence.trim().split_once('/')?;
+		let mut media = Self::new(type_, subtype)?;
+		while !rest.is_empty() {
+			let (name, after) = rest.split_once('=').unwrap_or((rest, ""));
+			let name = name.trim_start_matches([' ', '\t', ';']).trim();
+			let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+				unquote(quoted)
+			} else {
+				let (value, after) = after.split_once(';').unwrap_or((after, ""));
+				(value.trim().to_owned(), after)
+			};
+			rest = after;
+			if is_token(name) && !value.is_empty() {
+				media = media.with_param(name, value);
+			}
+		}
+		Some(media)
+	}
+
+	/// The top-level type, such as `text`.
+	#[must_use]
+	pub fn type_(&self) -> &str {
+		&self.essence[..self.slash]
+	}
+
+	/// The subtype, such as `html` or `svg+xml`.
+	#[must_use]
+	pub fn subtype(&self) -> &str {
+		&self.essence[self.slash + 1..]
+	}
+
+	/// The structured syntax suffix, such as `xml` for `image/svg+xml`.
+	#[must_use]
+	pub fn suffix(&self) -> Option<&str> {
+		self.subtype().rsplit_once('+').map(|(_, suffix)| suffix)
+	}
+
+	/// `type/subtype` without parameters.
+	#[must_use]
+	pub fn essence(&self) -> &str {
+		&self.essence
+	}
+
+	/// The value of a parameter, matching its name case-insensitively.
+	#[must_use]
+	pub fn param(&self, name: &str) -> Option<&str> {
+		self
+			.params
+			.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// All parameters in order, with lowercased names.
+	pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+		self
+			.params
+			.iter()
+			.map(|(key, value)| (key.as_str(), value.as_str()))
+	}
+
+	/// Sets a parameter, replacing any existing value for the same name.
+	#[must_use]
+	pub fn with_param(mut self, name: &str, value: impl Into<String>) -> Self {
+		let name = name.to_ascii_lowercase();
+		let value = value.into();
+		match self.params.iter_mut().find(|(key, _)| *key == name) {
+			Some(slot) => slot.1 = value,
+			None => self.params.push((name, value)),
+		}
+		self
+	}
+
+	/// Whether the type is textual: `text/*`, JSON, JavaScript, XML
+	/// (including SVG), or any `+json` or `+xml` type.
+	#[must_use]
+	pub fn is_text(&self) -> bool {
+		self.type_() == "text"
+			|| matches!(self.suffix(), Some("json" | "xml"))
+			|| matches!(
+				self.essence(),
+				"application/json"
+					| "application/javascript"
+					| "application/x-javascript"
+					| "application/ecmascript"
+					| "application/xml"
+			)
+	}
+
+	/// Adds a `charset` parameter according to `policy`.
+	///
+	/// Only textual types (see [`MediaType::is_text`]) are changed, and an
+	/// existing `charset` is kept. `content` should be the start of the
+	/// file; pass `&[]` when it has not been read, in which case
+	/// [`Charset::Detect`] adds nothing.
+	#[must_use]
+	pub fn with_charset(self, policy: Charset, content: &[u8]) -> Self {
+		if !self.is_text() || self.param("charset").is_some() {
+			return self;
+		}
+		let charset = match policy {
+			Charset::Never => None,
+			Charset::Utf8 => Some("utf-8"),
+			Charset::Detect => match bom(content) {
+				Some(charset) => Some(charset),
+				None => (!content.is_empty() && is_utf8_prefix(content)).then_some("utf-8"),
+			},
+		};
+		match charset {
+			Some(charset) => self.with_param("charset", charset),
+			None => self,
+		}
+	}
+}
+
+impl fmt::Display for MediaType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.essence)?;
+		for (name, value) in &self.params {
+			if is_token(value) {
+				write!(f, "; {name}={value}")?;
+			} else {
+				let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+				write!(f, "; {name}=\"{escaped}\"")?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl FromStr for MediaType {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::parse(s).ok_or_else(|| Error::InvalidMediaType(s.to_owned()))
+	}
+}
+
+impl From<MediaType> for String {
+	fn from(media: MediaType) -> Self {
+		media.to_string()
+	}
+}
+
+impl PartialEq<str> for MediaType {
+	fn eq(&self, other: &str) -> bool {
+		if self.params.is_empty() {
+			self.essence == other
+		} else {
+			self.to_string().as_str() == other
+		}
+	}
+}
+
+impl PartialEq<&str> for MediaType {
+	fn eq(&self, other: &&str) -> bool {
+		*self == **other
+	}
+}
+
+/// When [`MediaType::with_charset`] adds a `charset` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Charset {
+	/// Never add one.
+	Never,
+	/// Add the encoding named by a byte order mark, or `utf-8` when the
+	/// content is valid UTF-8 (a sequence cut off at the end is allowed).
+	/// Empty or unread content gets no charset, since nothing is known
+	/// about its encoding.
+	#[default]
+	Detect,
+	/// Always add `utf-8`, without looking at the content.
+	Utf8,
+}
+
+/// The charset named by a leading byte order mark.
+fn bom(content: &[u8]) -> Option<&'static str> {
+	if content.starts_with(&[0xEF, 0xBB, 0xBF]) {
+		Some("utf-8")
+	} else if content.starts_with(&[0xFF, 0xFE]) {
+		Some("utf-16le")
+	} else if content.starts_with(&[0xFE, 0xFF]) {
+		Some("utf-16be")
+	} else {
+		None
+	}
+}
+
+/// Whether `content` is valid UTF-8, allowing a multibyte sequence to be
+/// cut off at the end as happens with a fixed-size prefix.
+fn is_utf8_prefix(content: &[u8]) -> bool {
+	match std::str::from_utf8(content) {
+		Ok(_) => true,
+		Err(err) => err.error_len().is_none(),
+	}
+}
+
+/// Whether `s` is a non-empty RFC 9110 token.
+fn is_token(s: &str) -> bool {
+	!s.is_empty()
+		&& s
+			.bytes()
+			.all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Reads a quoted-string body (after the opening quote), returning the
+/// unescaped value and the text after the next `;`.
+fn unquote(s: &str) -> (String, &str) {
+	let mut value = String::new();
+	let mut chars = s.char_indices();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'\\' => value.extend(chars.next().map(|(_, c)| c)),
+			'"' => {
+				let rest = &s[i + 1..];
+				let rest = rest.split_once(';').map_or("", |(_, after)| after);
+				return (value, rest);
+			}
+			c => value.push(c),
+		}
+	}
+	(value, "")
+}
+
 /// Detects the MIME type using a multi-strategy approach.
 ///
 /// Detection order by priority:
 /// 1. File extension guess (requires `extension` feature)
 /// 2. Magic byte sniffing (requires `sniff` feature and non-empty `content`)
-/// 3. UTF-8 heuristic: valid UTF-8 content yields `text/plain`
+/// 3. Text heuristic: text content is recognized as HTML, XML, SVG or JSON,
+///    or else yields `text/plain`; see [`Strategy::Text`]
 /// 4. Fallback: `application/octet-stream`
 ///
-/// The `path` argument is only used for extension-based guessing and never
-/// touches the filesystem. Pass `&[]` for `content` to skip byte-level checks.
+/// This is the [`Strategy::DEFAULT`] pipeline; use a [`MimeDetector`] to
+/// change the order or add overrides. The `path` argument is only used for
+/// extension-based guessing and never touches the filesystem. Pass `&[]` for
+/// `content` to skip byte-level checks. The result carries no parameters;
+/// see [`content_type`] for one with a `charset`.
 ///
 /// ```
 /// // Works with &Path, PathBuf, and &str:
@@ -24,30 +303,960 @@ use std::time::SystemTime;
 /// assert_eq!(mime2, "text/plain");
 /// ```
 #[must_use]
-pub fn detect(path: impl AsRef<Path>, content: &[u8]) -> String {
+pub fn detect(path: impl AsRef<Path>, content: &[u8]) -> MediaType {
 	let path = path.as_ref();
+	Strategy::DEFAULT
+		.iter()
+		.find_map(|strategy| strategy.run(path, content))
+		.unwrap_or_else(octet_stream)
+}
+
+/// One stage of MIME detection. See [`MimeDetector::strategies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strategy {
+	/// The rules configured on a [`MimeDetector`].
+	Override,
+	/// A guess from the file extension (requires `extension` feature).
+	Extension,
+	/// Magic byte sniffing (requires `sniff` feature).
+	Sniff,
+	/// Text/binary classification in the style of WHATWG MIME sniffing.
+	///
+	/// Content is text when it starts with a UTF-8 or UTF-16 byte order
+	/// mark, or has no binary control bytes and is valid UTF-8 (a multibyte
+	/// sequence cut off at the end is allowed). UTF-8 text is then
+	/// recognized as HTML, XML, SVG or JSON by its start, and is otherwise
+	/// `text/plain`.
+	Text,
+}
+
+impl Strategy {
+	/// The order used by [`detect`]: name-based rules before content.
+	pub const DEFAULT: [Self; 4] = [Self::Override, Self::Extension, Self::Sniff, Self::Text];
+
+	/// Content before name, for untrusted uploads whose extension may lie.
+	/// Configured overrides still come first.
+	pub const SNIFF_FIRST: [Self; 4] = [Self::Override, Self::Sniff, Self::Extension, Self::Text];
+
+	/// Names only, never looking at content, in the spirit of
+	/// `X-Content-Type-Options: nosniff`.
+	pub const NOSNIFF: [Self; 2] = [Self::Override, Self::Extension];
+
+	/// Runs a built-in stage. [`Strategy::Override`] needs a detector and
+	/// always yields `None` here.
+	fn run(self, path: &Path, content: &[u8]) -> Option<MediaType> {
+		match self {
+			Self::Override => None,
+			Self::Extension => by_extension(path),
+			Self::Sniff => by_magic(content),
+			Self::Text => by_text(content),
+		}
+	}
+}
+
+/// The result of [`MimeDetector::detect_with_strategy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+	/// The detected media type.
+	pub media: MediaType,
+	/// The stage that decided, or `None` when every stage passed and the
+	/// result is the `application/octet-stream` fallback.
+	pub strategy: Option<Strategy>,
+}
+
+fn by_extension(path: &Path) -> Option<MediaType> {
 	#[cfg(feature = "extension")]
 	if let Some(guess) = mime_guess::from_path(path).first()
 		&& !(guess.type_() == "application" && guess.subtype() == "octet-stream")
 	{
-		return guess.to_string();
+		return MediaType::parse(guess.essence_str());
 	}
+	#[cfg(not(feature = "extension"))]
+	let _ = path;
+	None
+}
+
+fn by_magic(content: &[u8]) -> Option<MediaType> {
+	// HTML and XML are left to the text stage, which also tells SVG apart.
+	#[cfg(feature = "sniff")]
+	if let Some(kind) = infer::get(content)
+		&& !matches!(kind.mime_type(), "text/html" | "text/xml")
+	{
+		return MediaType::parse(kind.mime_type());
+	}
+	#[cfg(not(feature = "sniff"))]
+	let _ = content;
+	None
+}
+
+/// WHATWG HTML signatures, each followed by a space or `>`.
+const HTML_TAGS: [&[u8]; 17] = [
+	b"<!doctype html",
+	b"<html",
+	b"<head",
+	b"<script",
+	b"<iframe",
+	b"<h1",
+	b"<div",
+	b"<font",
+	b"<table",
+	b"<a",
+	b"<style",
+	b"<title",
+	b"<b",
+	b"<body",
+	b"<br",
+	b"<p",
+	b"<!--",
+];
 
-	if !content.is_empty() {
-		#[cfg(feature = "sniff")]
-		if let Some(kind) = infer::get(content) {
-			return kind.mime_type().to_owned();
+/// Recognizes content a browser may render or run: HTML, XML, SVG and PDF.
+/// Leading byte order marks and whitespace are skipped.
+fn by_markup(content: &[u8]) -> Option<MediaType> {
+	let start = content.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(content);
+	let start = start.trim_ascii_start();
+	let starts_with = |prefix: &[u8]| {
+		start
+			.get(..prefix.len())
+			.is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+	};
+	let media = |s| MediaType::parse(s);
+	if start.starts_with(b"%PDF-") {
+		return media("application/pdf");
+	}
+	if starts_with(b"<svg") {
+		return media("image/svg+xml");
+	}
+	if starts_with(b"<?xml") {
+		// An SVG document usually opens with an XML declaration.
+		let head = &start[..start.len().min(1024)];
+		let svg = head.windows(4).any(|w| w.eq_ignore_ascii_case(b"<svg"));
+		return media(if svg { "image/svg+xml" } else { "text/xml" });
+	}
+	let html = HTML_TAGS
+		.iter()
+		.any(|tag| starts_with(tag) && matches!(start.get(tag.len()), Some(b' ' | b'>')));
+	html.then(text_html)
+}
+
+fn by_text(content: &[u8]) -> Option<MediaType> {
+	if content.is_empty() {
+		return None;
+	}
+	// UTF-16 text is not checked further.
+	if bom(content).is_some_and(|charset| charset != "utf-8") {
+		return Some(text_plain());
+	}
+	if content.iter().any(|&b| is_binary_byte(b)) || !is_utf8_prefix(content) {
+		return None;
+	}
+	if let Some(media) = by_markup(content)
+		&& media.essence() != "application/pdf"
+	{
+		return Some(media);
+	}
+	if is_json_prefix(content) {
+		return MediaType::parse("application/json");
+	}
+	Some(text_plain())
+}
+
+/// WHATWG binary data bytes: C0 controls other than tab, line feed, form
+/// feed, carriage return and escape.
+fn is_binary_byte(b: u8) -> bool {
+	matches!(b, 0x00..=0x08 | 0x0B | 0x0E..=0x1A | 0x1C..=0x1F)
+}
+
+/// What a JSON prefix scanner expects next.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Json {
+	Value,
+	ValueOrClose,
+	Key,
+	KeyOrClose,
+	Colon,
+	CommaOrClose,
+	Done,
+}
+
+/// Whether `content` is a JSON object or array, or a prefix of one cut off
+/// anywhere. Scalars at the top level are not treated as JSON.
+fn is_json_prefix(content: &[u8]) -> bool {
+	let content = content.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(content);
+	if !matches!(content.trim_ascii_start().first(), Some(b'{' | b'[')) {
+		return false;
+	}
+	let mut stack = Vec::new();
+	let mut state = Json::Value;
+	let mut i = 0;
+	while i < content.len() {
+		let b = content[i];
+		i += 1;
+		if b.is_ascii_whitespace() {
+			continue;
 		}
+		let after_value = |stack: &Vec<u8>| {
+			if stack.is_empty() {
+				Json::Done
+			} else {
+				Json::CommaOrClose
+			}
+		};
+		state = match (state, b) {
+			(Json::Value | Json::ValueOrClose, b'{') => {
+				stack.push(b'}');
+				Json::KeyOrClose
+			}
+			(Json::Value | Json::ValueOrClose, b'[') => {
+				stack.push(b']');
+				Json::ValueOrClose
+			}
+			(Json::Value | Json::ValueOrClose | Json::Key | Json::KeyOrClose, b'"') => {
+				let key = matches!(state, Json::Key | Json::KeyOrClose);
+				let Some(end) = json_string_end(&content[i..]) else {
+					return false;
+				};
+				i += end;
+				if i > content.len() {
+					return true;
+				}
+				if key {
+					Json::Colon
+				} else {
+					after_value(&stack)
+				}
+			}
+			(Json::Value | Json::ValueOrClose, b'-' | b'0'..=b'9') => {
+				while i < content.len()
+					&& matches!(content[i], b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+				{
+					i += 1;
+				}
+				after_value(&stack)
+			}
+			(Json::Value | Json::ValueOrClose, b't' | b'f' | b'n') => {
+				let word: &[u8] = match b {
+					b't' => b"true",
+					b'f' => b"false",
+					_ => b"null",
+				};
+				let rest = &content[i - 1..];
+				let len = rest.len().min(word.len());
+				if rest[..len] != word[..len] {
+					return false;
+				}
+				i += len - 1;
+				after_value(&stack)
+			}
+			(Json::ValueOrClose | Json::KeyOrClose | Json::CommaOrClose, b'}' | b']')
+				if stack.last() == Some(&b)
+					&& (state == Json::CommaOrClose || (b == b']') == (state == Json::ValueOrClose)) =>
+			{
+				stack.pop();
+				after_value(&stack)
+			}
+			(Json::CommaOrClose, b',') if stack.last() == Some(&b'}') => Json::Key,
+			(Json::Colon, b':') | (Json::CommaOrClose, b',') => Json::Value,
+			_ => return false,
+		};
+	}
+	true
+}
 
-		if std::str::from_utf8(content).is_ok() {
-			return "text/plain".to_owned();
+/// The length of a JSON string body up to and including its closing quote,
+/// one past the end of `rest` when cut off, or `None` when invalid.
+fn json_string_end(rest: &[u8]) -> Option<usize> {
+	let mut i = 0;
+	while i < rest.len() {
+		match rest[i] {
+			b'"' => return Some(i + 1),
+			b'\\' => i += 2,
+			b if b < 0x20 => return None,
+			_ => i += 1,
 		}
 	}
+	Some(rest.len() + 1)
+}
 
-	#[cfg(not(feature = "extension"))]
-	let _ = path;
+/// Detects the MIME type with [`detect`] and adds a `charset` following
+/// [`Charset::Detect`], ready for a `Content-Type` header.
+///
+/// ```
+/// use serve_static::mime::content_type;
+///
+/// assert_eq!(content_type("notes", b"caf\xc3\xa9"), "text/plain; charset=utf-8");
+/// assert_eq!(content_type("blob", &[0x00, 0xFF]), "application/octet-stream");
+/// ```
+#[must_use]
+pub fn content_type(path: impl AsRef<Path>, content: &[u8]) -> MediaType {
+	detect(path, content).with_charset(Charset::Detect, content)
+}
+
+/// How many leading bytes of content the detection stages look at: the
+/// WHATWG resource header size, which covers every magic number and the
+/// markup checks.
+pub const SNIFF_LEN: usize = 1445;
+
+/// The result of detecting the type of a reader's content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sniffed {
+	/// The detected media type, without parameters.
+	pub media: MediaType,
+	/// The bytes consumed from the reader, to be sent before the rest of
+	/// the body.
+	pub prefix: Vec<u8>,
+}
+
+impl Sniffed {
+	/// The media type with a `charset` following [`Charset::Detect`].
+	#[must_use]
+	pub fn content_type(&self) -> MediaType {
+		self
+			.media
+			.clone()
+			.with_charset(Charset::Detect, &self.prefix)
+	}
+}
+
+/// Reads up to [`SNIFF_LEN`] bytes from `reader` and detects the type like
+/// [`detect`].
+///
+/// ```
+/// use std::io::Read;
+///
+/// let mut file: &[u8] = b"{\"name\": \"serve_static\"}";
+/// let sniffed = serve_static::mime::detect_read("package", &mut file).unwrap();
+/// assert_eq!(sniffed.media, "application/json");
+///
+/// // Replay the prefix, then stream what is left.
+/// let mut body = sniffed.prefix.as_slice().chain(file);
+/// let mut out = String::new();
+/// body.read_to_string(&mut out).unwrap();
+/// assert_eq!(out, r#"{"name": "serve_static"}"#);
+/// ```
+pub fn detect_read<R: Read + ?Sized>(
+	path: impl AsRef<Path>,
+	reader: &mut R,
+) -> Result<Sniffed, Error> {
+	MimeDetector::new().detect_read(path, reader)
+}
+
+/// Like [`detect_read`], for a Tokio reader (requires `tokio` feature).
+#[cfg(feature = "tokio")]
+pub async fn detect_async_read<R: tokio::io::AsyncRead + Unpin + ?Sized>(
+	path: impl AsRef<Path>,
+	reader: &mut R,
+) -> Result<Sniffed, Error> {
+	MimeDetector::new().detect_async_read(path, reader).await
+}
+
+/// MIME detection with overrides consulted before the built-in strategies.
+///
+/// Rules are checked from most to least specific: an exact file name, then
+/// glob patterns in the order they were added, then extensions, longest
+/// first (so `tar.gz` beats `gz`). Globs containing a `/` match the whole
+/// path with any leading `/` removed; others match the file name.
+///
+/// The rules form the [`Strategy::Override`] stage of a pipeline that
+/// otherwise runs like [`detect`]; [`MimeDetector::strategies`] reorders or
+/// drops stages.
+///
+/// ```
+/// use serve_static::mime::MimeDetector;
+///
+/// let detector = MimeDetector::web()
+///     .extension("pbf", "application/x-protobuf".parse().unwrap())
+///     .filename("Dockerfile", "text/x-dockerfile".parse().unwrap())
+///     .glob("data/**/*.bin", "application/x-tile".parse().unwrap());
+///
+/// assert_eq!(detector.detect("app.wasm", &[]), "application/wasm");
+/// assert_eq!(detector.detect("tiles/1.PBF", &[]), "application/x-protobuf");
+/// assert_eq!(detector.detect("build/Dockerfile", &[]), "text/x-dockerfile");
+/// assert_eq!(detector.detect("/data/a/b.bin", &[]), "application/x-tile");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeDetector {
+	extensions: HashMap<String, MediaType>,
+	filenames: HashMap<String, MediaType>,
+	globs: Vec<(String, MediaType)>,
+	strategies: Vec<Strategy>,
+}
+
+impl Default for MimeDetector {
+	fn default() -> Self {
+		Self {
+			extensions: HashMap::new(),
+			filenames: HashMap::new(),
+			globs: Vec::new(),
+			strategies: Strategy::DEFAULT.to_vec(),
+		}
+	}
+}
+
+impl MimeDetector {
+	/// Creates a detector with no overrides.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a detector with overrides for web assets that extension
+	/// tables often get wrong or lack: `.wasm`, `.mjs`, `.webmanifest`,
+	/// and `apple-app-site-association`.
+	#[must_use]
+	pub fn web() -> Self {
+		let media = |s: &str| MediaType::parse(s).unwrap_or_else(octet_stream);
+		Self::new()
+			.extension("wasm", media("application/wasm"))
+			.extension("mjs", media("text/javascript"))
+			.extension("webmanifest", media("application/manifest+json"))
+			.filename("apple-app-site-association", media("application/json"))
+	}
+
+	/// Maps an extension, without the leading dot and compared
+	/// case-insensitively, to `media`. Compound extensions such as `tar.gz`
+	/// are allowed.
+	#[must_use]
+	pub fn extension(mut self, extension: &str, media: MediaType) -> Self {
+		let extension = extension.trim_start_matches('.').to_lowercase();
+		self.extensions.insert(extension, media);
+		self
+	}
+
+	/// Maps an exact, case-sensitive file name to `media`.
+	#[must_use]
+	pub fn filename(mut self, name: impl Into<String>, media: MediaType) -> Self {
+		self.filenames.insert(name.into(), media);
+		self
+	}
+
+	/// Maps paths matching a glob pattern to `media`.
+	#[must_use]
+	pub fn glob(mut self, pattern: &str, media: MediaType) -> Self {
+		self
+			.globs
+			.push((pattern.trim_start_matches('/').to_owned(), media));
+		self
+	}
+
+	/// Sets which stages run, in order. Stages left out are disabled; pass
+	/// [`Strategy::NOSNIFF`] to never look at content, or
+	/// [`Strategy::SNIFF_FIRST`] to trust content over names.
+	///
+	/// ```
+	/// use serve_static::mime::{MimeDetector, Strategy};
+	///
+	/// let html = b"<!DOCTYPE html><script>alert(1)</script>";
+	/// let nosniff = MimeDetector::new().strategies(Strategy::NOSNIFF);
+	/// assert_eq!(nosniff.detect("upload", html), "application/octet-stream");
+	/// ```
+	#[must_use]
+	pub fn strategies(mut self, order: impl IntoIterator<Item = Strategy>) -> Self {
+		self.strategies = order.into_iter().collect();
+		self
+	}
+
+	/// Adds every extension mapping from `types`, replacing earlier
+	/// mappings for the same extensions.
+	#[must_use]
+	pub fn types(mut self, types: &TypeMap) -> Self {
+		for (extension, media) in types.iter() {
+			self.extensions.insert(extension.to_owned(), media.clone());
+		}
+		self
+	}
+
+	/// The override for `path`, if any rule matches.
+	#[must_use]
+	pub fn lookup(&self, path: impl AsRef<Path>) -> Option<&MediaType> {
+		let path = path.as_ref().to_string_lossy();
+		let path = path.trim_start_matches('/');
+		let name = path.rsplit('/').next().unwrap_or_default();
+		if let Some(media) = self.filenames.get(name) {
+			return Some(media);
+		}
+		let glob = self.globs.iter().find(|(pattern, _)| {
+			let text = if pattern.contains('/') { path } else { name };
+			glob::matches(pattern, text)
+		});
+		if let Some((_, media)) = glob {
+			return Some(media);
+		}
+		if self.extensions.is_empty() {
+			return None;
+		}
+		let name = name.to_lowercase();
+		name
+			.match_indices('.')
+			.filter(|&(i, _)| i > 0)
+			.find_map(|(i, _)| self.extensions.get(&name[i + 1..]))
+	}
+
+	/// Detects the MIME type of `path`, preferring the override rules over
+	/// [`detect`].
+	#[must_use]
+	pub fn detect(&self, path: impl AsRef<Path>, content: &[u8]) -> MediaType {
+		self.detect_with_strategy(path, content).media
+	}
+
+	/// Runs the pipeline and reports which stage decided.
+	///
+	/// ```
+	/// use serve_static::mime::{MimeDetector, Strategy};
+	///
+	/// let detection = MimeDetector::new().detect_with_strategy("notes", b"hello");
+	/// assert_eq!(detection.media, "text/plain");
+	/// assert_eq!(detection.strategy, Some(Strategy::Text));
+	/// ```
+	#[must_use]
+	pub fn detect_with_strategy(&self, path: impl AsRef<Path>, content: &[u8]) -> Detection {
+		let path = path.as_ref();
+		for &strategy in &self.strategies {
+			let media = match strategy {
+				Strategy::Override => self.lookup(path).cloned(),
+				_ => strategy.run(path, content),
+			};
+			if let Some(media) = media {
+				return Detection {
+					media,
+					strategy: Some(strategy),
+				};
+			}
+		}
+		Detection {
+			media: octet_stream(),
+			strategy: None,
+		}
+	}
+
+	/// Like [`check_mismatch`], with this detector's rules declaring the
+	/// type. The strategy order does not matter here: names always declare
+	/// and content is always sniffed.
+	#[must_use]
+	pub fn check_mismatch(&self, path: impl AsRef<Path>, content: &[u8]) -> TypeCheck {
+		let path = path.as_ref();
+		let declared = self.lookup(path).cloned().or_else(|| by_extension(path));
+		let sniffed = by_markup(content).or_else(|| by_magic(content));
+		let verdict = match (&declared, &sniffed) {
+			(Some(declared), Some(sniffed)) if equivalent(declared, sniffed) => Verdict::Consistent,
+			(Some(declared), Some(sniffed)) if is_active(sniffed) && !same_family(declared, sniffed) => {
+				Verdict::Dangerous
+			}
+			(Some(_), Some(_)) => Verdict::Benign,
+			_ => Verdict::Undetermined,
+		};
+		TypeCheck {
+			declared,
+			sniffed,
+			verdict,
+		}
+	}
+
+	/// How many bytes of content this detector looks at: [`SNIFF_LEN`], or
+	/// zero when no enabled stage reads content.
+	#[must_use]
+	pub fn prefix_len(&self) -> usize {
+		let sniffs = self
+			.strategies
+			.iter()
+			.any(|s| matches!(s, Strategy::Sniff | Strategy::Text));
+		if sniffs { SNIFF_LEN } else { 0 }
+	}
+
+	/// Reads [`MimeDetector::prefix_len`] bytes from `reader`, or until it
+	/// ends, and detects the type.
+	pub fn detect_read<R: Read + ?Sized>(
+		&self,
+		path: impl AsRef<Path>,
+		reader: &mut R,
+	) -> Result<Sniffed, Error> {
+		let mut prefix = vec![0; self.prefix_len()];
+		let mut filled = 0;
+		while filled < prefix.len() {
+			match reader.read(&mut prefix[filled..]) {
+				Ok(0) => break,
+				Ok(n) => filled += n,
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+				Err(err) => return Err(Error::Io(err)),
+			}
+		}
+		prefix.truncate(filled);
+		Ok(self.sniffed(path.as_ref(), prefix))
+	}
+
+	/// Like [`MimeDetector::detect_read`], for a Tokio reader (requires
+	/// `tokio` feature).
+	#[cfg(feature = "tokio")]
+	pub async fn detect_async_read<R: tokio::io::AsyncRead + Unpin + ?Sized>(
+		&self,
+		path: impl AsRef<Path>,
+		reader: &mut R,
+	) -> Result<Sniffed, Error> {
+		use tokio::io::AsyncReadExt;
+
+		let mut prefix = vec![0; self.prefix_len()];
+		let mut filled = 0;
+		while filled < prefix.len() {
+			match reader.read(&mut prefix[filled..]).await {
+				Ok(0) => break,
+				Ok(n) => filled += n,
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+				Err(err) => return Err(Error::Io(err)),
+			}
+		}
+		prefix.truncate(filled);
+		Ok(self.sniffed(path.as_ref(), prefix))
+	}
+
+	fn sniffed(&self, path: &Path, prefix: Vec<u8>) -> Sniffed {
+		Sniffed {
+			media: self.detect(path, &prefix),
+			prefix,
+		}
+	}
+
+	/// Like [`content_type`], with the override rules applied first.
+	#[must_use]
+	pub fn content_type(&self, path: impl AsRef<Path>, content: &[u8]) -> MediaType {
+		self
+			.detect(path, content)
+			.with_charset(Charset::Detect, content)
+	}
+}
+
+/// Extension to media type mappings loaded from an Apache `mime.types` file
+/// or an nginx `types` block, for use with [`MimeDetector::types`].
+///
+/// Extensions are lowercased. When an extension is listed more than once
+/// the last mapping wins, as in Apache and nginx, and the replaced one is
+/// recorded in [`TypeMap::duplicates`] so it can be reported.
+///
+/// ```
+/// use serve_static::mime::{MimeDetector, TypeMap};
+///
+/// let types = TypeMap::parse_nginx("types { application/wasm wasm; text/html html htm; }").unwrap();
+/// let detector = MimeDetector::new().types(&types);
+/// assert_eq!(detector.detect("index.HTM", &[]), "text/html");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeMap {
+	extensions: BTreeMap<String, MediaType>,
+	duplicates: Vec<Duplicate>,
+}
+
+/// An extension mapped more than once in a [`TypeMap`] source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Duplicate {
+	/// The repeated extension.
+	pub extension: String,
+	/// The 1-based line of the mapping that replaced the earlier one.
+	pub line: usize,
+	/// The media type that was replaced.
+	pub previous: MediaType,
+	/// The media type now in effect.
+	pub media: MediaType,
+}
+
+impl TypeMap {
+	/// Parses the Apache `mime.types` format: one media type per line
+	/// followed by its extensions, separated by whitespace. Lines may list
+	/// no extensions, and `#` starts a comment.
+	pub fn parse_mime_types(text: &str) -> Result<Self, Error> {
+		let mut map = Self::default();
+		for (index, line) in text.lines().enumerate() {
+			let line_no = index + 1;
+			let line = line.split_once('#').map_or(line, |(before, _)| before);
+			let mut words = line.split_whitespace();
+			let Some(media) = words.next() else {
+				continue;
+			};
+			let media = parse_media(media, line_no)?;
+			for extension in words {
+				map.insert(extension, &media, line_no);
+			}
+		}
+		Ok(map)
+	}
 
-	"application/octet-stream".to_owned()
+	/// Parses nginx `types` syntax: `type ext ...;` statements, either as a
+	/// bare block body or inside one or more `types { ... }` blocks of a
+	/// larger configuration. `#` starts a comment, and words may be quoted.
+	pub fn parse_nginx(text: &str) -> Result<Self, Error> {
+		let tokens = nginx_tokens(text)?;
+		let has_block = tokens
+			.windows(2)
+			.any(|pair| pair[0].text == "types" && pair[1].text == "{" && !pair[0].quoted);
+
+		let mut map = Self::default();
+		let mut statement: Vec<&Token> = Vec::new();
+		let mut inside = !has_block;
+		let mut tokens = tokens.iter().peekable();
+		while let Some(token) = tokens.next() {
+			let bare = !token.quoted;
+			if !inside {
+				if bare && token.text == "types" && tokens.peek().is_some_and(|t| t.text == "{") {
+					tokens.next();
+					inside = true;
+				}
+				continue;
+			}
+			match token.text.as_str() {
+				";" if bare => {
+					map.statement(&statement)?;
+					statement.clear();
+				}
+				"}" if bare && has_block => {
+					if let Some(first) = statement.first() {
+						return Err(types_error(first.line, "missing ';'"));
+					}
+					inside = false;
+				}
+				"{" | "}" if bare => {
+					return Err(types_error(
+						token.line,
+						format!("unexpected '{}'", token.text),
+					));
+				}
+				_ => statement.push(token),
+			}
+		}
+		if let Some(first) = statement.first() {
+			return Err(types_error(first.line, "missing ';'"));
+		}
+		if inside && has_block {
+			let line = text.lines().count();
+			return Err(types_error(line, "unclosed 'types' block"));
+		}
+		Ok(map)
+	}
+
+	fn statement(&mut self, words: &[&Token]) -> Result<(), Error> {
+		let Some((media, extensions)) = words.split_first() else {
+			return Ok(());
+		};
+		let parsed = parse_media(&media.text, media.line)?;
+		for extension in extensions {
+			self.insert(&extension.text, &parsed, extension.line);
+		}
+		Ok(())
+	}
+
+	fn insert(&mut self, extension: &str, media: &MediaType, line: usize) {
+		let extension = extension.trim_start_matches('.').to_lowercase();
+		if extension.is_empty() {
+			return;
+		}
+		if let Some(previous) = self.extensions.insert(extension.clone(), media.clone())
+			&& previous != *media
+		{
+			self.duplicates.push(Duplicate {
+				extension,
+				line,
+				previous,
+				media: media.clone(),
+			});
+		}
+	}
+
+	/// The media type for an extension, without the leading dot.
+	#[must_use]
+	pub fn get(&self, extension: &str) -> Option<&MediaType> {
+		self.extensions.get(&extension.to_lowercase())
+	}
+
+	/// All mappings, ordered by extension.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &MediaType)> {
+		self
+			.extensions
+			.iter()
+			.map(|(extension, media)| (extension.as_str(), media))
+	}
+
+	/// The number of extensions mapped.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.extensions.len()
+	}
+
+	/// Whether no extensions are mapped.
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.extensions.is_empty()
+	}
+
+	/// Extensions that were mapped to a different type more than once, in
+	/// source order.
+	#[must_use]
+	pub fn duplicates(&self) -> &[Duplicate] {
+		&self.duplicates
+	}
+}
+
+fn types_error(line: usize, message: impl Into<String>) -> Error {
+	Error::InvalidTypes {
+		line,
+		message: message.into(),
+	}
+}
+
+fn parse_media(text: &str, line: usize) -> Result<MediaType, Error> {
+	MediaType::parse(text).ok_or_else(|| types_error(line, format!("invalid media type '{text}'")))
+}
+
+/// A word or punctuation token of nginx configuration.
+#[derive(Debug)]
+struct Token {
+	text: String,
+	line: usize,
+	quoted: bool,
+}
+
+/// Splits nginx configuration into words, quoted strings and the `;`, `{`
+/// and `}` punctuation, dropping comments.
+fn nginx_tokens(text: &str) -> Result<Vec<Token>, Error> {
+	let mut tokens = Vec::new();
+	let mut line = 1;
+	let mut chars = text.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'\n' => line += 1,
+			c if c.is_whitespace() => {}
+			'#' => while chars.next_if(|&c| c != '\n').is_some() {},
+			';' | '{' | '}' => tokens.push(Token {
+				text: c.to_string(),
+				line,
+				quoted: false,
+			}),
+			'"' | '\'' => {
+				let start = line;
+				let mut word = String::new();
+				loop {
+					match chars.next() {
+						None => return Err(types_error(start, "unterminated quote")),
+						Some('\\') => word.extend(chars.next()),
+						Some(q) if q == c => break,
+						Some(other) => {
+							if other == '\n' {
+								line += 1;
+							}
+							word.push(other);
+						}
+					}
+				}
+				tokens.push(Token {
+					text: word,
+					line: start,
+					quoted: true,
+				});
+			}
+			c => {
+				let mut word = String::from(c);
+				while let Some(next) =
+					chars.next_if(|&c| !c.is_whitespace() && !matches!(c, ';' | '{' | '}' | '#'))
+				{
+					word.push(next);
+				}
+				tokens.push(Token {
+					text: word,
+					line,
+					quoted: false,
+				});
+			}
+		}
+	}
+	Ok(tokens)
+}
+
+/// How the type implied by a file's name compares with its content, from
+/// [`check_mismatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeCheck {
+	/// The type from the name: overrides, then the extension.
+	pub declared: Option<MediaType>,
+	/// The type recognized from the content.
+	pub sniffed: Option<MediaType>,
+	/// The classification.
+	pub verdict: Verdict,
+}
+
+/// The classification in a [`TypeCheck`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+	/// The name and content agree.
+	Consistent,
+	/// The name or the content gives no type to compare.
+	Undetermined,
+	/// They disagree, but the content is not something a browser would
+	/// render or run, such as a PNG named `.jpg`.
+	Benign,
+	/// Active content (HTML, SVG, XML or PDF) behind a name that does not
+	/// declare it, or that declares an active type of another family, such
+	/// as HTML named `.svg`. Serve it as an attachment or refuse it.
+	Dangerous,
+}
+
+/// Compares the type declared by the name of `path` with the type sniffed
+/// from `content`, using the default [`MimeDetector`].
+///
+/// ```
+/// use serve_static::mime::{MimeDetector, Verdict};
+///
+/// let detector = MimeDetector::new().extension("jpg", "image/jpeg".parse().unwrap());
+/// let check = detector.check_mismatch("evil.jpg", b"<svg onload=alert(1)>");
+/// assert_eq!(check.verdict, Verdict::Dangerous);
+/// assert_eq!(check.sniffed.unwrap(), "image/svg+xml");
+/// ```
+#[must_use]
+pub fn check_mismatch(path: impl AsRef<Path>, content: &[u8]) -> TypeCheck {
+	MimeDetector::new().check_mismatch(path, content)
+}
+
+/// Whether browsers may render or execute the type as a document.
+fn is_active(media: &MediaType) -> bool {
+	media.suffix() == Some("xml")
+		|| matches!(
+			media.essence(),
+			"text/html" | "text/xml" | "application/xml" | "application/pdf"
+		)
+}
+
+/// Whether two active types are read by the same engine: both XML (any
+/// `+xml` type included), or the same essence.
+fn same_family(declared: &MediaType, sniffed: &MediaType) -> bool {
+	let xml = |m: &MediaType| m.suffix() == Some("xml") || m.subtype() == "xml";
+	declared.essence() == sniffed.essence() || (xml(declared) && xml(sniffed))
+}
+
+/// Whether two types name the same content, treating the XML aliases and
+/// types sharing a `+xml` suffix with plain XML as equal.
+fn equivalent(declared: &MediaType, sniffed: &MediaType) -> bool {
+	let xml = |m: &MediaType| m.suffix() == Some("xml") || m.subtype() == "xml";
+	declared.essence() == sniffed.essence()
+		|| (xml(declared) && matches!(sniffed.essence(), "text/xml" | "application/xml"))
+}
+
+fn text_html() -> MediaType {
+	MediaType {
+		essence: "text/html".to_owned(),
+		slash: 4,
+		params: Vec::new(),
+	}
+}
+
+fn text_plain() -> MediaType {
+	MediaType {
+		essence: "text/plain".to_owned(),
+		slash: 4,
+		params: Vec::new(),
+	}
+}
+
+fn octet_stream() -> MediaType {
+	MediaType {
+		essence: "application/octet-stream".to_owned(),
+		slash: 11,
+		params: Vec::new(),
+	}
 }
 
 /// Generates a weak ETag from file metadata.
@@ -180,6 +1389,475 @@ mod tests {
 		// Verify that PathBuf can be passed directly (impl AsRef<Path>)
 		let p = std::path::PathBuf::from("readme.txt");
 		let mime = detect(p, b"hello");
-		assert!(!mime.is_empty());
+		assert!(!mime.essence().is_empty());
+	}
+
+	#[test]
+	fn parse_media_type() {
+		let mt = MediaType::parse("image/SVG+XML").unwrap();
+		assert_eq!(mt.type_(), "image");
+		assert_eq!(mt.subtype(), "svg+xml");
+		assert_eq!(mt.suffix(), Some("xml"));
+		assert!(mt.is_text());
+
+		let mt = MediaType::parse(r#"text/plain;format=flowed; title="a \"b\"; c" ;x"#).unwrap();
+		assert_eq!(mt.param("FORMAT"), Some("flowed"));
+		assert_eq!(mt.param("title"), Some(r#"a "b"; c"#));
+		assert_eq!(mt.params().count(), 2);
+		assert_eq!(
+			mt.to_string(),
+			r#"text/plain; format=flowed; title="a \"b\"; c""#
+		);
+		assert_eq!(MediaType::parse(&mt.to_string()), Some(mt));
+
+		assert_eq!(MediaType::parse("text"), None);
+		assert_eq!(MediaType::parse("text/"), None);
+		assert_eq!(MediaType::parse("te xt/plain"), None);
+	}
+
+	#[test]
+	fn textual_types() {
+		for text in [
+			"text/css",
+			"application/json",
+			"application/ld+json",
+			"application/javascript",
+			"application/xml",
+			"image/svg+xml",
+		] {
+			assert!(MediaType::parse(text).unwrap().is_text(), "{text}");
+		}
+		for binary in ["image/png", "application/octet-stream", "font/woff2"] {
+			assert!(!MediaType::parse(binary).unwrap().is_text(), "{binary}");
+		}
+	}
+
+	#[test]
+	fn charset_policy() {
+		let css = MediaType::new("text", "css").unwrap();
+		let png = MediaType::new("image", "png").unwrap();
+		assert_eq!(
+			css.clone().with_charset(Charset::Detect, b"a{}"),
+			"text/css; charset=utf-8"
+		);
+		assert_eq!(
+			css.clone().with_charset(Charset::Detect, &[0xFF, 0x00]),
+			"text/css"
+		);
+		assert_eq!(css.clone().with_charset(Charset::Detect, b""), "text/css");
+		assert_eq!(css.clone().with_charset(Charset::Never, b"a{}"), "text/css");
+		assert_eq!(
+			css.clone().with_charset(Charset::Utf8, &[0xFF, 0x00]),
+			"text/css; charset=utf-8"
+		);
+		assert_eq!(png.with_charset(Charset::Utf8, b""), "image/png");
+
+		// BOMs name the encoding, even when the rest is not UTF-8.
+		assert_eq!(
+			css
+				.clone()
+				.with_charset(Charset::Detect, &[0xFF, 0xFE, b'a', 0x00]),
+			"text/css; charset=utf-16le"
+		);
+		// A multibyte character cut off by a prefix read is still UTF-8.
+		assert_eq!(
+			css.clone().with_charset(Charset::Detect, &[b'a', 0xC3]),
+			"text/css; charset=utf-8"
+		);
+		// An explicit charset is kept.
+		let latin = css.with_param("charset", "iso-8859-1");
+		assert_eq!(
+			latin.with_charset(Charset::Utf8, b""),
+			"text/css; charset=iso-8859-1"
+		);
+	}
+
+	#[test]
+	fn detector_overrides() {
+		let media = |s: &str| s.parse::<MediaType>().unwrap();
+		let detector = MimeDetector::web()
+			.extension(".PBF", media("application/x-protobuf"))
+			.extension("gz", media("application/gzip"))
+			.extension("tar.gz", media("application/x-gtar"))
+			.filename("Dockerfile", media("text/x-dockerfile"))
+			.glob("*.map", media("application/json"))
+			.glob("/static/**/*.js", media("text/javascript"))
+			.glob("*.js", media("application/x-unused"));
+
+		assert_eq!(detector.detect("app.wasm", &[]), "application/wasm");
+		assert_eq!(detector.detect("mod.mjs", &[]), "text/javascript");
+		assert_eq!(
+			detector.detect("site.webmanifest", &[]),
+			"application/manifest+json"
+		);
+		assert_eq!(
+			detector.detect(".well-known/apple-app-site-association", b"{}"),
+			"application/json"
+		);
+		assert_eq!(
+			detector.detect("tiles/0.pbf", &[]),
+			"application/x-protobuf"
+		);
+		assert_eq!(detector.detect("a.tar.gz", &[]), "application/x-gtar");
+		assert_eq!(detector.detect("a.gz", &[]), "application/gzip");
+		assert_eq!(detector.detect("x/Dockerfile", &[]), "text/x-dockerfile");
+		assert_eq!(detector.detect("dockerfile", b"FROM"), "text/plain");
+		assert_eq!(detector.detect("app.js.map", &[]), "application/json");
+		assert_eq!(detector.detect("/static/js/app.js", &[]), "text/javascript");
+		assert_eq!(detector.detect("lib/app.js", &[]), "application/x-unused");
+		// A leading dot is a hidden file, not an extension.
+		assert_eq!(detector.lookup(".pbf"), None);
+		assert_eq!(detector.lookup("README"), None);
+		assert_eq!(
+			detector.content_type("mod.mjs", b"export {}"),
+			"text/javascript; charset=utf-8"
+		);
+	}
+
+	#[test]
+	#[cfg(all(feature = "extension", feature = "sniff"))]
+	fn strategy_order() {
+		let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+		let default = MimeDetector::new();
+		let sniff_first = MimeDetector::new().strategies(Strategy::SNIFF_FIRST);
+		let nosniff = MimeDetector::new().strategies(Strategy::NOSNIFF);
+
+		let detection = default.detect_with_strategy("style.css", &png);
+		assert_eq!(detection.media, "text/css");
+		assert_eq!(detection.strategy, Some(Strategy::Extension));
+
+		let detection = sniff_first.detect_with_strategy("style.css", &png);
+		assert_eq!(detection.media, "image/png");
+		assert_eq!(detection.strategy, Some(Strategy::Sniff));
+		// Text never beats a name, even when content comes first.
+		assert_eq!(sniff_first.detect("style.css", b"a{}"), "text/css");
+
+		assert_eq!(nosniff.detect("image", &png), "application/octet-stream");
+		assert_eq!(
+			nosniff.detect_with_strategy("notes", b"text").strategy,
+			None
+		);
+		assert_eq!(nosniff.detect("a.css", &png), "text/css");
+
+		let overridden = MimeDetector::new()
+			.extension("css", "text/x-custom".parse().unwrap())
+			.strategies([Strategy::Sniff, Strategy::Override]);
+		assert_eq!(overridden.detect("a.css", &png), "image/png");
+		let detection = overridden.detect_with_strategy("a.css", b"a{}");
+		assert_eq!(detection.strategy, Some(Strategy::Override));
+
+		let none = MimeDetector::new().strategies([]);
+		assert_eq!(none.detect("a.css", &png), "application/octet-stream");
+	}
+
+	#[test]
+	fn text_or_binary() {
+		let text = |content: &[u8]| by_text(content).map(|m| m.to_string());
+		assert_eq!(text(b"caf\xC3\xA9").as_deref(), Some("text/plain"));
+		// A prefix read may cut a multibyte character in half.
+		assert_eq!(text(b"caf\xC3").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"caf\xC3 au lait").as_deref(), None);
+		assert_eq!(
+			text(b"tab\tnew\r\nfeed\x0Cesc\x1B").as_deref(),
+			Some("text/plain")
+		);
+		assert_eq!(text(b"PK\x03\x04 names").as_deref(), None);
+		assert_eq!(text(b"nul\0").as_deref(), None);
+		assert_eq!(
+			text(&[0xFF, 0xFE, b'h', 0x00, b'i', 0x00]).as_deref(),
+			Some("text/plain")
+		);
+		assert_eq!(
+			text(&[0xFE, 0xFF, 0x00, b'h']).as_deref(),
+			Some("text/plain")
+		);
+		assert_eq!(text(b"\xEF\xBB\xBFhello").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"").as_deref(), None);
+	}
+
+	#[test]
+	fn text_recognized_by_content() {
+		let text = |content: &[u8]| by_text(content).map(|m| m.to_string());
+		assert_eq!(
+			text(b"<!DOCTYPE html>\n<html>").as_deref(),
+			Some("text/html")
+		);
+		assert_eq!(
+			text(b"<?xml version=\"1.0\"?><rss>").as_deref(),
+			Some("text/xml")
+		);
+		assert_eq!(
+			text(b"<svg xmlns=\"http://www.w3.org/2000/svg\">").as_deref(),
+			Some("image/svg+xml")
+		);
+		assert_eq!(text(b"%PDF-1.7 text").as_deref(), Some("text/plain"));
+		assert_eq!(
+			text(b" {\"a\": [1, -2.5e3, true, null], \"b\": {}}\n").as_deref(),
+			Some("application/json")
+		);
+		assert_eq!(
+			text(b"[{\"name\": \"x\"}, {\"na").as_deref(),
+			Some("application/json")
+		);
+		assert_eq!(text(b"[1, 2, fal").as_deref(), Some("application/json"));
+		assert_eq!(text(b"[]").as_deref(), Some("application/json"));
+		assert_eq!(text(b"[1] see the footnote").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"{ not json }").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"{\"a\" 1}").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"[1}").as_deref(), Some("text/plain"));
+		assert_eq!(text(b"\"just a string\"").as_deref(), Some("text/plain"));
+	}
+
+	#[test]
+	fn detect_by_content_without_extension() {
+		assert_eq!(
+			detect("upload", b"<?xml version=\"1.0\"?>\n<svg>"),
+			"image/svg+xml"
+		);
+		assert_eq!(detect("upload", b"{\"ok\": true}"), "application/json");
+		assert_eq!(detect("upload", b"<html><body>"), "text/html");
+		assert_eq!(
+			content_type("upload", &[0xFF, 0xFE, b'h', 0x00]),
+			"text/plain; charset=utf-16le"
+		);
+	}
+
+	/// Hands out at most three bytes per call, with an interruption first.
+	struct Trickle<'a> {
+		data: &'a [u8],
+		interrupted: bool,
+	}
+
+	impl Read for Trickle<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+			if !self.interrupted {
+				self.interrupted = true;
+				return Err(io::ErrorKind::Interrupted.into());
+			}
+			let n = buf.len().min(3).min(self.data.len());
+			buf[..n].copy_from_slice(&self.data[..n]);
+			self.data = &self.data[n..];
+			Ok(n)
+		}
+	}
+
+	#[test]
+	fn detect_read_bounded_prefix() {
+		let mut long = b"<svg>".to_vec();
+		long.resize(SNIFF_LEN + 100, b' ');
+		let mut reader = Trickle {
+			data: &long,
+			interrupted: false,
+		};
+		let sniffed = detect_read("upload", &mut reader).unwrap();
+		assert_eq!(sniffed.media, "image/svg+xml");
+		assert_eq!(sniffed.content_type(), "image/svg+xml; charset=utf-8");
+		assert_eq!(sniffed.prefix, long[..SNIFF_LEN]);
+		assert_eq!(reader.data.len(), 100);
+
+		let mut short: &[u8] = b"hi";
+		let sniffed = detect_read("upload", &mut short).unwrap();
+		assert_eq!(
+			(sniffed.media.essence(), sniffed.prefix.as_slice()),
+			("text/plain", &b"hi"[..])
+		);
+
+		// Nothing is read when no stage looks at content.
+		let nosniff = MimeDetector::new().strategies(Strategy::NOSNIFF);
+		assert_eq!(nosniff.prefix_len(), 0);
+		let mut untouched: &[u8] = b"<html>";
+		let sniffed = nosniff.detect_read("upload", &mut untouched).unwrap();
+		assert!(sniffed.prefix.is_empty());
+		assert_eq!(untouched, b"<html>");
+	}
+
+	#[test]
+	fn detect_read_error() {
+		struct Broken;
+		impl Read for Broken {
+			fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+				Err(io::ErrorKind::BrokenPipe.into())
+			}
+		}
+		assert!(matches!(detect_read("x", &mut Broken), Err(Error::Io(_))));
+	}
+
+	#[test]
+	#[cfg(feature = "tokio")]
+	fn detect_async_read_prefix() {
+		use std::future::Future;
+		use std::pin::pin;
+		use std::task::{Context, Poll, Waker};
+
+		let mut data: &[u8] = b"{\"a\": 1} and then some";
+		let mut future = pin!(detect_async_read("upload", &mut data));
+		let mut cx = Context::from_waker(Waker::noop());
+		// A byte slice is always ready, so one poll completes.
+		let Poll::Ready(sniffed) = future.as_mut().poll(&mut cx) else {
+			panic!("slice reader should be ready");
+		};
+		let sniffed = sniffed.unwrap();
+		assert_eq!(sniffed.media, "text/plain");
+		assert_eq!(sniffed.prefix, b"{\"a\": 1} and then some");
+	}
+
+	#[test]
+	fn markup_sniffing() {
+		let sniff = |content: &[u8]| by_markup(content).map(|m| m.to_string());
+		assert_eq!(sniff(b"  <!DOCTYPE html>").as_deref(), Some("text/html"));
+		assert_eq!(sniff(b"\xEF\xBB\xBF<p>hi").as_deref(), Some("text/html"));
+		assert_eq!(sniff(b"<SCRIPT>alert(1)").as_deref(), Some("text/html"));
+		assert_eq!(sniff(b"<pre>").as_deref(), None);
+		assert_eq!(
+			sniff(b"<?xml version=\"1.0\"?><feed/>").as_deref(),
+			Some("text/xml")
+		);
+		assert_eq!(
+			sniff(b"<?xml version=\"1.0\"?>\n<SVG xmlns=\"\">").as_deref(),
+			Some("image/svg+xml")
+		);
+		assert_eq!(sniff(b"%PDF-1.7").as_deref(), Some("application/pdf"));
+		assert_eq!(sniff(b"plain words").as_deref(), None);
+	}
+
+	#[test]
+	#[cfg(feature = "extension")]
+	fn mismatch_verdicts() {
+		let verdict = |path: &str, content: &[u8]| check_mismatch(path, content).verdict;
+
+		assert_eq!(verdict("page.html", b"<html><body>"), Verdict::Consistent);
+		assert_eq!(verdict("icon.svg", b"<?xml?><svg>"), Verdict::Consistent);
+		assert_eq!(verdict("feed.atom", b"<?xml?><feed>"), Verdict::Consistent);
+		assert_eq!(verdict("photo.jpg", b"<html><script>"), Verdict::Dangerous);
+		assert_eq!(verdict("photo.png", b"<svg onload=x>"), Verdict::Dangerous);
+		assert_eq!(verdict("notes.txt", b"<iframe src=x>"), Verdict::Dangerous);
+		assert_eq!(verdict("avatar.gif", b"%PDF-1.4"), Verdict::Dangerous);
+		// An active declared type only covers content of its own family.
+		assert_eq!(verdict("x.svg", b"<html><script>"), Verdict::Dangerous);
+		assert_eq!(verdict("doc.pdf", b"<html>"), Verdict::Dangerous);
+		assert_eq!(verdict("page.html", b"<svg>"), Verdict::Dangerous);
+		assert_eq!(verdict("data.xml", b"<svg>"), Verdict::Benign);
+		assert_eq!(verdict("notes.txt", b"plain"), Verdict::Undetermined);
+		assert_eq!(verdict("upload", b"<html>"), Verdict::Undetermined);
+
+		let detector = MimeDetector::new().extension("img", "image/png".parse().unwrap());
+		let check = detector.check_mismatch("a.img", b"<svg>");
+		assert_eq!(check.declared.unwrap(), "image/png");
+		assert_eq!(check.verdict, Verdict::Dangerous);
+	}
+
+	#[test]
+	#[cfg(all(feature = "extension", feature = "sniff"))]
+	fn mismatch_binary_formats() {
+		let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+		assert_eq!(
+			check_mismatch("photo.png", &png).verdict,
+			Verdict::Consistent
+		);
+		let check = check_mismatch("photo.jpg", &png);
+		assert_eq!(check.verdict, Verdict::Benign);
+		assert_eq!(check.sniffed.unwrap(), "image/png");
+	}
+
+	#[test]
+	fn mime_types_file() {
+		let text = "\
+# MIME type			Extensions
+application/json		json
+application/x-empty
+text/html			html htm   # trailing comment
+image/JPEG			jpg JPEG jpe
+text/plain			txt
+text/x-c			c cc
+text/x-c++			cc
+application/json		JSON
+";
+		let types = TypeMap::parse_mime_types(text).unwrap();
+		assert_eq!(types.len(), 9);
+		assert_eq!(types.get("HTM").unwrap(), "text/html");
+		assert_eq!(types.get("jpeg").unwrap(), "image/jpeg");
+		assert_eq!(types.get("json").unwrap(), "application/json");
+		assert_eq!(types.get("comment"), None);
+		// The last mapping wins, and changed mappings are reported.
+		assert_eq!(types.get("cc").unwrap(), "text/x-c++");
+		assert_eq!(types.duplicates().len(), 1);
+		let dup = &types.duplicates()[0];
+		assert_eq!((dup.extension.as_str(), dup.line), ("cc", 8));
+		assert_eq!(dup.previous, "text/x-c");
+		assert_eq!(dup.media, "text/x-c++");
+
+		let err = TypeMap::parse_mime_types("text/plain txt\nbogus txt\n").unwrap_err();
+		assert!(matches!(err, Error::InvalidTypes { line: 2, .. }));
+	}
+
+	#[test]
+	fn nginx_types_block() {
+		let conf = r#"
+http {
+    include       mime.types;
+    default_type  application/octet-stream;
+
+    types {
+        text/html                 html htm shtml;
+        # comment { ; }
+        "application/javascript"  js
+                                  mjs;
+        image/svg+xml             svg svgz;
+        application/wasm          wasm;
+        text/javascript           js;
+    }
+    server { listen 80; }
+}
+"#;
+		let types = TypeMap::parse_nginx(conf).unwrap();
+		assert_eq!(types.len(), 8);
+		assert_eq!(types.get("mjs").unwrap(), "application/javascript");
+		assert_eq!(types.get("js").unwrap(), "text/javascript");
+		assert_eq!(types.get("svgz").unwrap(), "image/svg+xml");
+		assert_eq!(types.get("80"), None);
+		assert_eq!(types.duplicates().len(), 1);
+		assert_eq!(types.duplicates()[0].line, 13);
+
+		// A bare block body, as in an included snippet.
+		let body = TypeMap::parse_nginx("text/plain txt; application/pdf pdf;").unwrap();
+		assert_eq!(body.get("pdf").unwrap(), "application/pdf");
+
+		let detector = MimeDetector::new().types(&types);
+		assert_eq!(detector.detect("a.mjs", &[]), "application/javascript");
+	}
+
+	#[test]
+	fn nginx_errors() {
+		let missing = TypeMap::parse_nginx("types {\n text/html html\n}").unwrap_err();
+		assert!(matches!(missing, Error::InvalidTypes { line: 2, .. }));
+		let unclosed = TypeMap::parse_nginx("types {\n text/html html;\n");
+		assert!(matches!(unclosed, Err(Error::InvalidTypes { .. })));
+		let quote = TypeMap::parse_nginx("text/html 'html;");
+		assert!(matches!(quote, Err(Error::InvalidTypes { line: 1, .. })));
+		let invalid = TypeMap::parse_nginx("types { html; }");
+		assert!(matches!(invalid, Err(Error::InvalidTypes { line: 1, .. })));
+	}
+
+	#[test]
+	fn media_type_from_str() {
+		assert!("application/wasm".parse::<MediaType>().is_ok());
+		let err = "wasm".parse::<MediaType>().unwrap_err();
+		assert!(matches!(err, Error::InvalidMediaType(ref s) if s == "wasm"));
+	}
+
+	#[test]
+	#[cfg(feature = "extension")]
+	fn content_type_for_files() {
+		assert_eq!(
+			content_type("index.html", b"<p>"),
+			"text/html; charset=utf-8"
+		);
+		// Nothing read, nothing known about the encoding.
+		assert_eq!(content_type("latin1.txt", &[]), "text/plain");
+		assert_eq!(
+			content_type("logo.svg", b"<svg/>"),
+			"image/svg+xml; charset=utf-8"
+		);
+		assert_eq!(content_type("photo.jpg", &[0xFF, 0xD8]), "image/jpeg");
 	}
 }
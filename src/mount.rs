@@ -0,0 +1,441 @@
+/* src/mount.rs */
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+
+use crate::error::Error;
+use crate::path;
+
+/// Characters re-encoded when a decoded remainder is handed back for
+/// resolution, so that it decodes to the same names a second time.
+const REENCODE: &AsciiSet = &CONTROLS.add(b'%');
+
+/// A URL prefix mapped to a jailed filesystem root.
+///
+/// Each mount carries its own resolution options, so `/uploads` can forbid
+/// symlinks and use strict mode while `/static` follows symlinks freely.
+///
+/// ```
+/// let mount = serve_static::mount::Mount::new("/static/", "/srv/static")
+///     .allow_symlinks(true)
+///     .strict(true);
+/// assert_eq!(mount.prefix(), "/static");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+	prefix: String,
+	root: PathBuf,
+	allow_symlinks: bool,
+	strict: bool,
+}
+
+impl Mount {
+	/// Creates a mount serving `root` under the URL `prefix`.
+	///
+	/// The prefix is normalized to start with `/` and to carry no trailing
+	/// `/`; both `""` and `"/"` create a catch-all mount. Symlinks are not
+	/// followed and strict mode is off by default.
+	#[must_use]
+	pub fn new(prefix: &str, root: impl Into<PathBuf>) -> Self {
+		let trimmed = prefix.trim_matches('/');
+		let prefix = if trimmed.is_empty() {
+			String::new()
+		} else {
+			format!("/{trimmed}")
+		};
+		Self {
+			prefix,
+			root: root.into(),
+			allow_symlinks: false,
+			strict: false,
+		}
+	}
+
+	/// Sets whether symlinks may point outside this mount's root.
+	///
+	/// See the `allow_symlinks` argument of [`path::resolve`].
+	#[must_use]
+	pub fn allow_symlinks(mut self, allow: bool) -> Self {
+		self.allow_symlinks = allow;
+		self
+	}
+
+	/// Sets whether URIs are resolved with [`path::resolve_strict`].
+	#[must_use]
+	pub fn strict(mut self, strict: bool) -> Self {
+		self.strict = strict;
+		self
+	}
+
+	/// The normalized URL prefix. Empty for the catch-all mount.
+	#[must_use]
+	pub fn prefix(&self) -> &str {
+		&self.prefix
+	}
+
+	/// The filesystem root served by this mount.
+	#[must_use]
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	/// Whether symlinks may point outside this mount's root.
+	#[must_use]
+	pub fn allows_symlinks(&self) -> bool {
+		self.allow_symlinks
+	}
+
+	/// Whether this mount uses strict resolution.
+	#[must_use]
+	pub fn is_strict(&self) -> bool {
+		self.strict
+	}
+
+	/// Returns the part of `uri` below this mount's prefix, or `None` when
+	/// the prefix does not match on a segment boundary.
+	///
+	/// The prefix is compared with the percent-decoded segments of `uri`,
+	/// skipping empty and `.` segments, so `/st%61tic//app.js` matches
+	/// `/static` too. The remainder is returned as written, unless the
+	/// prefix ends inside an encoded `%2F`, in which case the rest of that
+	/// segment is re-encoded.
+	///
+	/// ```
+	/// let mount = serve_static::mount::Mount::new("/static", "/srv/static");
+	/// assert_eq!(mount.strip("/static/app.js").unwrap(), "/app.js");
+	/// assert_eq!(mount.strip("/st%61tic/app.js").unwrap(), "/app.js");
+	/// assert_eq!(mount.strip("/static").unwrap(), "");
+	/// assert_eq!(mount.strip("/statics/app.js"), None);
+	/// ```
+	#[must_use]
+	pub fn strip<'u>(&self, uri: &'u str) -> Option<Cow<'u, str>> {
+		let mut wanted = self.prefix.split('/').filter(|s| !s.is_empty()).peekable();
+		let mut start = 0;
+		for raw in uri.split('/') {
+			if wanted.peek().is_none() {
+				break;
+			}
+			let end = start + raw.len();
+			let decoded = percent_decode_str(raw)
+				.decode_utf8()
+				.unwrap_or(Cow::Borrowed(raw));
+			let mut parts = decoded.split('/').filter(|part| !matches!(*part, "" | "."));
+			for part in parts.by_ref() {
+				if wanted.next()? != part {
+					return None;
+				}
+				if wanted.peek().is_none() {
+					break;
+				}
+			}
+			let inner: Vec<_> = parts.collect();
+			if wanted.peek().is_none() && !inner.is_empty() {
+				let mut rest = String::new();
+				for part in inner {
+					rest.push('/');
+					rest.extend(utf8_percent_encode(part, REENCODE));
+				}
+				rest.push_str(&uri[end..]);
+				return Some(Cow::Owned(rest));
+			}
+			start = end + 1;
+		}
+		if wanted.peek().is_some() {
+			return None;
+		}
+		Some(Cow::Borrowed(
+			uri.get(start.saturating_sub(1)..).unwrap_or_default(),
+		))
+	}
+
+	/// Resolves a URI that is already relative to this mount.
+	///
+	/// Applies [`path::resolve`] or [`path::resolve_strict`] with this
+	/// mount's options.
+	pub fn resolve(&self, relative_uri: &str) -> Result<PathBuf, Error> {
+		if self.strict {
+			path::resolve_strict(&self.root, relative_uri, self.allow_symlinks)
+		} else {
+			path::resolve(&self.root, relative_uri, self.allow_symlinks)
+		}
+	}
+}
+
+/// The outcome of resolving a URI against a [`MountTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved<'a> {
+	/// The mount that matched the URI.
+	pub mount: &'a Mount,
+	/// The jailed filesystem path under the mount's root.
+	pub path: PathBuf,
+}
+
+/// Maps URL prefixes to jailed roots using longest-prefix matching.
+///
+/// Prefixes only match on whole segments, so `/static` serves
+/// `/static/app.js` but not `/statics/app.js`. Prefixes are matched
+/// against the decoded URI, as described at [`Mount::strip`]; callers strip
+/// query strings and fragments beforehand, just as with [`path::resolve`].
+///
+/// ```
+/// use serve_static::mount::{Mount, MountTable};
+///
+/// let tmp = std::env::temp_dir();
+/// let table = MountTable::new()
+///     .with(Mount::new("/", &tmp))
+///     .with(Mount::new("/static", &tmp));
+///
+/// let hit = table.resolve("/static/app.js").unwrap();
+/// assert_eq!(hit.mount.prefix(), "/static");
+/// assert!(hit.path.ends_with("app.js"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MountTable {
+	/// Kept sorted by descending prefix length so the first match wins.
+	mounts: Vec<Mount>,
+}
+
+impl MountTable {
+	/// Creates an empty mount table.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a mount and returns the table, for chained construction.
+	#[must_use]
+	pub fn with(mut self, mount: Mount) -> Self {
+		self.insert(mount);
+		self
+	}
+
+	/// Adds a mount, replacing any existing mount with the same prefix.
+	pub fn insert(&mut self, mount: Mount) {
+		self.mounts.retain(|m| m.prefix != mount.prefix);
+		let pos = self
+			.mounts
+			.partition_point(|m| m.prefix.len() >= mount.prefix.len());
+		self.mounts.insert(pos, mount);
+	}
+
+	/// All mounts, longest prefix first.
+	#[must_use]
+	pub fn mounts(&self) -> &[Mount] {
+		&self.mounts
+	}
+
+	/// Finds the mount with the longest prefix matching `uri`, together with
+	/// the remainder of the URI below that prefix.
+	#[must_use]
+	pub fn find<'u>(&self, uri: &'u str) -> Option<(&Mount, Cow<'u, str>)> {
+		self
+			.mounts
+			.iter()
+			.find_map(|mount| mount.strip(uri).map(|rest| (mount, rest)))
+	}
+
+	/// Resolves `uri` to the matching mount and a jailed path below its root.
+	///
+	/// Returns [`Error::NoMount`] when no prefix matches.
+	pub fn resolve(&self, uri: &str) -> Result<Resolved<'_>, Error> {
+		let (mount, rest) = self.find(uri).ok_or(Error::NoMount)?;
+		if mount.strict {
+			// The remainder alone cannot show how the prefix was spelled.
+			path::normalize_strict(uri)?;
+		}
+		let path = mount.resolve(&rest)?;
+		Ok(Resolved { mount, path })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::error::StrictViolation;
+
+	struct Roots {
+		static_dir: tempfile::TempDir,
+		uploads_dir: tempfile::TempDir,
+		default_dir: tempfile::TempDir,
+	}
+
+	fn make_roots() -> Roots {
+		let roots = Roots {
+			static_dir: tempfile::tempdir().unwrap(),
+			uploads_dir: tempfile::tempdir().unwrap(),
+			default_dir: tempfile::tempdir().unwrap(),
+		};
+		std::fs::write(roots.static_dir.path().join("app.js"), b"js").unwrap();
+		std::fs::write(roots.uploads_dir.path().join("photo.jpg"), b"jpg").unwrap();
+		std::fs::write(roots.default_dir.path().join("index.html"), b"<html>").unwrap();
+		roots
+	}
+
+	fn make_table(roots: &Roots) -> MountTable {
+		MountTable::new()
+			.with(Mount::new("/", roots.default_dir.path()))
+			.with(Mount::new("/static", roots.static_dir.path()))
+			.with(Mount::new("/static/uploads", roots.uploads_dir.path()))
+	}
+
+	#[test]
+	fn prefix_normalization() {
+		assert_eq!(Mount::new("static", "/x").prefix(), "/static");
+		assert_eq!(Mount::new("/static/", "/x").prefix(), "/static");
+		assert_eq!(Mount::new("/", "/x").prefix(), "");
+		assert_eq!(Mount::new("", "/x").prefix(), "");
+	}
+
+	#[test]
+	fn longest_prefix_wins() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let hit = table.resolve("/static/uploads/photo.jpg").unwrap();
+		assert_eq!(hit.mount.prefix(), "/static/uploads");
+		let canon = roots.uploads_dir.path().canonicalize().unwrap();
+		assert_eq!(hit.path, canon.join("photo.jpg"));
+	}
+
+	#[test]
+	fn shorter_prefix_matches() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let hit = table.resolve("/static/app.js").unwrap();
+		assert_eq!(hit.mount.prefix(), "/static");
+		let canon = roots.static_dir.path().canonicalize().unwrap();
+		assert_eq!(hit.path, canon.join("app.js"));
+	}
+
+	#[test]
+	fn prefix_matches_whole_segments_only() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let hit = table.resolve("/statics/app.js").unwrap();
+		assert_eq!(hit.mount.prefix(), "");
+		let canon = roots.default_dir.path().canonicalize().unwrap();
+		assert_eq!(hit.path, canon.join("statics/app.js"));
+	}
+
+	#[test]
+	fn encoded_prefix_matches() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let canon = roots.static_dir.path().canonicalize().unwrap();
+		for uri in [
+			"/st%61tic/app.js",
+			"//static/./app.js",
+			"/%73tatic%2Fapp.js",
+		] {
+			let hit = table.resolve(uri).unwrap();
+			assert_eq!(hit.mount.prefix(), "/static", "{uri}");
+			assert_eq!(hit.path, canon.join("app.js"), "{uri}");
+		}
+		let hit = table.resolve("/static/upl%6Fads/photo.jpg").unwrap();
+		assert_eq!(hit.mount.prefix(), "/static/uploads");
+		// A remainder split out of an encoded segment keeps its escapes.
+		let mount = Mount::new("/static", roots.static_dir.path());
+		assert_eq!(mount.strip("/static%2Fa%2525b/c").unwrap(), "/a%2525b/c");
+	}
+
+	#[test]
+	fn encoded_prefix_keeps_strict_rules() {
+		let roots = make_roots();
+		let table =
+			make_table(&roots).with(Mount::new("/static/uploads", roots.uploads_dir.path()).strict(true));
+		for uri in ["/static//uploads/photo.jpg", "/static/./uploads/photo.jpg"] {
+			assert!(
+				matches!(table.resolve(uri), Err(Error::StrictViolation(_))),
+				"{uri}"
+			);
+		}
+		assert!(table.resolve("/static/upl%6Fads/photo.jpg").is_ok());
+	}
+
+	#[test]
+	fn bare_prefix_resolves_to_mount_root() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let hit = table.resolve("/static").unwrap();
+		let canon = roots.static_dir.path().canonicalize().unwrap();
+		assert_eq!(hit.path, canon);
+	}
+
+	#[test]
+	fn traversal_stays_inside_mount() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let hit = table.resolve("/static/../../uploads/photo.jpg").unwrap();
+		assert_eq!(hit.mount.prefix(), "/static");
+		let canon = roots.static_dir.path().canonicalize().unwrap();
+		assert!(hit.path.starts_with(&canon));
+	}
+
+	#[test]
+	fn no_matching_mount() {
+		let roots = make_roots();
+		let table = MountTable::new().with(Mount::new("/static", roots.static_dir.path()));
+		assert!(matches!(
+			table.resolve("/docs/index.html"),
+			Err(Error::NoMount)
+		));
+		assert!(matches!(
+			MountTable::new().resolve("/"),
+			Err(Error::NoMount)
+		));
+	}
+
+	#[test]
+	fn insert_replaces_same_prefix() {
+		let roots = make_roots();
+		let mut table = make_table(&roots);
+		table.insert(Mount::new("/static/", roots.default_dir.path()));
+		assert_eq!(table.mounts().len(), 3);
+		let hit = table.resolve("/static/index.html").unwrap();
+		assert_eq!(hit.mount.root(), roots.default_dir.path());
+	}
+
+	#[test]
+	fn mounts_sorted_longest_first() {
+		let roots = make_roots();
+		let table = make_table(&roots);
+		let prefixes: Vec<_> = table.mounts().iter().map(Mount::prefix).collect();
+		assert_eq!(prefixes, ["/static/uploads", "/static", ""]);
+	}
+
+	#[test]
+	fn strict_mount_rejects() {
+		let roots = make_roots();
+		let table =
+			make_table(&roots).with(Mount::new("/static/uploads", roots.uploads_dir.path()).strict(true));
+		let result = table.resolve("/static/uploads/../../etc/passwd");
+		assert!(matches!(
+			result,
+			Err(Error::StrictViolation(StrictViolation::EscapesRoot))
+		));
+		// The lenient /static mount still clamps.
+		assert!(table.resolve("/static/../../etc/passwd").is_ok());
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn per_mount_symlink_policy() {
+		let roots = make_roots();
+		let outside = tempfile::tempdir().unwrap();
+		let secret = outside.path().join("secret.txt");
+		std::fs::write(&secret, b"secret").unwrap();
+		std::os::unix::fs::symlink(&secret, roots.static_dir.path().join("link.txt")).unwrap();
+		std::os::unix::fs::symlink(&secret, roots.uploads_dir.path().join("link.txt")).unwrap();
+
+		let table = MountTable::new()
+			.with(Mount::new("/static", roots.static_dir.path()).allow_symlinks(true))
+			.with(Mount::new("/uploads", roots.uploads_dir.path()));
+
+		assert!(table.resolve("/static/link.txt").is_ok());
+		assert!(matches!(
+			table.resolve("/uploads/link.txt"),
+			Err(Error::SymlinkTraversal)
+		));
+	}
+}
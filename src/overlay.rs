@@ -0,0 +1,302 @@
+/* src/overlay.rs */
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::fs::{FileSystem, Metadata, StdFs};
+use crate::listing::{self, Entry};
+use crate::path;
+
+/// A URI resolved against one layer of an [`Overlay`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+	/// Index of the layer that satisfied the request (0 is the top layer).
+	pub layer: usize,
+	/// The jailed filesystem path inside that layer.
+	pub path: PathBuf,
+}
+
+/// An ordered stack of roots where the first layer containing a file wins.
+///
+/// Layers are listed top-most first, e.g. a customer override directory
+/// followed by the default theme. Every layer is jailed independently with
+/// [`path::resolve`], so a `..` can never step from one layer into another.
+/// The `*_in` methods read the layers from any [`FileSystem`].
+///
+/// ```
+/// let overlay = serve_static::overlay::Overlay::new(["/srv/custom", "/srv/default"]);
+/// assert_eq!(overlay.layers().len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlay {
+	layers: Vec<PathBuf>,
+	allow_symlinks: bool,
+}
+
+impl Overlay {
+	/// Creates an overlay from roots ordered top-most first.
+	#[must_use]
+	pub fn new<P: Into<PathBuf>>(layers: impl IntoIterator<Item = P>) -> Self {
+		Self {
+			layers: layers.into_iter().map(Into::into).collect(),
+			allow_symlinks: false,
+		}
+	}
+
+	/// Sets whether symlinks may point outside their layer's root.
+	#[must_use]
+	pub fn allow_symlinks(mut self, allow: bool) -> Self {
+		self.allow_symlinks = allow;
+		self
+	}
+
+	/// The layer roots, top-most first.
+	#[must_use]
+	pub fn layers(&self) -> &[PathBuf] {
+		&self.layers
+	}
+
+	/// Resolves `uri` to the top-most layer in which it exists.
+	///
+	/// Returns `Ok(None)` when no layer contains the path. Resolution errors
+	/// in any layer (invalid root, symlink traversal) are returned instead of
+	/// falling through, so a rejected request never silently reaches a lower
+	/// layer.
+	pub fn resolve(&self, uri: &str) -> Result<Option<Hit>, Error> {
+		self.resolve_in(&StdFs, uri)
+	}
+
+	/// Like [`Overlay::resolve`], with the layers read from `fs`.
+	pub fn resolve_in<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		uri: &str,
+	) -> Result<Option<Hit>, Error> {
+		for (layer, root) in self.layers.iter().enumerate() {
+			let path = path::resolve_in(fs, root, uri, self.allow_symlinks)?;
+			if metadata(fs, &path)?.is_some() {
+				return Ok(Some(Hit { layer, path }));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Resolves `uri` in every layer where it exists as a directory.
+	///
+	/// Read each returned directory and pass the entries, in the same order,
+	/// to [`merge`] to build the combined listing.
+	pub fn resolve_dirs(&self, uri: &str) -> Result<Vec<Hit>, Error> {
+		self.resolve_dirs_in(&StdFs, uri)
+	}
+
+	/// Like [`Overlay::resolve_dirs`], with the layers read from `fs`.
+	pub fn resolve_dirs_in<F: FileSystem + ?Sized>(
+		&self,
+		fs: &F,
+		uri: &str,
+	) -> Result<Vec<Hit>, Error> {
+		let mut hits = Vec::new();
+		for (layer, root) in self.layers.iter().enumerate() {
+			let path = path::resolve_in(fs, root, uri, self.allow_symlinks)?;
+			if metadata(fs, &path)?.is_some_and(|meta| meta.is_dir()) {
+				hits.push(Hit { layer, path });
+			}
+		}
+		Ok(hits)
+	}
+}
+
+/// Metadata of `path`, or `None` when nothing exists there.
+fn metadata<F: FileSystem + ?Sized>(fs: &F, path: &Path) -> Result<Option<Metadata>, Error> {
+	match fs.metadata(path) {
+		Ok(meta) => Ok(Some(meta)),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+		Err(e) => Err(Error::SecurityIo(e)),
+	}
+}
+
+/// Merges per-layer directory listings, top-most layer first.
+///
+/// An entry in an upper layer shadows any entry with the same name below
+/// it, whether file or directory. The result is ordered with
+/// [`listing::sort`].
+///
+/// ```
+/// use serve_static::listing::Entry;
+///
+/// let upper = vec![Entry::file("logo.png", 10)];
+/// let lower = vec![Entry::file("logo.png", 99), Entry::file("app.css", 5)];
+/// let merged = serve_static::overlay::merge([upper, lower]);
+/// assert_eq!(merged.len(), 2);
+/// assert_eq!(merged[1].size, Some(10));
+/// ```
+#[must_use]
+pub fn merge(layers: impl IntoIterator<Item = Vec<Entry>>) -> Vec<Entry> {
+	let mut seen = HashSet::new();
+	let mut merged = Vec::new();
+	for entries in layers {
+		for entry in entries {
+			if seen.insert(entry.name.clone()) {
+				merged.push(entry);
+			}
+		}
+	}
+	listing::sort(&mut merged);
+	merged
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn make_layers() -> (tempfile::TempDir, tempfile::TempDir) {
+		let custom = tempfile::tempdir().unwrap();
+		let default = tempfile::tempdir().unwrap();
+		std::fs::create_dir_all(default.path().join("css")).unwrap();
+		std::fs::write(default.path().join("css/theme.css"), b"default").unwrap();
+		std::fs::write(default.path().join("logo.png"), b"default").unwrap();
+		std::fs::create_dir_all(custom.path().join("css")).unwrap();
+		std::fs::write(custom.path().join("logo.png"), b"custom").unwrap();
+		(custom, default)
+	}
+
+	fn file(name: &str, size: u64) -> Entry {
+		Entry::file(name, size)
+	}
+
+	fn dir(name: &str) -> Entry {
+		Entry::dir(name)
+	}
+
+	#[test]
+	fn upper_layer_wins() {
+		let (custom, default) = make_layers();
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		let hit = overlay.resolve("/logo.png").unwrap().unwrap();
+		assert_eq!(hit.layer, 0);
+		assert_eq!(std::fs::read(&hit.path).unwrap(), b"custom");
+	}
+
+	#[test]
+	fn falls_through_to_lower_layer() {
+		let (custom, default) = make_layers();
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		let hit = overlay.resolve("/css/theme.css").unwrap().unwrap();
+		assert_eq!(hit.layer, 1);
+		assert_eq!(std::fs::read(&hit.path).unwrap(), b"default");
+	}
+
+	#[test]
+	fn missing_everywhere() {
+		let (custom, default) = make_layers();
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		assert_eq!(overlay.resolve("/missing.txt").unwrap(), None);
+	}
+
+	#[test]
+	fn empty_overlay() {
+		let overlay = Overlay::new(Vec::<PathBuf>::new());
+		assert_eq!(overlay.resolve("/logo.png").unwrap(), None);
+	}
+
+	#[test]
+	fn traversal_stays_in_layer() {
+		let (custom, default) = make_layers();
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		let hit = overlay.resolve("/../../logo.png").unwrap().unwrap();
+		assert_eq!(hit.layer, 0);
+		let canon = custom.path().canonicalize().unwrap();
+		assert!(hit.path.starts_with(canon));
+	}
+
+	#[test]
+	fn invalid_layer_is_an_error() {
+		let (custom, _default) = make_layers();
+		let overlay = Overlay::new([custom.path(), Path::new("/nonexistent_layer_xyz")]);
+		let result = overlay.resolve("/missing.txt");
+		assert!(matches!(result, Err(Error::InvalidRoot { .. })));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn symlink_escape_does_not_fall_through() {
+		let (custom, default) = make_layers();
+		let outside = tempfile::tempdir().unwrap();
+		let secret = outside.path().join("secret.txt");
+		std::fs::write(&secret, b"secret").unwrap();
+		std::os::unix::fs::symlink(&secret, custom.path().join("theme.css")).unwrap();
+
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		let result = overlay.resolve("/theme.css");
+		assert!(matches!(result, Err(Error::SymlinkTraversal)));
+	}
+
+	#[test]
+	fn resolve_dirs_lists_every_layer() {
+		let (custom, default) = make_layers();
+		let overlay = Overlay::new([custom.path(), default.path()]);
+		let hits = overlay.resolve_dirs("/css").unwrap();
+		let layers: Vec<_> = hits.iter().map(|h| h.layer).collect();
+		assert_eq!(layers, [0, 1]);
+
+		// A file is not a directory in any layer.
+		assert!(overlay.resolve_dirs("/logo.png").unwrap().is_empty());
+	}
+
+	#[test]
+	fn layers_in_memory() {
+		let mut fs = crate::fs::MemoryFs::new();
+		fs.insert_file("/custom/logo.png", b"custom")
+			.insert_dir("/custom/css")
+			.insert_file("/default/logo.png", b"default")
+			.insert_file("/default/css/theme.css", b"default")
+			.insert_file("/secret.txt", b"secret")
+			.insert_symlink("/custom/theme.css", "/secret.txt");
+		let overlay = Overlay::new(["/custom", "/default"]);
+
+		let hit = overlay.resolve_in(&fs, "/logo.png").unwrap().unwrap();
+		assert_eq!(
+			(hit.layer, hit.path),
+			(0, PathBuf::from("/custom/logo.png"))
+		);
+		let hit = overlay.resolve_in(&fs, "/css/theme.css").unwrap().unwrap();
+		assert_eq!(hit.layer, 1);
+		assert_eq!(overlay.resolve_in(&fs, "/missing.txt").unwrap(), None);
+		assert!(matches!(
+			overlay.resolve_in(&fs, "/theme.css"),
+			Err(Error::SymlinkTraversal)
+		));
+
+		let layers: Vec<_> = overlay
+			.resolve_dirs_in(&fs, "/css")
+			.unwrap()
+			.iter()
+			.map(|h| h.layer)
+			.collect();
+		assert_eq!(layers, [0, 1]);
+	}
+
+	#[test]
+	fn merge_upper_shadows_lower() {
+		let upper = vec![file("logo.png", 1), file("custom.css", 2)];
+		let lower = vec![file("logo.png", 99), file("app.js", 3), dir("img")];
+		let merged = merge([upper, lower]);
+		let names: Vec<_> = merged.iter().map(|e| e.name.as_str()).collect();
+		assert_eq!(names, ["img", "app.js", "custom.css", "logo.png"]);
+		assert_eq!(merged[3].size, Some(1));
+	}
+
+	#[test]
+	fn merge_file_shadows_directory() {
+		let upper = vec![file("docs", 7)];
+		let lower = vec![dir("docs")];
+		let merged = merge([upper, lower]);
+		assert_eq!(merged, [file("docs", 7)]);
+	}
+
+	#[test]
+	fn merge_no_layers() {
+		assert!(merge(Vec::<Vec<Entry>>::new()).is_empty());
+	}
+}
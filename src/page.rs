@@ -0,0 +1,468 @@
+/* src/page.rs */
+
+use std::fmt;
+
+use crate::fs::FileType;
+use crate::glob;
+use crate::listing::{self, Entry};
+
+/// Selects listing entries by name, kind and extension.
+///
+/// Every criterion that is set must match. Within one criterion, adding
+/// several values matches any of them, so `.extension("jpg").extension("png")`
+/// keeps both.
+///
+/// ```
+/// use serve_static::listing::Entry;
+/// use serve_static::page::Filter;
+///
+/// let filter = Filter::new().contains("LOG").extension("txt");
+/// assert!(filter.matches(&Entry::file("changelog.TXT", 1)));
+/// assert!(!filter.matches(&Entry::file("changelog.md", 1)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+	contains: Option<String>,
+	globs: Vec<String>,
+	kinds: Vec<FileType>,
+	extensions: Vec<String>,
+}
+
+impl Filter {
+	/// Creates a filter that matches every entry.
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Keeps names containing `text`, ignoring case.
+	#[must_use]
+	pub fn contains(mut self, text: &str) -> Self {
+		self.contains = Some(text.to_lowercase());
+		self
+	}
+
+	/// Keeps names matching a glob pattern (`*`, `?`, `**`), case-sensitively.
+	#[must_use]
+	pub fn glob(mut self, pattern: impl Into<String>) -> Self {
+		self.globs.push(pattern.into());
+		self
+	}
+
+	/// Keeps entries of a kind. [`FileType::Dir`] matches every entry listed
+	/// as a directory, including symlinks followed to one; other kinds
+	/// compare [`Entry::kind`].
+	#[must_use]
+	pub fn kind(mut self, kind: FileType) -> Self {
+		self.kinds.push(kind);
+		self
+	}
+
+	/// Keeps non-directory entries with an extension, given without the dot
+	/// and compared ignoring case.
+	#[must_use]
+	pub fn extension(mut self, extension: &str) -> Self {
+		self
+			.extensions
+			.push(extension.trim_start_matches('.').to_lowercase());
+		self
+	}
+
+	/// Whether `entry` passes every criterion.
+	#[must_use]
+	pub fn matches(&self, entry: &Entry) -> bool {
+		if let Some(text) = &self.contains
+			&& !entry.name.to_lowercase().contains(text.as_str())
+		{
+			return false;
+		}
+		if !self.globs.is_empty()
+			&& !self
+				.globs
+				.iter()
+				.any(|pattern| glob::matches(pattern, &entry.name))
+		{
+			return false;
+		}
+		if !self.kinds.is_empty()
+			&& !self.kinds.iter().any(|&kind| match kind {
+				FileType::Dir => entry.is_dir,
+				kind => !entry.is_dir && entry.kind == kind,
+			}) {
+			return false;
+		}
+		if !self.extensions.is_empty() {
+			let extension = listing::extension(&entry.name);
+			if entry.is_dir
+				|| extension.is_empty()
+				|| !self.extensions.contains(&extension.to_lowercase())
+			{
+				return false;
+			}
+		}
+		true
+	}
+}
+
+/// An opaque position in a paginated listing.
+///
+/// Cursors are URL-, HTML- and JSON-safe strings that can be round-tripped
+/// through [`Cursor::as_str`] and [`Cursor::parse`]. They remember the name
+/// of the entry at the page boundary, so a cursor keeps pointing at the
+/// same place when entries are added or removed elsewhere in the
+/// directory. If the boundary entry itself disappears, the cursor resumes at
+/// the position it had.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cursor(String);
+
+/// Which side of the boundary entry a cursor's page lies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+	After,
+	Before,
+}
+
+impl Cursor {
+	fn new(direction: Direction, offset: usize, name: &str) -> Self {
+		let tag = match direction {
+			Direction::After => 'a',
+			Direction::Before => 'b',
+		};
+		let raw = format!("{tag}{offset}:{name}");
+		let mut hex = String::with_capacity(raw.len() * 2);
+		for byte in raw.bytes() {
+			hex.push(char::from(HEX[usize::from(byte >> 4)]));
+			hex.push(char::from(HEX[usize::from(byte & 0xf)]));
+		}
+		Self(hex)
+	}
+
+	/// Parses a cursor previously produced by a [`Page`]. Returns `None` for
+	/// anything else, which callers usually map to `400 Bad Request`.
+	#[must_use]
+	pub fn parse(text: &str) -> Option<Self> {
+		let cursor = Self(text.to_owned());
+		cursor.decode()?;
+		Some(cursor)
+	}
+
+	/// The cursor as a string.
+	#[must_use]
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	fn decode(&self) -> Option<(Direction, usize, String)> {
+		let hex = self.0.as_bytes();
+		if !hex.len().is_multiple_of(2) {
+			return None;
+		}
+		let mut raw = Vec::with_capacity(hex.len() / 2);
+		for pair in hex.chunks_exact(2) {
+			let high = HEX.iter().position(|&c| c == pair[0])?;
+			let low = HEX.iter().position(|&c| c == pair[1])?;
+			raw.push(u8::try_from(high << 4 | low).ok()?);
+		}
+		let raw = String::from_utf8(raw).ok()?;
+		let direction = match raw.chars().next()? {
+			'a' => Direction::After,
+			'b' => Direction::Before,
+			_ => return None,
+		};
+		let (offset, name) = raw[1..].split_once(':')?;
+		Some((direction, offset.parse().ok()?, name.to_owned()))
+	}
+}
+
+impl fmt::Display for Cursor {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// One page of a filtered listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+	/// The entries on this page, in listing order.
+	pub entries: Vec<Entry>,
+	/// The number of entries passing the filter, across all pages.
+	pub total: usize,
+	/// The position of the first entry on this page among the filtered
+	/// entries.
+	pub offset: usize,
+	/// The cursor for the following page, if any.
+	pub next: Option<Cursor>,
+	/// The cursor for the preceding page, if any.
+	pub prev: Option<Cursor>,
+}
+
+/// Where a [`Paginator`] starts its page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Position {
+	Offset(usize),
+	Cursor(Cursor),
+}
+
+/// Cuts filtered listing entries into pages.
+///
+/// Entries are paged in the order given, so sort them first with
+/// [`crate::listing::sort_by`]; cursors are only meaningful for the same
+/// order and filter they were produced with.
+///
+/// ```
+/// use serve_static::listing::Entry;
+/// use serve_static::page::{Cursor, Filter, Paginator};
+///
+/// let entries: Vec<Entry> = (0..5).map(|i| Entry::file(format!("f{i}.txt"), i)).collect();
+/// let first = Paginator::new(2).page(&entries);
+/// assert_eq!(first.total, 5);
+/// assert_eq!(first.entries[0].name, "f0.txt");
+///
+/// let token = first.next.unwrap().to_string();
+/// let second = Paginator::new(2)
+///     .cursor(Cursor::parse(&token).unwrap())
+///     .page(&entries);
+/// assert_eq!(second.entries[0].name, "f2.txt");
+/// assert!(second.prev.is_some());
+///
+/// let filtered = Paginator::new(10).filter(Filter::new().glob("*.md")).page(&entries);
+/// assert_eq!(filtered.total, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paginator {
+	limit: usize,
+	filter: Filter,
+	position: Position,
+}
+
+impl Paginator {
+	/// Creates a paginator returning up to `limit` entries per page, from
+	/// the start. A `limit` of zero is treated as one.
+	#[must_use]
+	pub fn new(limit: usize) -> Self {
+		Self {
+			limit: limit.max(1),
+			filter: Filter::new(),
+			position: Position::Offset(0),
+		}
+	}
+
+	/// Sets the filter applied before paging.
+	#[must_use]
+	pub fn filter(mut self, filter: Filter) -> Self {
+		self.filter = filter;
+		self
+	}
+
+	/// Starts the page at a position among the filtered entries.
+	#[must_use]
+	pub fn offset(mut self, offset: usize) -> Self {
+		self.position = Position::Offset(offset);
+		self
+	}
+
+	/// Starts the page from a cursor returned with a previous page.
+	#[must_use]
+	pub fn cursor(mut self, cursor: Cursor) -> Self {
+		self.position = Position::Cursor(cursor);
+		self
+	}
+
+	/// Filters `entries` and returns the requested page.
+	#[must_use]
+	pub fn page(&self, entries: &[Entry]) -> Page {
+		let matched: Vec<&Entry> = entries.iter().filter(|e| self.filter.matches(e)).collect();
+		let total = matched.len();
+		let find = |offset: usize, name: &str| {
+			if matched.get(offset).is_some_and(|e| e.name == name) {
+				Some(offset)
+			} else {
+				matched.iter().position(|e| e.name == name)
+			}
+		};
+
+		let start = match &self.position {
+			Position::Offset(offset) => *offset,
+			Position::Cursor(cursor) => match cursor.decode() {
+				Some((Direction::After, offset, name)) => find(offset, &name).map_or(offset, |i| i + 1),
+				Some((Direction::Before, offset, name)) => find(offset, &name)
+					.unwrap_or(offset)
+					.saturating_sub(self.limit),
+				None => 0,
+			},
+		}
+		.min(total);
+		let end = start.saturating_add(self.limit).min(total);
+
+		let slice = &matched[start..end];
+		let next =
+			(end < total).then(|| Cursor::new(Direction::After, end - 1, &matched[end - 1].name));
+		let prev = (start > 0 && start < total)
+			.then(|| Cursor::new(Direction::Before, start, &matched[start].name));
+		Page {
+			entries: slice.iter().map(|&e| e.clone()).collect(),
+			total,
+			offset: start,
+			next,
+			prev,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::path::PathBuf;
+
+	fn files(count: usize) -> Vec<Entry> {
+		(0..count)
+			.map(|i| Entry::file(format!("f{i:02}.txt"), 1))
+			.collect()
+	}
+
+	fn names(page: &Page) -> Vec<&str> {
+		page.entries.iter().map(|e| e.name.as_str()).collect()
+	}
+
+	#[test]
+	fn offset_and_limit() {
+		let entries = files(5);
+		let page = Paginator::new(2).offset(2).page(&entries);
+		assert_eq!(names(&page), ["f02.txt", "f03.txt"]);
+		assert_eq!((page.total, page.offset), (5, 2));
+		assert!(page.next.is_some() && page.prev.is_some());
+
+		let past_end = Paginator::new(2).offset(99).page(&entries);
+		assert!(past_end.entries.is_empty());
+		assert_eq!(past_end.offset, 5);
+		assert_eq!((past_end.next, past_end.prev), (None, None));
+
+		let zero = Paginator::new(0).page(&entries);
+		assert_eq!(zero.entries.len(), 1);
+	}
+
+	#[test]
+	fn walks_forward_and_back() {
+		let entries = files(5);
+		let first = Paginator::new(2).page(&entries);
+		assert_eq!(names(&first), ["f00.txt", "f01.txt"]);
+		assert_eq!(first.prev, None);
+
+		let second = Paginator::new(2)
+			.cursor(first.next.clone().unwrap())
+			.page(&entries);
+		assert_eq!(names(&second), ["f02.txt", "f03.txt"]);
+		let third = Paginator::new(2)
+			.cursor(second.next.clone().unwrap())
+			.page(&entries);
+		assert_eq!(names(&third), ["f04.txt"]);
+		assert_eq!(third.next, None);
+
+		let back = Paginator::new(2).cursor(third.prev.unwrap()).page(&entries);
+		assert_eq!(back, second);
+		let start = Paginator::new(2).cursor(back.prev.unwrap()).page(&entries);
+		assert_eq!(start, first);
+	}
+
+	#[test]
+	fn cursor_stable_under_insertions() {
+		let mut entries = files(6);
+		let first = Paginator::new(3).page(&entries);
+		// A new entry sorted onto the first page must not shift the second.
+		entries.insert(0, Entry::file("a-new.txt", 1));
+		let second = Paginator::new(3).cursor(first.next.unwrap()).page(&entries);
+		assert_eq!(names(&second), ["f03.txt", "f04.txt", "f05.txt"]);
+		assert_eq!(second.offset, 4);
+	}
+
+	#[test]
+	fn cursor_falls_back_to_offset() {
+		let mut entries = files(6);
+		let first = Paginator::new(3).page(&entries);
+		entries.retain(|e| e.name != "f02.txt");
+		let second = Paginator::new(3).cursor(first.next.unwrap()).page(&entries);
+		assert_eq!(names(&second), ["f03.txt", "f04.txt", "f05.txt"]);
+	}
+
+	#[test]
+	fn cursor_encoding() {
+		let cursor = Cursor::new(Direction::After, 7, "a b/é:\"<x>");
+		assert!(cursor.as_str().bytes().all(|b| b.is_ascii_hexdigit()));
+		let parsed = Cursor::parse(cursor.as_str()).unwrap();
+		assert_eq!(
+			parsed.decode(),
+			Some((Direction::After, 7, "a b/é:\"<x>".to_owned()))
+		);
+		assert_eq!(Cursor::parse(""), None);
+		assert_eq!(Cursor::parse("abc"), None);
+		assert_eq!(Cursor::parse("zz"), None);
+		// Valid hex, but not a cursor.
+		assert_eq!(Cursor::parse("6869"), None);
+	}
+
+	#[test]
+	fn filters() {
+		let link = Entry::file("latest", 0)
+			.with_size(None)
+			.with_symlink_target(PathBuf::from("v2"));
+		let entries = [
+			Entry::dir("docs"),
+			Entry::dir("images.d"),
+			Entry::file("Photo.JPG", 1),
+			Entry::file("logo.png", 1),
+			Entry::file("notes.txt", 1),
+			Entry::file(".jpg", 1),
+			Entry::file("draft.", 1),
+			link,
+		];
+		let names = |filter: Filter| -> Vec<String> {
+			let page = Paginator::new(100).filter(filter).page(&entries);
+			page.entries.into_iter().map(|e| e.name).collect()
+		};
+		assert_eq!(
+			names(Filter::new().contains("O")),
+			["docs", "Photo.JPG", "logo.png", "notes.txt"]
+		);
+		assert_eq!(
+			names(Filter::new().glob("*.png").glob("n*")),
+			["logo.png", "notes.txt"]
+		);
+		assert_eq!(
+			names(Filter::new().kind(FileType::Dir)),
+			["docs", "images.d"]
+		);
+		assert_eq!(names(Filter::new().kind(FileType::Symlink)), ["latest"]);
+		assert_eq!(
+			names(Filter::new().extension("jpg").extension(".PNG")),
+			["Photo.JPG", "logo.png"]
+		);
+		assert_eq!(names(Filter::new().extension("d")), Vec::<String>::new());
+		// Dotfiles and trailing dots have no extension, as when sorting.
+		assert_eq!(names(Filter::new().extension("")), Vec::<String>::new());
+		assert_eq!(
+			names(
+				Filter::new()
+					.kind(FileType::File)
+					.contains("o")
+					.extension("png")
+			),
+			["logo.png"]
+		);
+	}
+
+	#[test]
+	fn filtered_totals_and_cursors() {
+		let mut entries = files(6);
+		entries.push(Entry::file("other.md", 1));
+		let filter = Filter::new().extension("txt");
+		let first = Paginator::new(4).filter(filter.clone()).page(&entries);
+		assert_eq!(first.total, 6);
+		let second = Paginator::new(4)
+			.filter(filter)
+			.cursor(first.next.unwrap())
+			.page(&entries);
+		assert_eq!(names(&second), ["f04.txt", "f05.txt"]);
+	}
+}
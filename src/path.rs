@@ -2,7 +2,8 @@
 
 use std::path::{Component, Path, PathBuf};
 
-use crate::error::Error;
+use crate::error::{Error, StrictViolation};
+use crate::fs::{FileSystem, StdFs};
 
 /// Resolves a URI path into a physical filesystem path under `root`.
 ///
@@ -25,19 +26,179 @@ use crate::error::Error;
 /// assert!(result2.is_ok());
 /// ```
 pub fn resolve(root: impl AsRef<Path>, uri: &str, allow_symlinks: bool) -> Result<PathBuf, Error> {
-	let root = root.as_ref();
-	let root = root.canonicalize().map_err(|source| Error::InvalidRoot {
+	resolve_in(&StdFs, root, uri, allow_symlinks)
+}
+
+/// Resolves a URI path like [`resolve`], querying `fs` instead of `std::fs`.
+///
+/// ```
+/// use serve_static::error::Error;
+/// use serve_static::fs::MemoryFs;
+///
+/// let mut fs = MemoryFs::new();
+/// fs.insert_file("/srv/index.html", b"<html>");
+/// fs.insert_file("/etc/passwd", b"root");
+/// fs.insert_symlink("/srv/passwd", "/etc/passwd");
+///
+/// let path = serve_static::path::resolve_in(&fs, "/srv", "/index.html", false).unwrap();
+/// assert_eq!(path, std::path::Path::new("/srv/index.html"));
+///
+/// let result = serve_static::path::resolve_in(&fs, "/srv", "/passwd", false);
+/// assert!(matches!(result, Err(Error::SymlinkTraversal)));
+/// ```
+pub fn resolve_in<F: FileSystem + ?Sized>(
+	fs: &F,
+	root: impl AsRef<Path>,
+	uri: &str,
+	allow_symlinks: bool,
+) -> Result<PathBuf, Error> {
+	let root = canonical_root(fs, root.as_ref())?;
+	let decoded = decode(uri)?;
+	resolve_decoded(fs, &root, &decoded, allow_symlinks)
+}
+
+/// Resolves a URI path like [`resolve`], but rejects instead of normalizing
+/// suspicious input.
+///
+/// Any of the following yields [`Error::StrictViolation`]:
+/// - a `..` segment that would climb above `root`
+/// - a percent-encoded dot segment such as `%2e%2e` or `.%2E`
+/// - a `.` segment or an empty segment (`//`, or `%2F%2F` once decoded)
+///
+/// A single leading and a single trailing `/` are permitted, so directory
+/// URIs like `/docs/` still resolve. `..` segments that stay inside the root
+/// (`/a/../b`) are accepted and normalized as usual.
+///
+/// ```
+/// use serve_static::error::{Error, StrictViolation};
+///
+/// let root = std::env::temp_dir();
+/// assert!(serve_static::path::resolve_strict(&root, "/docs/", true).is_ok());
+///
+/// let result = serve_static::path::resolve_strict(&root, "/../etc/passwd", true);
+/// assert!(matches!(result, Err(Error::StrictViolation(StrictViolation::EscapesRoot))));
+/// ```
+pub fn resolve_strict(
+	root: impl AsRef<Path>,
+	uri: &str,
+	allow_symlinks: bool,
+) -> Result<PathBuf, Error> {
+	resolve_strict_in(&StdFs, root, uri, allow_symlinks)
+}
+
+/// Resolves a URI path like [`resolve_strict`], querying `fs` instead of
+/// `std::fs`.
+pub fn resolve_strict_in<F: FileSystem + ?Sized>(
+	fs: &F,
+	root: impl AsRef<Path>,
+	uri: &str,
+	allow_symlinks: bool,
+) -> Result<PathBuf, Error> {
+	let root = canonical_root(fs, root.as_ref())?;
+	check_strict(uri)?;
+	let decoded = decode(uri)?;
+	resolve_decoded(fs, &root, &decoded, allow_symlinks)
+}
+
+/// Normalizes a URI path into a relative, `/`-separated key without
+/// touching the filesystem.
+///
+/// Applies the same decoding and `..` clamping as [`resolve`], which makes
+/// it suitable for looking up assets that do not live on disk. The result
+/// has no leading or trailing `/`; the root is the empty string.
+///
+/// ```
+/// let key = serve_static::path::normalize("/assets/../%2e%2e/css//site.css").unwrap();
+/// assert_eq!(key, "css/site.css");
+/// assert_eq!(serve_static::path::normalize("/").unwrap(), "");
+/// ```
+pub fn normalize(uri: &str) -> Result<String, Error> {
+	let decoded = decode(uri)?;
+	let mut segments: Vec<&str> = Vec::new();
+	for segment in decoded.split('/') {
+		match segment {
+			"" | "." => {}
+			".." => {
+				segments.pop();
+			}
+			_ => segments.push(segment),
+		}
+	}
+	Ok(segments.join("/"))
+}
+
+/// Normalizes a URI path like [`normalize`], applying the checks of
+/// [`resolve_strict`] first.
+pub fn normalize_strict(uri: &str) -> Result<String, Error> {
+	check_strict(uri)?;
+	normalize(uri)
+}
+
+/// Canonicalizes a serving root, reporting failure as [`Error::InvalidRoot`].
+pub(crate) fn canonical_root<F: FileSystem + ?Sized>(
+	fs: &F,
+	root: &Path,
+) -> Result<PathBuf, Error> {
+	fs.canonicalize(root).map_err(|source| Error::InvalidRoot {
 		path: root.to_path_buf(),
 		source,
-	})?;
+	})
+}
 
+fn decode(uri: &str) -> Result<std::borrow::Cow<'_, str>, Error> {
 	let decoded = percent_encoding::percent_decode_str(uri).decode_utf8()?;
 	if decoded.contains('\0') {
 		return Err(Error::NullByte);
 	}
-	let mut resolved = root.clone();
+	Ok(decoded)
+}
+
+/// Validates the raw (still percent-encoded) URI for [`resolve_strict`].
+fn check_strict(uri: &str) -> Result<(), Error> {
+	let trimmed = uri.strip_prefix('/').unwrap_or(uri);
+	if trimmed.is_empty() {
+		return Ok(());
+	}
+	// A single trailing slash names a directory; `//` is an empty segment.
+	let trimmed = trimmed.strip_suffix('/').unwrap_or(trimmed);
+
+	let mut depth = 0usize;
+	for segment in trimmed.split('/') {
+		match segment {
+			"" => return Err(Error::StrictViolation(StrictViolation::EmptySegment)),
+			"." => return Err(Error::StrictViolation(StrictViolation::DotSegment)),
+			".." => {
+				depth = depth
+					.checked_sub(1)
+					.ok_or(Error::StrictViolation(StrictViolation::EscapesRoot))?;
+			}
+			_ => {
+				// Encoded separators (`%2F`) would smuggle extra segments past
+				// the checks above, so inspect the decoded form as well.
+				let decoded = percent_encoding::percent_decode_str(segment).decode_utf8()?;
+				let parts = || decoded.split(['/', '\\']);
+				if parts().any(|part| part == "." || part == "..") {
+					return Err(Error::StrictViolation(StrictViolation::EncodedDotSegment));
+				}
+				if parts().any(str::is_empty) {
+					return Err(Error::StrictViolation(StrictViolation::EmptySegment));
+				}
+				depth += 1;
+			}
+		}
+	}
+	Ok(())
+}
 
-	for component in Path::new(decoded.as_ref()).components() {
+fn resolve_decoded<F: FileSystem + ?Sized>(
+	fs: &F,
+	root: &Path,
+	decoded: &str,
+	allow_symlinks: bool,
+) -> Result<PathBuf, Error> {
+	let mut resolved = root.to_path_buf();
+
+	for component in Path::new(decoded).components() {
 		match component {
 			Component::Normal(c) => resolved.push(c),
 			Component::ParentDir => {
@@ -50,9 +211,9 @@ pub fn resolve(root: impl AsRef<Path>, uri: &str, allow_symlinks: bool) -> Resul
 	}
 
 	if !allow_symlinks {
-		match resolved.canonicalize() {
+		match fs.canonicalize(&resolved) {
 			Ok(canonical) => {
-				if !canonical.starts_with(&root) {
+				if !canonical.starts_with(root) {
 					return Err(Error::SymlinkTraversal);
 				}
 				return Ok(canonical);
@@ -70,9 +231,9 @@ pub fn resolve(root: impl AsRef<Path>, uri: &str, allow_symlinks: bool) -> Resul
 							verified = true;
 							break;
 						}
-						match ancestor.canonicalize() {
+						match fs.canonicalize(&ancestor) {
 							Ok(canonical) => {
-								if !canonical.starts_with(&root) {
+								if !canonical.starts_with(root) {
 									return Err(Error::SymlinkTraversal);
 								}
 								verified = true;
@@ -96,7 +257,7 @@ pub fn resolve(root: impl AsRef<Path>, uri: &str, allow_symlinks: bool) -> Resul
 
 	// Defence-in-depth: even when symlinks are allowed the constructed
 	// path must never escape root.
-	if !resolved.starts_with(&root) {
+	if !resolved.starts_with(root) {
 		return Err(Error::SymlinkTraversal);
 	}
 
@@ -324,4 +485,236 @@ mod tests {
 		let result = resolve(root.path(), "/%C3%28", true);
 		assert!(matches!(result, Err(Error::InvalidEncoding(_))));
 	}
+
+	// ── strict mode: suspicious input must be rejected, not clamped ──
+
+	fn violation(uri: &str) -> Option<StrictViolation> {
+		let root = make_root();
+		match resolve_strict(root.path(), uri, true) {
+			Err(Error::StrictViolation(v)) => Some(v),
+			_ => None,
+		}
+	}
+
+	#[test]
+	fn strict_normal_path() {
+		let root = make_root();
+		let result = resolve_strict(root.path(), "/assets/images/logo.png", false).unwrap();
+		assert!(result.ends_with("assets/images/logo.png"));
+	}
+
+	#[test]
+	fn strict_root_and_trailing_slash() {
+		let root = make_root();
+		let canon_root = root.path().canonicalize().unwrap();
+		assert_eq!(resolve_strict(root.path(), "", true).unwrap(), canon_root);
+		assert_eq!(resolve_strict(root.path(), "/", true).unwrap(), canon_root);
+		let result = resolve_strict(root.path(), "/assets/", true).unwrap();
+		assert_eq!(result, canon_root.join("assets"));
+	}
+
+	#[test]
+	fn strict_parent_inside_root_allowed() {
+		let root = make_root();
+		let result = resolve_strict(root.path(), "/assets/../index.html", true).unwrap();
+		let canon_root = root.path().canonicalize().unwrap();
+		assert_eq!(result, canon_root.join("index.html"));
+	}
+
+	#[test]
+	fn strict_traversal_rejected() {
+		assert_eq!(
+			violation("/../../etc/passwd"),
+			Some(StrictViolation::EscapesRoot)
+		);
+		assert_eq!(
+			violation("/assets/../../etc"),
+			Some(StrictViolation::EscapesRoot)
+		);
+		assert_eq!(violation(".."), Some(StrictViolation::EscapesRoot));
+	}
+
+	#[test]
+	fn strict_encoded_dots_rejected() {
+		assert_eq!(
+			violation("/%2e%2e/etc"),
+			Some(StrictViolation::EncodedDotSegment)
+		);
+		assert_eq!(
+			violation("/assets/.%2E"),
+			Some(StrictViolation::EncodedDotSegment)
+		);
+		assert_eq!(
+			violation("/%2e/index.html"),
+			Some(StrictViolation::EncodedDotSegment)
+		);
+	}
+
+	#[test]
+	fn strict_encoded_slash_smuggling_rejected() {
+		assert_eq!(
+			violation("/assets%2F..%2F..%2Fetc"),
+			Some(StrictViolation::EncodedDotSegment)
+		);
+	}
+
+	#[test]
+	fn strict_dot_and_empty_segments_rejected() {
+		assert_eq!(
+			violation("/./index.html"),
+			Some(StrictViolation::DotSegment)
+		);
+		assert_eq!(
+			violation("/assets//images"),
+			Some(StrictViolation::EmptySegment)
+		);
+		assert_eq!(
+			violation("//index.html"),
+			Some(StrictViolation::EmptySegment)
+		);
+		assert_eq!(violation("//"), Some(StrictViolation::EmptySegment));
+		assert_eq!(violation("/assets//"), Some(StrictViolation::EmptySegment));
+		assert_eq!(
+			violation("/a%2F%2Fb"),
+			Some(StrictViolation::EmptySegment)
+		);
+		assert_eq!(violation("/a%2F"), Some(StrictViolation::EmptySegment));
+		assert_eq!(violation("/%2Fa"), Some(StrictViolation::EmptySegment));
+	}
+
+	#[test]
+	fn strict_double_encoding_stays_literal() {
+		let root = make_root();
+		let result = resolve_strict(root.path(), "/%252e%252e", true).unwrap();
+		assert!(result.ends_with("%2e%2e"));
+	}
+
+	#[test]
+	fn strict_still_rejects_null_byte() {
+		let root = make_root();
+		let result = resolve_strict(root.path(), "/file%00.txt", true);
+		assert!(matches!(result, Err(Error::NullByte)));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn strict_symlink_traversal_blocked() {
+		let root = make_root();
+		let outside = tempfile::tempdir().unwrap();
+		let link = root.path().join("evil");
+		std::os::unix::fs::symlink(outside.path(), &link).unwrap();
+
+		let result = resolve_strict(root.path(), "/evil/file.txt", false);
+		assert!(matches!(result, Err(Error::SymlinkTraversal)));
+	}
+
+	// ── normalize: in-memory counterpart of resolve ──
+
+	#[test]
+	fn normalize_basic() {
+		assert_eq!(
+			normalize("/assets/images/logo.png").unwrap(),
+			"assets/images/logo.png"
+		);
+		assert_eq!(normalize("").unwrap(), "");
+		assert_eq!(normalize("/").unwrap(), "");
+		assert_eq!(normalize("/docs/").unwrap(), "docs");
+	}
+
+	#[test]
+	fn normalize_clamps_traversal() {
+		assert_eq!(normalize("/../../etc/passwd").unwrap(), "etc/passwd");
+		assert_eq!(normalize("/%2e%2e/a/./b/../c").unwrap(), "a/c");
+	}
+
+	#[test]
+	fn normalize_decodes() {
+		assert_eq!(normalize("/%E4%B8%AD%E6%96%87.txt").unwrap(), "中文.txt");
+		assert_eq!(normalize("/%252e%252e").unwrap(), "%2e%2e");
+		assert!(matches!(normalize("/a%00"), Err(Error::NullByte)));
+	}
+
+	#[test]
+	fn normalize_strict_rejects() {
+		assert_eq!(normalize_strict("/a/../b").unwrap(), "b");
+		assert!(matches!(
+			normalize_strict("/../b"),
+			Err(Error::StrictViolation(StrictViolation::EscapesRoot))
+		));
+	}
+
+	// ── pluggable filesystem: same rules against an in-memory fake ──
+
+	fn make_memory_fs() -> crate::fs::MemoryFs {
+		let mut fs = crate::fs::MemoryFs::new();
+		fs.insert_file("/srv/assets/images/logo.png", b"png")
+			.insert_file("/srv/index.html", b"<html>")
+			.insert_file("/etc/passwd", b"root")
+			.insert_symlink("/srv/alias.html", "index.html")
+			.insert_symlink("/srv/link.txt", "/etc/passwd")
+			.insert_symlink("/srv/evil", "/etc")
+			.insert_symlink("/srv/loop", "loop");
+		fs
+	}
+
+	#[test]
+	fn memory_fs_normal_path() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/assets/../assets/images/logo.png", false).unwrap();
+		assert_eq!(result, Path::new("/srv/assets/images/logo.png"));
+	}
+
+	#[test]
+	fn memory_fs_traversal_clamped() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/../../etc/passwd", false).unwrap();
+		assert_eq!(result, Path::new("/srv/etc/passwd"));
+	}
+
+	#[test]
+	fn memory_fs_symlink_within_root() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/alias.html", false).unwrap();
+		assert_eq!(result, Path::new("/srv/index.html"));
+	}
+
+	#[test]
+	fn memory_fs_symlink_traversal_blocked() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/link.txt", false);
+		assert!(matches!(result, Err(Error::SymlinkTraversal)));
+		let result = resolve_in(&fs, "/srv", "/evil/nonexistent.txt", false);
+		assert!(matches!(result, Err(Error::SymlinkTraversal)));
+	}
+
+	#[test]
+	fn memory_fs_symlink_allowed_when_flag_set() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/link.txt", true).unwrap();
+		assert_eq!(result, Path::new("/srv/link.txt"));
+	}
+
+	#[test]
+	fn memory_fs_symlink_loop_is_security_io() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/srv", "/loop", false);
+		assert!(matches!(result, Err(Error::SecurityIo(_))));
+	}
+
+	#[test]
+	fn memory_fs_invalid_root() {
+		let fs = make_memory_fs();
+		let result = resolve_in(&fs, "/nope", "/file", false);
+		assert!(matches!(result, Err(Error::InvalidRoot { .. })));
+	}
+
+	#[test]
+	fn memory_fs_strict() {
+		let fs = make_memory_fs();
+		let result = resolve_strict_in(&fs, "/srv", "/../etc/passwd", true);
+		assert!(matches!(
+			result,
+			Err(Error::StrictViolation(StrictViolation::EscapesRoot))
+		));
+	}
 }
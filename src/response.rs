@@ -0,0 +1,737 @@
+/* src/response.rs */
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cache::CacheControl;
+use crate::fs::Metadata;
+use crate::mime::{self, MediaType};
+use crate::range::{self, ByteRange};
+use crate::time::DateTime;
+
+/// The status of a planned response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+	/// 200: the whole file.
+	Ok,
+	/// 206: the requested byte range.
+	PartialContent,
+	/// 304: the client's cached copy is current.
+	NotModified,
+	/// 412: an `If-Match` or `If-Unmodified-Since` precondition failed.
+	PreconditionFailed,
+	/// 416: the requested range lies outside the file.
+	RangeNotSatisfiable,
+}
+
+impl Status {
+	/// The numeric status code.
+	#[must_use]
+	pub const fn code(self) -> u16 {
+		match self {
+			Self::Ok => 200,
+			Self::PartialContent => 206,
+			Self::NotModified => 304,
+			Self::PreconditionFailed => 412,
+			Self::RangeNotSatisfiable => 416,
+		}
+	}
+}
+
+/// What is known about the file being served, gathered without reading it.
+///
+/// By default the content type comes from [`mime::content_type`] on the
+/// path alone, so text types carry no `charset`, and the ETag is the strong
+/// form of [`mime::etag`] when the modification time is known, so that
+/// clients can use it in `If-Match` and `If-Range`. Both can be replaced,
+/// for example with a type sniffed by [`mime::detect_read`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+	size: u64,
+	modified: Option<SystemTime>,
+	content_type: MediaType,
+	etag: Option<String>,
+	cache_control: CacheControl,
+	vary: Option<String>,
+}
+
+impl FileInfo {
+	/// Describes a file of `size` bytes at `path`.
+	#[must_use]
+	pub fn new(path: impl AsRef<Path>, size: u64) -> Self {
+		Self {
+			size,
+			modified: None,
+			content_type: mime::content_type(path, &[]),
+			etag: None,
+			cache_control: CacheControl::new(),
+			vary: None,
+		}
+	}
+
+	/// Describes the file at `path` from its metadata.
+	#[must_use]
+	pub fn from_metadata(path: impl AsRef<Path>, meta: &Metadata) -> Self {
+		Self::new(path, meta.len).modified(meta.modified)
+	}
+
+	/// Sets the modification time, used for `Last-Modified`, the default
+	/// ETag and date preconditions.
+	#[must_use]
+	pub fn modified(mut self, modified: Option<SystemTime>) -> Self {
+		self.modified = modified;
+		self
+	}
+
+	/// Replaces the detected content type.
+	#[must_use]
+	pub fn content_type(mut self, media: MediaType) -> Self {
+		self.content_type = media;
+		self
+	}
+
+	/// Replaces the default ETag. Pass a complete entity tag, such as
+	/// `"abc"` or `W/"abc"`.
+	#[must_use]
+	pub fn etag(mut self, etag: impl Into<String>) -> Self {
+		self.etag = Some(etag.into());
+		self
+	}
+
+	/// Sets the `Cache-Control` directives, for example from a
+	/// [`crate::cache::CachePolicy`]. Empty directives send no header.
+	#[must_use]
+	pub fn cache_control(mut self, directives: CacheControl) -> Self {
+		self.cache_control = directives;
+		self
+	}
+
+	/// Sets the `Vary` header, for when the representation was chosen by
+	/// request headers such as `Accept-Encoding`.
+	#[must_use]
+	pub fn vary(mut self, vary: impl Into<String>) -> Self {
+		self.vary = Some(vary.into());
+		self
+	}
+
+	/// The ETag, by default [`mime::etag`] without its weak marker: the
+	/// nanosecond modification time and size stand in for the content.
+	fn entity_tag(&self) -> Option<String> {
+		self.etag.clone().or_else(|| {
+			self.modified.map(|modified| {
+				let tag = mime::etag(modified, self.size);
+				tag
+					.strip_prefix("W/")
+					.map_or_else(|| tag.clone(), str::to_owned)
+			})
+		})
+	}
+
+	/// The modification time truncated to whole seconds, as sent in
+	/// `Last-Modified` and compared with request dates.
+	fn modified_secs(&self) -> Option<u64> {
+		self
+			.modified
+			.map(|time| time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+	}
+}
+
+/// The status, headers and byte region of a response to a static file
+/// request, following RFC 9110 conditional and range request rules.
+///
+/// Planning performs no I/O: the caller sends the headers and then, when
+/// [`ResponsePlan::body`] is set and the method is not `HEAD`, those bytes
+/// of the file.
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use serve_static::response::{FileInfo, ResponsePlan, Status};
+///
+/// let file = FileInfo::new("notes.txt", 1000)
+///     .modified(Some(UNIX_EPOCH + Duration::from_secs(1_710_505_810)));
+///
+/// let plan = ResponsePlan::new("GET", [("Range", "bytes=0-99")], &file).unwrap();
+/// assert_eq!(plan.status, Status::PartialContent);
+/// assert_eq!(plan.header("content-range"), Some("bytes 0-99/1000"));
+/// assert_eq!(plan.body.unwrap().length, 100);
+///
+/// let plan = ResponsePlan::new(
+///     "GET",
+///     [("If-Modified-Since", "Fri, 15 Mar 2024 12:30:10 GMT")],
+///     &file,
+/// )
+/// .unwrap();
+/// assert_eq!(plan.status, Status::NotModified);
+/// assert_eq!(plan.body, None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponsePlan {
+	/// The response status.
+	pub status: Status,
+	/// Response headers in a conventional order.
+	pub headers: Vec<(&'static str, String)>,
+	/// The bytes of the file to send, or `None` when the response has no
+	/// body. Also set for `HEAD`, where it describes the body a `GET`
+	/// would carry.
+	pub body: Option<ByteRange>,
+}
+
+/// The request headers that affect planning.
+#[derive(Debug, Default)]
+struct Conditions {
+	if_match: Option<String>,
+	if_none_match: Option<String>,
+	if_modified_since: Option<String>,
+	if_unmodified_since: Option<String>,
+	range: Option<String>,
+	if_range: Option<String>,
+}
+
+impl Conditions {
+	fn from_headers<K: AsRef<str>, V: AsRef<str>>(headers: impl IntoIterator<Item = (K, V)>) -> Self {
+		let mut conditions = Self::default();
+		for (name, value) in headers {
+			let slot = match name.as_ref().to_ascii_lowercase().as_str() {
+				"if-match" => &mut conditions.if_match,
+				"if-none-match" => &mut conditions.if_none_match,
+				"if-modified-since" => &mut conditions.if_modified_since,
+				"if-unmodified-since" => &mut conditions.if_unmodified_since,
+				"range" => &mut conditions.range,
+				"if-range" => &mut conditions.if_range,
+				_ => continue,
+			};
+			let value = value.as_ref().trim();
+			// Repeated list headers combine; a repeated date keeps the first.
+			match slot {
+				Some(existing)
+					if name.as_ref().eq_ignore_ascii_case("if-none-match")
+						|| name.as_ref().eq_ignore_ascii_case("if-match") =>
+				{
+					existing.push_str(", ");
+					existing.push_str(value);
+				}
+				Some(_) => {}
+				None => *slot = Some(value.to_owned()),
+			}
+		}
+		conditions
+	}
+}
+
+impl ResponsePlan {
+	/// Plans the response to a `method` request with `headers` for `file`.
+	///
+	/// Header names match case-insensitively. Preconditions are evaluated
+	/// in the RFC 9110 order: `If-Match`, `If-Unmodified-Since`,
+	/// `If-None-Match`, `If-Modified-Since`, then `Range` with `If-Range`.
+	/// Ranges apply to `GET` only; malformed and multi-range requests are
+	/// answered with the whole file.
+	///
+	/// Returns `None` for methods other than `GET` and `HEAD`, leaving the
+	/// answer to the caller.
+	#[must_use]
+	pub fn new<K: AsRef<str>, V: AsRef<str>>(
+		method: &str,
+		headers: impl IntoIterator<Item = (K, V)>,
+		file: &FileInfo,
+	) -> Option<Self> {
+		if method != "GET" && method != "HEAD" {
+			return None;
+		}
+		let conditions = Conditions::from_headers(headers);
+		Some(Self::plan(method == "GET", &conditions, file))
+	}
+
+	fn plan(get: bool, conditions: &Conditions, file: &FileInfo) -> Self {
+		let etag = file.entity_tag();
+		let modified = file.modified_secs();
+
+		let precondition_failed = match (&conditions.if_match, &conditions.if_unmodified_since) {
+			(Some(list), _) => !matches_any(list, etag.as_deref(), true),
+			(None, Some(date)) => match (parse_date(date), modified) {
+				(Some(since), Some(modified)) => modified > since,
+				_ => false,
+			},
+			(None, None) => false,
+		};
+		if precondition_failed {
+			return Self {
+				status: Status::PreconditionFailed,
+				headers: Vec::new(),
+				body: None,
+			};
+		}
+
+		let not_modified = match (&conditions.if_none_match, &conditions.if_modified_since) {
+			(Some(list), _) => matches_any(list, etag.as_deref(), false),
+			(None, Some(date)) => match (parse_date(date), modified) {
+				(Some(since), Some(modified)) => modified <= since,
+				_ => false,
+			},
+			(None, None) => false,
+		};
+		if not_modified {
+			let mut plan = Self {
+				status: Status::NotModified,
+				headers: Vec::new(),
+				body: None,
+			};
+			plan.push_validators(file, etag);
+			plan.push_caching(file);
+			return plan;
+		}
+
+		let range =
+			conditions
+				.range
+				.as_deref()
+				.filter(|_| get)
+				.filter(|_| match &conditions.if_range {
+					Some(validator) => if_range_matches(validator, etag.as_deref(), modified),
+					None => true,
+				});
+		let (status, body) = match range {
+			Some(header) => match range::parse(header, file.size) {
+				Some(range) => (Status::PartialContent, range),
+				None if is_single_range(header) => {
+					return Self {
+						status: Status::RangeNotSatisfiable,
+						headers: vec![
+							("Content-Range", format!("bytes */{}", file.size)),
+							("Accept-Ranges", "bytes".to_owned()),
+						],
+						body: None,
+					};
+				}
+				None => (Status::Ok, whole(file.size)),
+			},
+			None => (Status::Ok, whole(file.size)),
+		};
+
+		let mut plan = Self {
+			status,
+			headers: vec![
+				("Content-Type", file.content_type.to_string()),
+				("Content-Length", body.length.to_string()),
+			],
+			body: Some(body),
+		};
+		if status == Status::PartialContent {
+			let end = body.start + body.length - 1;
+			let value = format!("bytes {}-{end}/{}", body.start, file.size);
+			plan.headers.push(("Content-Range", value));
+		}
+		plan.headers.push(("Accept-Ranges", "bytes".to_owned()));
+		plan.push_validators(file, etag);
+		plan.push_caching(file);
+		plan
+	}
+
+	/// The value of a planned header, matching the name case-insensitively.
+	#[must_use]
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self
+			.headers
+			.iter()
+			.find(|(key, _)| key.eq_ignore_ascii_case(name))
+			.map(|(_, value)| value.as_str())
+	}
+
+	fn push_validators(&mut self, file: &FileInfo, etag: Option<String>) {
+		if let Some(etag) = etag {
+			self.headers.push(("ETag", etag));
+		}
+		if let Some(modified) = file.modified {
+			let date = DateTime::from_system_time(modified).http_date();
+			self.headers.push(("Last-Modified", date));
+		}
+	}
+
+	fn push_caching(&mut self, file: &FileInfo) {
+		if !file.cache_control.is_empty() {
+			self
+				.headers
+				.push(("Cache-Control", file.cache_control.to_string()));
+		}
+		if let Some(vary) = &file.vary {
+			self.headers.push(("Vary", vary.clone()));
+		}
+	}
+}
+
+fn whole(size: u64) -> ByteRange {
+	ByteRange {
+		start: 0,
+		length: size,
+	}
+}
+
+/// An HTTP-date as whole seconds since the epoch.
+fn parse_date(value: &str) -> Option<u64> {
+	let time = DateTime::parse_http_date(value)?.to_system_time()?;
+	time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Whether an `If-Match` or `If-None-Match` list matches `etag`, using the
+/// strong or weak comparison of RFC 9110 section 8.8.3.2.
+/// `*` matches any current representation, even one without an ETag.
+fn matches_any(list: &str, etag: Option<&str>, strong: bool) -> bool {
+	if list.trim() == "*" {
+		return true;
+	}
+	let Some(etag) = etag else {
+		return false;
+	};
+	list
+		.split(',')
+		.any(|candidate| etag_eq(candidate.trim(), etag, strong))
+}
+
+fn etag_eq(a: &str, b: &str, strong: bool) -> bool {
+	let weak_a = a.starts_with("W/");
+	let weak_b = b.starts_with("W/");
+	if strong && (weak_a || weak_b) {
+		return false;
+	}
+	a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// Whether an `If-Range` validator still matches: a strong entity tag, or
+/// a date exactly equal to the modification time.
+fn if_range_matches(validator: &str, etag: Option<&str>, modified: Option<u64>) -> bool {
+	if validator.starts_with('"') || validator.starts_with("W/") {
+		return etag.is_some_and(|etag| etag_eq(validator, etag, true));
+	}
+	parse_date(validator).is_some_and(|date| Some(date) == modified)
+}
+
+/// Whether a `Range` value is a syntactically valid single byte range, so
+/// that failing to satisfy it means 416 rather than ignoring it.
+fn is_single_range(header: &str) -> bool {
+	let Some(spec) = header.strip_prefix("bytes=") else {
+		return false;
+	};
+	let Some((first, last)) = spec.split_once('-') else {
+		return false;
+	};
+	let (first, last) = (first.trim(), last.trim());
+	let number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+	match (first.is_empty(), last.is_empty()) {
+		(true, true) => false,
+		(true, false) => number(last),
+		(false, true) => number(first),
+		(false, false) => {
+			number(first) && number(last) && first.parse::<u64>().ok() <= last.parse::<u64>().ok()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	const NO_HEADERS: [(&str, &str); 0] = [];
+	const DATE: &str = "Fri, 15 Mar 2024 12:30:10 GMT";
+
+	fn file() -> FileInfo {
+		FileInfo::new("notes.txt", 1000)
+			.content_type("text/plain; charset=utf-8".parse().unwrap())
+			.modified(Some(UNIX_EPOCH + Duration::from_millis(1_710_505_810_500)))
+			.etag("\"v1\"")
+	}
+
+	fn plan(method: &str, headers: &[(&str, &str)]) -> ResponsePlan {
+		ResponsePlan::new(method, headers.iter().copied(), &file()).unwrap()
+	}
+
+	#[test]
+	fn full_response_headers() {
+		let file = file()
+			.cache_control(CacheControl::no_cache())
+			.vary("Accept-Encoding");
+		let plan = ResponsePlan::new("GET", NO_HEADERS, &file).unwrap();
+		assert_eq!(plan.status, Status::Ok);
+		assert_eq!(plan.status.code(), 200);
+		assert_eq!(
+			plan.body,
+			Some(ByteRange {
+				start: 0,
+				length: 1000
+			})
+		);
+		let names: Vec<_> = plan.headers.iter().map(|(name, _)| *name).collect();
+		assert_eq!(
+			names,
+			[
+				"Content-Type",
+				"Content-Length",
+				"Accept-Ranges",
+				"ETag",
+				"Last-Modified",
+				"Cache-Control",
+				"Vary"
+			]
+		);
+		assert_eq!(
+			plan.header("content-type"),
+			Some("text/plain; charset=utf-8")
+		);
+		assert_eq!(plan.header("Content-Length"), Some("1000"));
+		assert_eq!(plan.header("Last-Modified"), Some(DATE));
+		assert_eq!(plan.header("Cache-Control"), Some("no-cache"));
+	}
+
+	#[test]
+	fn head_matches_get_without_ranges() {
+		let plan = plan("HEAD", &[("Range", "bytes=0-9")]);
+		assert_eq!(plan.status, Status::Ok);
+		assert_eq!(plan.header("Content-Length"), Some("1000"));
+	}
+
+	#[test]
+	fn other_methods() {
+		for method in ["POST", "PUT", "get", "OPTIONS"] {
+			assert_eq!(
+				ResponsePlan::new(method, NO_HEADERS, &file()),
+				None,
+				"{method}"
+			);
+		}
+	}
+
+	#[test]
+	fn default_etag_and_type() {
+		let modified = UNIX_EPOCH + Duration::from_secs(100);
+		let file = FileInfo::new("logo.bin", 500).modified(Some(modified));
+		let plan = ResponsePlan::new("GET", NO_HEADERS, &file).unwrap();
+		let etag = mime::etag(modified, 500);
+		let strong = etag.strip_prefix("W/").unwrap();
+		assert_eq!(plan.header("ETag"), Some(strong));
+		assert_eq!(
+			plan.header("Content-Type"),
+			Some("application/octet-stream")
+		);
+
+		let bare = ResponsePlan::new("GET", NO_HEADERS, &FileInfo::new("x", 0)).unwrap();
+		assert_eq!(bare.header("ETag"), None);
+		assert_eq!(bare.header("Last-Modified"), None);
+		assert_eq!(
+			bare.body,
+			Some(ByteRange {
+				start: 0,
+				length: 0
+			})
+		);
+	}
+
+	#[test]
+	fn ranges() {
+		let plan = plan("GET", &[("range", "bytes=-100")]);
+		assert_eq!(plan.status.code(), 206);
+		assert_eq!(
+			plan.body,
+			Some(ByteRange {
+				start: 900,
+				length: 100
+			})
+		);
+		assert_eq!(plan.header("Content-Range"), Some("bytes 900-999/1000"));
+		assert_eq!(plan.header("Content-Length"), Some("100"));
+
+		for unsatisfiable in ["bytes=1000-", "bytes=5000-6000", "bytes=-0"] {
+			let plan = self::plan("GET", &[("Range", unsatisfiable)]);
+			assert_eq!(plan.status.code(), 416, "{unsatisfiable}");
+			assert_eq!(plan.header("Content-Range"), Some("bytes */1000"));
+			assert_eq!(plan.body, None);
+		}
+		for ignored in [
+			"bytes=0-1,5-6",
+			"items=0-1",
+			"bytes=9-3",
+			"bytes=a-b",
+			"bytes=-",
+		] {
+			let plan = self::plan("GET", &[("Range", ignored)]);
+			assert_eq!(plan.status, Status::Ok, "{ignored}");
+		}
+	}
+
+	#[test]
+	fn if_range() {
+		let range = ("Range", "bytes=0-9");
+		assert_eq!(
+			plan("GET", &[range, ("If-Range", "\"v1\"")]).status,
+			Status::PartialContent
+		);
+		assert_eq!(
+			plan("GET", &[range, ("If-Range", "\"v0\"")]).status,
+			Status::Ok
+		);
+		assert_eq!(
+			plan("GET", &[range, ("If-Range", "W/\"v1\"")]).status,
+			Status::Ok
+		);
+		assert_eq!(
+			plan("GET", &[range, ("If-Range", DATE)]).status,
+			Status::PartialContent
+		);
+		assert_eq!(
+			plan(
+				"GET",
+				&[range, ("If-Range", "Fri, 15 Mar 2024 12:30:09 GMT")]
+			)
+			.status,
+			Status::Ok
+		);
+	}
+
+	#[test]
+	fn not_modified() {
+		for headers in [
+			&[("If-None-Match", "\"v1\"")][..],
+			&[("If-None-Match", "\"v0\", W/\"v1\"")],
+			&[("If-None-Match", "*")],
+			&[("If-Modified-Since", DATE)],
+			&[("If-Modified-Since", "Sat, 16 Mar 2024 00:00:00 GMT")],
+		] {
+			let plan = plan("GET", headers);
+			assert_eq!(plan.status.code(), 304, "{headers:?}");
+			assert_eq!(plan.body, None);
+			assert_eq!(plan.header("ETag"), Some("\"v1\""));
+			assert_eq!(plan.header("Content-Length"), None);
+		}
+		// If-None-Match takes precedence over If-Modified-Since.
+		let plan = plan(
+			"GET",
+			&[("If-None-Match", "\"v0\""), ("If-Modified-Since", DATE)],
+		);
+		assert_eq!(plan.status, Status::Ok);
+		// An earlier date or an invalid one means modified.
+		assert_eq!(
+			self::plan(
+				"GET",
+				&[("If-Modified-Since", "Fri, 15 Mar 2024 12:30:09 GMT")]
+			)
+			.status,
+			Status::Ok
+		);
+		assert_eq!(
+			self::plan("GET", &[("If-Modified-Since", "garbage")]).status,
+			Status::Ok
+		);
+		// Repeated headers combine.
+		let plan = self::plan(
+			"HEAD",
+			&[("If-None-Match", "\"a\""), ("if-none-match", "\"v1\"")],
+		);
+		assert_eq!(plan.status, Status::NotModified);
+	}
+
+	#[test]
+	fn preconditions() {
+		assert_eq!(plan("GET", &[("If-Match", "\"v1\"")]).status, Status::Ok);
+		assert_eq!(plan("GET", &[("If-Match", "*")]).status, Status::Ok);
+		for headers in [
+			&[("If-Match", "\"v0\"")][..],
+			&[("If-Match", "W/\"v1\"")],
+			&[("If-Unmodified-Since", "Fri, 15 Mar 2024 12:30:09 GMT")],
+			// A failed If-Match wins over a matching If-None-Match.
+			&[("If-Match", "\"v0\""), ("If-None-Match", "\"v1\"")],
+		] {
+			let plan = plan("GET", headers);
+			assert_eq!(plan.status.code(), 412, "{headers:?}");
+			assert_eq!(plan.body, None);
+		}
+		// If-Match takes precedence over If-Unmodified-Since.
+		let plan = plan(
+			"GET",
+			&[
+				("If-Match", "\"v1\""),
+				("If-Unmodified-Since", "Thu, 01 Jan 1970 00:00:00 GMT"),
+			],
+		);
+		assert_eq!(plan.status, Status::Ok);
+		assert_eq!(
+			self::plan("GET", &[("If-Unmodified-Since", DATE)]).status,
+			Status::Ok
+		);
+	}
+
+	#[test]
+	fn default_etag_preconditions() {
+		let file = FileInfo::new("a.txt", 10).modified(Some(UNIX_EPOCH + Duration::from_secs(100)));
+		let etag = ResponsePlan::new("GET", NO_HEADERS, &file)
+			.unwrap()
+			.header("ETag")
+			.unwrap()
+			.to_owned();
+		let status = |headers: &[(&str, &str)]| {
+			ResponsePlan::new("GET", headers.iter().copied(), &file)
+				.unwrap()
+				.status
+		};
+		assert_eq!(status(&[("If-Match", &etag)]), Status::Ok);
+		assert_eq!(status(&[("If-None-Match", &etag)]), Status::NotModified);
+		assert_eq!(
+			status(&[("Range", "bytes=0-4"), ("If-Range", &etag)]),
+			Status::PartialContent
+		);
+	}
+
+	#[test]
+	fn wildcard_without_validators() {
+		let file = FileInfo::new("a.txt", 10);
+		let status = |headers: &[(&str, &str)]| {
+			ResponsePlan::new("GET", headers.iter().copied(), &file)
+				.unwrap()
+				.status
+		};
+		assert_eq!(status(&[("If-Match", "*")]), Status::Ok);
+		assert_eq!(status(&[("If-None-Match", "*")]), Status::NotModified);
+		assert_eq!(
+			status(&[("If-Match", "\"v1\"")]),
+			Status::PreconditionFailed
+		);
+		assert_eq!(status(&[("If-None-Match", "\"v1\"")]), Status::Ok);
+	}
+
+	#[test]
+	fn unparseable_dates_are_ignored() {
+		for date in [
+			"Sun, 06 Nov 300000000000 08:49:37 GMT",
+			"Sun, 06 Nov 9223372036854775807 08:49:37 GMT",
+			"Sat, 31 Feb 2024 08:49:37 GMT",
+		] {
+			assert_eq!(
+				plan("GET", &[("If-Modified-Since", date)]).status,
+				Status::Ok
+			);
+			assert_eq!(
+				plan("GET", &[("If-Unmodified-Since", date)]).status,
+				Status::Ok
+			);
+			// An If-Range that does not match means the whole file.
+			let range = [("Range", "bytes=0-9"), ("If-Range", date)];
+			assert_eq!(plan("GET", &range).status, Status::Ok);
+		}
+	}
+
+	#[test]
+	fn from_metadata() {
+		let meta = Metadata {
+			file_type: crate::fs::FileType::File,
+			len: 42,
+			modified: Some(UNIX_EPOCH),
+			created: None,
+			readonly: false,
+		};
+		let file = FileInfo::from_metadata("index.html", &meta);
+		let plan = ResponsePlan::new("GET", NO_HEADERS, &file).unwrap();
+		assert_eq!(plan.header("Content-Length"), Some("42"));
+		assert_eq!(
+			plan.header("Last-Modified"),
+			Some("Thu, 01 Jan 1970 00:00:00 GMT")
+		);
+	}
+}
@@ -0,0 +1,313 @@
+/* src/time.rs */
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A UTC calendar date and time, split into fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DateTime {
+	pub(crate) year: i64,
+	pub(crate) month: u32,
+	pub(crate) day: u32,
+	pub(crate) hour: u32,
+	pub(crate) minute: u32,
+	pub(crate) second: u32,
+}
+
+impl DateTime {
+	/// Splits a [`SystemTime`] into UTC fields. Times before the epoch are
+	/// clamped to it, matching [`crate::mime::etag`].
+	pub(crate) fn from_system_time(time: SystemTime) -> Self {
+		let secs = time
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+		let days = i64::try_from(secs / 86_400).unwrap_or(i64::MAX);
+		let rem = secs % 86_400;
+		let (year, month, day) = civil_from_days(days);
+		Self {
+			year,
+			month,
+			day,
+			hour: (rem / 3600) as u32,
+			minute: (rem % 3600 / 60) as u32,
+			second: (rem % 60) as u32,
+		}
+	}
+
+	/// Formats as an RFC 3339 UTC timestamp, e.g. `2024-03-15T12:30:10Z`.
+	pub(crate) fn rfc3339(self) -> String {
+		format!(
+			"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+			self.year, self.month, self.day, self.hour, self.minute, self.second
+		)
+	}
+
+	/// Formats as an RFC 9110 IMF-fixdate, e.g. `Fri, 15 Mar 2024 12:30:10 GMT`.
+	pub(crate) fn http_date(self) -> String {
+		// 1970-01-01 was a Thursday.
+		let weekday = (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7);
+		format!(
+			"{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+			WEEKDAYS[weekday as usize],
+			self.day,
+			MONTHS[self.month as usize - 1],
+			self.year,
+			self.hour,
+			self.minute,
+			self.second
+		)
+	}
+
+	/// Parses an HTTP-date in any of the three RFC 9110 forms: IMF-fixdate,
+	/// obsolete RFC 850 and asctime. The weekday is not checked; the year
+	/// must have four digits and the day must exist in its month.
+	pub(crate) fn parse_http_date(value: &str) -> Option<Self> {
+		let words: Vec<&str> = value.split_whitespace().collect();
+		let (day, month, year, time) = match words.as_slice() {
+			// Sun, 06 Nov 1994 08:49:37 GMT, or asctime: Sun Nov  6 08:49:37 1994
+			[_, day, month, year, time, "GMT"] | [_, month, day, time, year] => {
+				if year.len() != 4 || !year.bytes().all(|b| b.is_ascii_digit()) {
+					return None;
+				}
+				(*day, *month, year.parse().ok()?, *time)
+			}
+			// Sunday, 06-Nov-94 08:49:37 GMT
+			[_, date, time, "GMT"] => {
+				let mut parts = date.split('-');
+				let (day, month, year) = (parts.next()?, parts.next()?, parts.next()?);
+				if year.len() != 2 || parts.next().is_some() {
+					return None;
+				}
+				let year: i64 = year.parse().ok()?;
+				(
+					day,
+					month,
+					if year < 70 { 2000 + year } else { 1900 + year },
+					*time,
+				)
+			}
+			_ => return None,
+		};
+		if day.is_empty() || day.len() > 2 || time.len() != 8 {
+			return None;
+		}
+		let mut clock = time.split(':').map(|part| {
+			if part.len() == 2 {
+				part.parse().ok()
+			} else {
+				None
+			}
+		});
+		let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+		let dt = Self {
+			year,
+			month: MONTHS.iter().position(|m| *m == month)? as u32 + 1,
+			day: day.parse().ok()?,
+			hour,
+			minute,
+			second,
+		};
+		dt.to_system_time().map(|_| dt)
+	}
+
+	/// Joins the fields back into a [`SystemTime`]. Returns `None` for
+	/// out-of-range fields, days missing from their month, and dates before
+	/// the epoch or after year 9999.
+	pub(crate) fn to_system_time(self) -> Option<SystemTime> {
+		if !(1970..=9999).contains(&self.year)
+			|| !(1..=12).contains(&self.month)
+			|| !(1..=days_in_month(self.year, self.month)).contains(&self.day)
+			|| self.hour > 23
+			|| self.minute > 59
+			|| self.second > 60
+		{
+			return None;
+		}
+		let days = u64::try_from(days_from_civil(self.year, self.month, self.day)).ok()?;
+		let secs = u64::from(self.hour) * 3600 + u64::from(self.minute) * 60 + u64::from(self.second);
+		let total = days.checked_mul(86_400)?.checked_add(secs)?;
+		UNIX_EPOCH.checked_add(Duration::from_secs(total))
+	}
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTHS: [&str; 12] = [
+	"Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The number of days in `month` (1-based) of `year`.
+fn days_in_month(year: i64, month: u32) -> u32 {
+	match month {
+		2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+		2 => 28,
+		4 | 6 | 9 | 11 => 30,
+		_ => 31,
+	}
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+///
+/// Howard Hinnant's `days_from_civil` algorithm.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+	let y = if month <= 2 { year - 1 } else { year };
+	let era = y.div_euclid(400);
+	let yoe = y.rem_euclid(400);
+	let m = i64::from(month);
+	let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(day) - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	era * 146_097 + doe - 719_468
+}
+
+/// The proleptic Gregorian date for a number of days since 1970-01-01.
+///
+/// Howard Hinnant's `civil_from_days` algorithm.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let doe = z.rem_euclid(146_097);
+	let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+	let year = yoe + era * 400 + i64::from(month <= 2);
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(secs: u64) -> SystemTime {
+		UNIX_EPOCH + Duration::from_secs(secs)
+	}
+
+	#[test]
+	fn epoch() {
+		let dt = DateTime::from_system_time(UNIX_EPOCH);
+		assert_eq!((dt.year, dt.month, dt.day, dt.hour), (1970, 1, 1, 0));
+		assert_eq!(dt.to_system_time(), Some(UNIX_EPOCH));
+	}
+
+	#[test]
+	fn known_dates() {
+		// 2000-03-01T00:00:00Z, just after a 400-year leap day.
+		let dt = DateTime::from_system_time(at(951_868_800));
+		assert_eq!((dt.year, dt.month, dt.day), (2000, 3, 1));
+		// 2024-03-15T12:30:10Z
+		let dt = DateTime::from_system_time(at(1_710_505_810));
+		assert_eq!(
+			(dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second),
+			(2024, 3, 15, 12, 30, 10)
+		);
+	}
+
+	#[test]
+	fn rfc3339() {
+		let dt = DateTime::from_system_time(at(1_710_505_810));
+		assert_eq!(dt.rfc3339(), "2024-03-15T12:30:10Z");
+		assert_eq!(
+			DateTime::from_system_time(UNIX_EPOCH).rfc3339(),
+			"1970-01-01T00:00:00Z"
+		);
+	}
+
+	#[test]
+	fn http_date() {
+		assert_eq!(
+			DateTime::from_system_time(at(1_710_505_810)).http_date(),
+			"Fri, 15 Mar 2024 12:30:10 GMT"
+		);
+		assert_eq!(
+			DateTime::from_system_time(UNIX_EPOCH).http_date(),
+			"Thu, 01 Jan 1970 00:00:00 GMT"
+		);
+	}
+
+	#[test]
+	fn parse_http_date_forms() {
+		let expected = at(784_111_777);
+		for value in [
+			"Sun, 06 Nov 1994 08:49:37 GMT",
+			"Sunday, 06-Nov-94 08:49:37 GMT",
+			"Sun Nov  6 08:49:37 1994",
+		] {
+			let dt = DateTime::parse_http_date(value).unwrap();
+			assert_eq!(dt.to_system_time(), Some(expected), "{value}");
+		}
+		let dt = DateTime::parse_http_date("Wed, 01-Jan-25 00:00:00 GMT").unwrap();
+		assert_eq!(dt.year, 2025);
+		let dt = DateTime::from_system_time(at(1_710_505_810));
+		assert_eq!(DateTime::parse_http_date(&dt.http_date()), Some(dt));
+	}
+
+	#[test]
+	fn parse_http_date_rejects() {
+		for value in [
+			"",
+			"yesterday",
+			"Sun, 06 Nov 1994 08:49:37 UTC",
+			"Sun, 06 Foo 1994 08:49:37 GMT",
+			"Sun, 32 Nov 1994 08:49:37 GMT",
+			"Sun, 06 Nov 1994 24:49:37 GMT",
+			"Sun, 06 Nov 1994 8:49:37 GMT",
+			"Sunday, 06-Nov-1994 08:49:37 GMT",
+			"Sun, 06 Nov 1969 08:49:37 GMT",
+			"Sun, 06 Nov 300000000000 08:49:37 GMT",
+			"Sun, 06 Nov 9223372036854775807 08:49:37 GMT",
+			"Sun Nov  6 08:49:37 300000000000",
+			"Sun, 06 Nov 01994 08:49:37 GMT",
+			"Sun, 06 Nov +994 08:49:37 GMT",
+			"Sat, 31 Feb 2024 08:49:37 GMT",
+			"Thu, 29 Feb 2023 08:49:37 GMT",
+			"Sun, 31 Nov 1994 08:49:37 GMT",
+		] {
+			assert_eq!(DateTime::parse_http_date(value), None, "{value}");
+		}
+	}
+
+	#[test]
+	fn round_trip() {
+		for secs in [
+			0,
+			59,
+			86_399,
+			86_400,
+			951_782_400,
+			1_709_164_800,
+			4_102_444_800,
+		] {
+			let dt = DateTime::from_system_time(at(secs));
+			assert_eq!(dt.to_system_time(), Some(at(secs)), "{secs}");
+		}
+	}
+
+	#[test]
+	fn pre_epoch_clamped() {
+		let dt = DateTime::from_system_time(UNIX_EPOCH - Duration::from_secs(10));
+		assert_eq!(dt.year, 1970);
+	}
+
+	#[test]
+	fn invalid_fields() {
+		let mut dt = DateTime::from_system_time(UNIX_EPOCH);
+		dt.month = 13;
+		assert_eq!(dt.to_system_time(), None);
+		let mut dt = DateTime::from_system_time(UNIX_EPOCH);
+		dt.year = 1969;
+		dt.month = 12;
+		dt.day = 31;
+		assert_eq!(dt.to_system_time(), None);
+		let mut dt = DateTime::from_system_time(UNIX_EPOCH);
+		dt.year = i64::MAX;
+		assert_eq!(dt.to_system_time(), None);
+		let mut dt = DateTime::from_system_time(UNIX_EPOCH);
+		dt.year = 2024;
+		dt.month = 2;
+		dt.day = 29;
+		assert!(dt.to_system_time().is_some());
+		dt.day = 30;
+		assert_eq!(dt.to_system_time(), None);
+	}
+}